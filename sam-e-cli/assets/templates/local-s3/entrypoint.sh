@@ -10,7 +10,7 @@ minio server /buckets & \
     {%- if infra.infrastructure_type == "S3" %}
   mc mb local/{{infra.Properties.Name}}; \
   mc anonymous set public local/{{infra.Properties.Name}}; \
-  mc event add local/{{infra.Properties.Name}} arn:minio:sqs::LOCAL:webhook --event put,get,delete; \
+  mc event add local/{{infra.Properties.Name}} arn:minio:sqs::LOCAL:webhook --event put,delete; \
     {%- endif %}
   {%- endfor %}
 
@@ -30,6 +30,8 @@ pub enum Command {
     Template(TemplateCommand),
     #[clap(subcommand)]
     Frontend(FrontendCommand),
+    #[clap(subcommand)]
+    Queue(QueueCommand),
 }
 
 #[derive(Debug, Args)]
@@ -66,6 +68,14 @@ pub enum FrontendCommand {
     Stop,
 }
 
+#[derive(Debug, Subcommand)]
+pub enum QueueCommand {
+    #[command(
+        about = "Move the messages in a queue's dead-letter queue back to the queue. The environment must be running."
+    )]
+    Redrive,
+}
+
 #[derive(Debug, Subcommand)]
 pub enum FunctionCommand {
     #[command(about = "Add a new function from the template list to the local environment")]
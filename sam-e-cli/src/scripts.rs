@@ -1,12 +1,14 @@
 pub mod environment;
 pub mod frontend;
 pub mod function;
+pub mod queue;
 pub mod template;
 pub mod utils;
 
 pub use environment::get_environment_script;
 pub use frontend::get_frontend_script;
 pub use function::get_function_script;
+pub use queue::get_queue_script;
 use template::get_template_script;
 
 use crate::data::cli::Command;
@@ -20,5 +22,6 @@ pub async fn get_command_script(command: Command) -> anyhow::Result<()> {
         Command::Environment(subcommand) => get_environment_script(subcommand).await,
         Command::Template(subcommand) => get_template_script(subcommand),
         Command::Frontend(subcommand) => get_frontend_script(subcommand),
+        Command::Queue(subcommand) => get_queue_script(subcommand).await,
     }
 }
@@ -1,12 +1,16 @@
+pub mod certificate;
 pub mod infrastructure;
 pub mod lambda;
 pub mod template;
 
 use crate::scripts::{
     environment::build::{
-        infrastructure::{create_infrastructure_files, get_infrastructure_from_resources},
+        infrastructure::{
+            check_api_endpoints, create_infrastructure_files, get_infrastructure_from_resources,
+            keep_api_endpoints, keep_api_key_values,
+        },
         lambda::{
-            add_build_settings, get_lambdas_from_resources, select_lambdas,
+            add_build_settings, check_api_routes, get_lambdas_from_resources, select_lambdas,
             specify_environment_vars,
         },
         template::parse_templates_into_resources,
@@ -16,7 +20,7 @@ use crate::scripts::{
 
 use sam_e_types::{
     cloudformation::Resource,
-    config::{runtime::RuntimeBuilder, Lambda},
+    config::{infrastructure::Infrastructure, runtime::RuntimeBuilder, Lambda},
 };
 
 use serde::Deserialize;
@@ -89,7 +93,10 @@ pub fn build() -> anyhow::Result<()> {
     let lambdas_with_builds = add_build_settings(lambdas_with_env_vars);
 
     // Extracts the infrastructure ready to be added to the config
-    let infrastructure = get_infrastructure_from_resources(&resources)?;
+    let mut infrastructure = get_infrastructure_from_resources(&resources)?;
+    keep_api_key_values(&mut infrastructure, config.get_infrastructure());
+    keep_api_endpoints(&mut infrastructure, config.get_infrastructure());
+    check_api_endpoints(&infrastructure)?;
     debug!("Infrastructure: {:#?}", infrastructure);
     config.set_infrastructure(infrastructure);
 
@@ -98,11 +105,20 @@ pub fn build() -> anyhow::Result<()> {
         .chain(lambdas_with_builds)
         .collect();
 
-    let use_api_source = &combined_lambdas.iter().any(|l| {
+    check_api_routes(&combined_lambdas, config.get_infrastructure())?;
+
+    // WebSocket APIs and function URLs are also served by the API source but aren't linked to
+    // lambdas by events
+    let use_api_source = &(combined_lambdas.iter().any(|l| {
         l.get_events()
             .iter()
             .any(|e| e.get_api_properties().is_some())
-    });
+    }) || config.get_infrastructure().iter().any(|i| {
+        matches!(
+            i,
+            Infrastructure::WebSocketApi(_) | Infrastructure::FunctionUrl(_)
+        )
+    }));
     let use_queue_source = &combined_lambdas.iter().any(|l| {
         l.get_events()
             .iter()
@@ -125,6 +141,13 @@ pub fn build() -> anyhow::Result<()> {
         .with_use_api_source(*use_api_source)
         .with_use_queue_source(*use_queue_source)
         .with_use_s3_source(false)
+        .with_account_id(runtime_clone.get_account_id_setting().cloned())
+        .with_region(runtime_clone.get_region_setting().cloned())
+        .with_api_id(runtime_clone.get_api_id_setting().cloned())
+        .with_use_https(runtime_clone.get_use_https())
+        .with_certificate_location(runtime_clone.get_certificate_location().cloned())
+        .with_certificate_key_location(runtime_clone.get_certificate_key_location().cloned())
+        .with_use_s3_store(runtime_clone.get_use_s3_store())
         .build();
 
     config.set_runtime(new_runtime);
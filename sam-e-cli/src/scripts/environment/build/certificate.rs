@@ -0,0 +1,85 @@
+use sam_e_types::config::runtime::Runtime;
+
+use anyhow::Error;
+use rcgen::{generate_simple_self_signed, CertifiedKey};
+use std::{fs, path::Path};
+use tracing::{debug, info, warn};
+
+const CERTIFICATE_DIRECTORY: &str = ".sam-e/certs";
+
+/// The names the generated certificate is valid for. APIs are picked by host header on
+/// `<api name>.localhost` so those are covered too, alongside the invoker's name on the docker
+/// network.
+const CERTIFICATE_NAMES: [&str; 4] = ["localhost", "*.localhost", "127.0.0.1", "sam-e-invoker"];
+
+/// Returns the absolute locations of the certificate and key to serve HTTPS with, or None when
+/// HTTPS isn't used. A certificate set in the runtime is used as it is, otherwise a self-signed
+/// certificate is generated in the .sam-e directory and reused each time the environment is built.
+pub fn get_certificate(runtime: &Runtime) -> anyhow::Result<Option<(String, String)>> {
+    if !runtime.get_use_https() {
+        debug!("HTTPS not enabled in runtime. Skipping certificate");
+        return Ok(None);
+    }
+
+    let (certificate_location, key_location) = match (
+        runtime.get_certificate_location(),
+        runtime.get_certificate_key_location(),
+    ) {
+        (Some(certificate), Some(key)) => {
+            debug!("Using the certificate set in the runtime");
+            (certificate.to_string(), key.to_string())
+        }
+        (None, None) => generate_certificate()?,
+        _ => {
+            return Err(Error::msg(
+                "Both certificate_location and certificate_key_location must be set to use your own certificate",
+            ));
+        }
+    };
+
+    let certificate_location = fs::canonicalize(&certificate_location).map_err(|e| {
+        Error::msg(format!(
+            "Failed to find certificate at {}: {}",
+            certificate_location, e
+        ))
+    })?;
+    let key_location = fs::canonicalize(&key_location).map_err(|e| {
+        Error::msg(format!(
+            "Failed to find certificate key at {}: {}",
+            key_location, e
+        ))
+    })?;
+
+    Ok(Some((
+        certificate_location.to_string_lossy().to_string(),
+        key_location.to_string_lossy().to_string(),
+    )))
+}
+
+fn generate_certificate() -> anyhow::Result<(String, String)> {
+    let certificate_location = format!("{}/cert.pem", CERTIFICATE_DIRECTORY);
+    let key_location = format!("{}/key.pem", CERTIFICATE_DIRECTORY);
+
+    if Path::new(&certificate_location).exists() && Path::new(&key_location).exists() {
+        debug!("Using the previously generated certificate");
+        return Ok((certificate_location, key_location));
+    }
+
+    info!("Generating a self-signed certificate for HTTPS within .sam-e directory");
+    let CertifiedKey { cert, key_pair } = generate_simple_self_signed(
+        CERTIFICATE_NAMES
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>(),
+    )?;
+
+    fs::create_dir_all(CERTIFICATE_DIRECTORY)?;
+    fs::write(&certificate_location, cert.pem())?;
+    fs::write(&key_location, key_pair.serialize_pem())?;
+    warn!(
+        "The certificate is self-signed, so will need to be trusted by your browser or system: {}",
+        certificate_location
+    );
+
+    Ok((certificate_location, key_location))
+}
@@ -1,11 +1,30 @@
 use sam_e_types::{
-    cloudformation::resource::{Bucket, DbInstance, EventBus, EventRule, ResourceType},
+    cloudformation::{
+        intrinsic::{get_referenced_resource, get_scalar_string},
+        resource::{
+            self,
+            apigw::{ApiAuth, ApiAuthorizer},
+            ApiGateway, ApiGatewayV2Api, ApiGatewayV2Integration, ApiGatewayV2Route,
+            ApiGatewayV2Stage, ApiKey, Bucket, DbInstance, EventBus, EventRule, FunctionUrlConfig,
+            HttpApi, LambdaUrl, Queue, ResourceType, UsagePlan, UsagePlanKey,
+        },
+    },
     config::{
         infrastructure::{
+            api::{
+                ApiType, Authorizer, Cors, CorsBuilder, IdentitySource, JwtAuthorizerBuilder,
+                LambdaAuthorizerBuilder, LambdaAuthorizerType, NO_AUTHORIZER,
+            },
             event_rule::{EventPatternBuilder, EventRuleBuilder},
+            function_url::{FunctionUrlAuthType, InvokeMode, DEFAULT_FUNCTION_URL_PORT},
+            s3::{CorsRule, NotificationFilter},
             triggers::Triggers,
-            EventBusBuilder, Infrastructure, MysqlBuilder, PostgresBuilder, QueueBuilder,
-            ResourceContainer, S3Builder,
+            usage_plan::{Quota, QuotaPeriod, Throttle},
+            websocket_api::DEFAULT_WEBSOCKET_PORT,
+            ApiBuilder, ApiKeyBuilder, EventBusBuilder, FunctionUrlBuilder,
+            FunctionUrlInfrastructure, Infrastructure, MysqlBuilder, PostgresBuilder, QueueBuilder,
+            ResourceContainer, S3Builder, UsagePlanBuilder, WebSocketApiBuilder,
+            WebSocketApiInfrastructure,
         },
         Config,
     },
@@ -17,7 +36,7 @@ use std::{collections::HashMap, fs};
 use tera::{Context, Tera};
 use tracing::{debug, error, info, trace, warn};
 
-use crate::scripts::environment::build::ResourceWithTemplate;
+use crate::scripts::environment::build::{certificate::get_certificate, ResourceWithTemplate};
 
 const SAM_E_DIRECTORY: &str = ".sam-e";
 
@@ -93,10 +112,80 @@ pub fn get_infrastructure_from_resources(
             }
             ResourceType::Queue => {
                 trace!("Found a queue!");
-                let sqs_infra = QueueBuilder::new()
+                let mut sqs_builder = QueueBuilder::new()
                     .name(resource_name.to_string())
-                    .template_name(resource.get_template_name().to_string())
-                    .build()?;
+                    .template_name(resource.get_template_name().to_string());
+
+                // Queues don't need any properties so a queue without them is still created
+                let queue_props =
+                    serde_yaml::from_value::<Queue>(resource.get_resources().properties.clone())
+                        .ok();
+                if let Some((dead_letter_queue, max_receive_count)) = queue_props
+                    .as_ref()
+                    .and_then(|queue| queue.get_redrive_policy().as_ref())
+                    .and_then(get_redrive_policy)
+                {
+                    debug!(
+                        "Queue {} has dead-letter queue: {}",
+                        resource_name, dead_letter_queue
+                    );
+                    sqs_builder = sqs_builder.redrive_policy(dead_letter_queue, max_receive_count);
+                }
+
+                let is_enabled = |value: &Option<serde_yaml::Value>| {
+                    value.as_ref().and_then(get_scalar_string).as_deref() == Some("true")
+                };
+                let get_number = |value: &Option<serde_yaml::Value>| {
+                    value
+                        .as_ref()
+                        .and_then(get_scalar_string)
+                        .and_then(|value| value.parse::<u32>().ok())
+                };
+                if let Some(queue_props) = &queue_props {
+                    sqs_builder = sqs_builder
+                        .fifo_queue(is_enabled(queue_props.get_fifo_queue()))
+                        .content_based_deduplication(is_enabled(
+                            queue_props.get_content_based_deduplication(),
+                        ));
+
+                    if let Some(visibility_timeout) =
+                        get_number(queue_props.get_visibility_timeout())
+                    {
+                        sqs_builder = sqs_builder.visibility_timeout(visibility_timeout);
+                    }
+                    if let Some(delay_seconds) = get_number(queue_props.get_delay_seconds()) {
+                        sqs_builder = sqs_builder.delay_seconds(delay_seconds);
+                    }
+                    if let Some(message_retention_period) =
+                        get_number(queue_props.get_message_retention_period())
+                    {
+                        sqs_builder =
+                            sqs_builder.message_retention_period(message_retention_period);
+                    }
+                    if let Some(wait_time_seconds) =
+                        get_number(queue_props.get_receive_message_wait_time_seconds())
+                    {
+                        sqs_builder =
+                            sqs_builder.receive_message_wait_time_seconds(wait_time_seconds);
+                    }
+                    if let Some(maximum_message_size) =
+                        get_number(queue_props.get_maximum_message_size())
+                    {
+                        sqs_builder = sqs_builder.maximum_message_size(maximum_message_size);
+                    }
+
+                    for tag in queue_props.get_tags().iter().flatten() {
+                        let key = tag.key.as_ref().and_then(get_scalar_string);
+                        let value = tag.value.as_ref().and_then(get_scalar_string);
+                        if let (Some(key), Some(value)) = (key, value) {
+                            sqs_builder = sqs_builder.tag(key, value);
+                        } else {
+                            warn!("Unable to read tag on queue: {}. Skipping", resource_name);
+                        }
+                    }
+                }
+
+                let sqs_infra = sqs_builder.build()?;
                 infrastructure.push(Infrastructure::Sqs(ResourceContainer::new(sqs_infra)));
             }
             ResourceType::Bucket => {
@@ -200,19 +289,249 @@ pub fn get_infrastructure_from_resources(
                     event_rule_infra,
                 )));
             }
+            ResourceType::ApiGateway => {
+                debug!("Found a REST API!");
+                let Ok(api) = serde_yaml::from_value::<ApiGateway>(
+                    resource.get_resources().properties.clone(),
+                ) else {
+                    warn!(
+                        "Unable to parse API properties for: {}. Skipping",
+                        resource_name
+                    );
+                    continue;
+                };
+
+                debug!("Properties: {:?}", api);
+
+                let cors = api
+                    .get_cors()
+                    .as_ref()
+                    .and_then(|cors| create_rest_api_cors(cors, resource_name));
+
+                infrastructure.push(create_infrastructure_from_api_resource(
+                    ApiType::Rest,
+                    api.get_stage_name(),
+                    api.get_auth(),
+                    cors,
+                    api.get_models().as_ref(),
+                    resource_name,
+                    resource.get_template_name(),
+                )?);
+            }
+            ResourceType::HttpApi => {
+                debug!("Found an HTTP API!");
+                let Ok(api) =
+                    serde_yaml::from_value::<HttpApi>(resource.get_resources().properties.clone())
+                else {
+                    warn!(
+                        "Unable to parse HTTP API properties for: {}. Skipping",
+                        resource_name
+                    );
+                    continue;
+                };
+
+                debug!("Properties: {:?}", api);
+
+                let cors = api
+                    .get_cors_configuration()
+                    .as_ref()
+                    .and_then(|cors| create_http_api_cors(cors, resource_name));
+
+                infrastructure.push(create_infrastructure_from_api_resource(
+                    ApiType::Http,
+                    api.get_stage_name(),
+                    api.get_auth(),
+                    cors,
+                    None,
+                    resource_name,
+                    resource.get_template_name(),
+                )?);
+            }
+            ResourceType::ApiKey => {
+                debug!("Found an API key!");
+                let Ok(api_key) =
+                    serde_yaml::from_value::<ApiKey>(resource.get_resources().properties.clone())
+                else {
+                    warn!(
+                        "Unable to parse API key properties for: {}. Skipping",
+                        resource_name
+                    );
+                    continue;
+                };
+
+                let mut api_key_builder = ApiKeyBuilder::new()
+                    .name(resource_name.to_string())
+                    .template_name(resource.get_template_name().to_string());
+
+                if let Some(value) = api_key.value.as_ref().and_then(get_scalar_string) {
+                    api_key_builder = api_key_builder.value(value);
+                } else {
+                    info!(
+                        "No value set for API key: {}. A value will be generated (see the sam-e-config)",
+                        resource_name
+                    );
+                }
+
+                if let Some(enabled) = api_key.enabled.as_ref().and_then(|v| v.as_bool()) {
+                    api_key_builder = api_key_builder.enabled(enabled);
+                }
+
+                infrastructure.push(Infrastructure::ApiKey(ResourceContainer::new(
+                    api_key_builder.build()?,
+                )));
+            }
+            ResourceType::UsagePlan => {
+                debug!("Found a usage plan!");
+                let Ok(usage_plan) = serde_yaml::from_value::<UsagePlan>(
+                    resource.get_resources().properties.clone(),
+                ) else {
+                    warn!(
+                        "Unable to parse usage plan properties for: {}. Skipping",
+                        resource_name
+                    );
+                    continue;
+                };
+
+                infrastructure.push(create_infrastructure_from_usage_plan(
+                    &usage_plan,
+                    resource_name,
+                    resource.get_template_name(),
+                    resources,
+                )?);
+            }
+            ResourceType::ApiGatewayV2Api => {
+                let Ok(api) = serde_yaml::from_value::<ApiGatewayV2Api>(
+                    resource.get_resources().properties.clone(),
+                ) else {
+                    warn!(
+                        "Unable to parse API Gateway V2 properties for: {}. Skipping",
+                        resource_name
+                    );
+                    continue;
+                };
+
+                let protocol_type = api.get_protocol_type().as_ref().and_then(get_scalar_string);
+                if !protocol_type.is_some_and(|p| p.eq_ignore_ascii_case("WEBSOCKET")) {
+                    warn!(
+                        "API {} is not a WebSocket API. Use AWS::Serverless::HttpApi for HTTP APIs. Skipping",
+                        resource_name
+                    );
+                    continue;
+                }
+
+                debug!("Found a WebSocket API!");
+                infrastructure.push(create_infrastructure_from_websocket_api(
+                    &api,
+                    resource_name,
+                    resource.get_template_name(),
+                    resources,
+                )?);
+            }
+            ResourceType::Function => {
+                let Some(function_url_config) =
+                    resource.get_resources().properties.get("FunctionUrlConfig")
+                else {
+                    trace!("Function has no function URL");
+                    continue;
+                };
+
+                let Ok(function_url_config) =
+                    serde_yaml::from_value::<FunctionUrlConfig>(function_url_config.clone())
+                else {
+                    warn!(
+                        "Unable to parse FunctionUrlConfig for: {}. Skipping",
+                        resource_name
+                    );
+                    continue;
+                };
+
+                debug!("Found a function URL!");
+                infrastructure.push(create_infrastructure_from_function_url(
+                    resource_name,
+                    resource.get_template_name(),
+                    function_url_config.get_auth_type(),
+                    function_url_config.get_cors(),
+                    function_url_config.get_invoke_mode(),
+                )?);
+            }
+            ResourceType::LambdaUrl => {
+                let Ok(lambda_url) = serde_yaml::from_value::<LambdaUrl>(
+                    resource.get_resources().properties.clone(),
+                ) else {
+                    warn!(
+                        "Unable to parse function URL properties for: {}. Skipping",
+                        resource_name
+                    );
+                    continue;
+                };
+
+                let Some(function_name) =
+                    get_referenced_resource(lambda_url.get_target_function_arn())
+                else {
+                    warn!(
+                        "Unable to find the function targeted by function URL {}. Skipping",
+                        resource_name
+                    );
+                    continue;
+                };
+
+                if lambda_url.get_qualifier().is_some() {
+                    debug!(
+                        "Function URL {} targets an alias or version, the function is invoked as is",
+                        resource_name
+                    );
+                }
+
+                debug!("Found a function URL for: {}", function_name);
+                infrastructure.push(create_infrastructure_from_function_url(
+                    &function_name,
+                    resource.get_template_name(),
+                    lambda_url.get_auth_type(),
+                    lambda_url.get_cors(),
+                    lambda_url.get_invoke_mode(),
+                )?);
+            }
             _ => {
                 trace!("Resource not recognized as infrastructure");
             }
         }
     }
 
+    // Each WebSocket API is served on its own port. These are given out in name order so they stay
+    // the same between builds.
+    let mut websocket_apis: Vec<&mut WebSocketApiInfrastructure> = infrastructure
+        .iter_mut()
+        .filter_map(|infra| match infra {
+            Infrastructure::WebSocketApi(api) => Some(&mut api.properties),
+            _ => None,
+        })
+        .collect();
+    websocket_apis.sort_by(|a, b| a.name.cmp(&b.name));
+    for (index, websocket_api) in websocket_apis.into_iter().enumerate() {
+        websocket_api.port = DEFAULT_WEBSOCKET_PORT + index as u16;
+    }
+
+    // Function URLs are also given a port each in the same way
+    let mut function_urls: Vec<&mut FunctionUrlInfrastructure> = infrastructure
+        .iter_mut()
+        .filter_map(|infra| match infra {
+            Infrastructure::FunctionUrl(function_url) => Some(&mut function_url.properties),
+            _ => None,
+        })
+        .collect();
+    function_urls.sort_by(|a, b| a.function_name.cmp(&b.function_name));
+    for (index, function_url) in function_urls.into_iter().enumerate() {
+        function_url.port = DEFAULT_FUNCTION_URL_PORT + index as u16;
+    }
+
     Ok(infrastructure)
 }
 
 /// Creates an infrastructure object from the S3 resource. This is done by checking the properties
-/// and adding the bucket name to the infrastructure object. If there are any queue configurations
-/// they are also added to the triggers of the infrastructure object. Will return an error if the
-/// template yaml is not formatted correctly.
+/// and adding the bucket name to the infrastructure object. Any queue and lambda configurations in
+/// the notification configuration are added to the triggers of the infrastructure object, and
+/// EventBridge notifications are enabled if the bucket has them turned on. Will
+/// return an error if the template yaml is not formatted correctly.
 fn create_infrastructure_from_s3_resource(
     resource: &Bucket,
     resource_name: &str,
@@ -220,35 +539,27 @@ fn create_infrastructure_from_s3_resource(
 ) -> Result<Infrastructure> {
     debug!("Creating infrastructure from S3 resource");
     debug!("Properties: {:?}", resource);
-    let bucket_name = if let Some(name) = resource.get_bucket_name().as_str() {
-        if name.is_empty() {
-            warn!(
-                "Unable to parse bucket name for S3 resource: {}. Defaulting to resource name",
-                resource_name
-            );
-            resource_name.to_lowercase() // makes lowercase because s3 buckets are lowercase
-        } else {
-            name.to_string()
-        }
-    } else {
-        warn!(
-            "Unable to find bucket name for S3 resource: {}. Defaulting to resource name",
-            resource_name
-        );
-        resource_name.to_lowercase() // makes lowercase because s3 buckets are lowercase
-    };
+    let bucket_name = get_bucket_name(resource, resource_name);
 
-    let s3_infra_builder = S3Builder::new()
+    let mut s3_infra_builder = S3Builder::new()
         .name(bucket_name.to_string())
         .template_name(template_name.to_string());
 
     let mut queue_triggers = vec![];
+    let mut lambda_triggers = vec![];
     if let Some(notification_configuration) = resource.get_notification_configuration() {
         if let Some(queue_config) = notification_configuration.get_queue_configurations() {
             for queue in queue_config {
-                let queue_val = queue.get_queue().as_str();
-                if let Some(queue_name) = queue_val {
-                    queue_triggers.push(queue_name.to_string());
+                if let Some(queue_name) = get_referenced_resource(queue.get_queue()) {
+                    s3_infra_builder =
+                        s3_infra_builder.notification_filter(create_notification_filter(
+                            &queue_name,
+                            queue.get_event(),
+                            queue.get_filter(),
+                        ));
+                    if !queue_triggers.contains(&queue_name) {
+                        queue_triggers.push(queue_name);
+                    }
                 } else {
                     warn!(
                         "Unable to parse queue name for S3 bucket: {}. Skipping",
@@ -257,6 +568,42 @@ fn create_infrastructure_from_s3_resource(
                 }
             }
         }
+
+        if let Some(lambda_config) = notification_configuration.get_lambda_configurations() {
+            for lambda in lambda_config {
+                if let Some(function_name) = get_referenced_resource(lambda.get_function()) {
+                    s3_infra_builder =
+                        s3_infra_builder.notification_filter(create_notification_filter(
+                            &function_name,
+                            lambda.get_event(),
+                            lambda.get_filter(),
+                        ));
+                    if !lambda_triggers.contains(&function_name) {
+                        lambda_triggers.push(function_name);
+                    }
+                } else {
+                    warn!(
+                        "Unable to parse function name for S3 bucket: {}. Skipping",
+                        bucket_name
+                    );
+                }
+            }
+        }
+
+        if let Some(event_bridge_config) =
+            notification_configuration.get_event_bridge_configuration()
+        {
+            // The configuration being present enables it unless it's explicitly turned off
+            let event_bridge_enabled = match event_bridge_config.get_event_bridge_enabled() {
+                serde_yaml::Value::Null => true,
+                enabled => get_scalar_string(enabled).as_deref() == Some("true"),
+            };
+            debug!(
+                "EventBridge notifications for S3 bucket: {}: {}",
+                bucket_name, event_bridge_enabled
+            );
+            s3_infra_builder = s3_infra_builder.event_bridge_enabled(event_bridge_enabled);
+        }
     } else {
         warn!(
             "No notification configurations found for S3 bucket: {}",
@@ -264,15 +611,941 @@ fn create_infrastructure_from_s3_resource(
         );
     }
 
+    if let Some(cors_configuration) = resource.get_cors_configuration() {
+        for cors_rule in cors_configuration.get_cors_rules() {
+            s3_infra_builder = s3_infra_builder.cors_rule(create_cors_rule(cors_rule));
+        }
+    }
+
     let s3_infra = s3_infra_builder
-        .triggers(Triggers::new(None, Some(queue_triggers)))
+        .triggers(Triggers::new(Some(lambda_triggers), Some(queue_triggers)))
         .build()?;
 
     Ok(Infrastructure::S3(ResourceContainer::new(s3_infra)))
 }
 
+/// Takes the values of a bucket's CORS rule, for the local S3 store to apply
+fn create_cors_rule(cors_rule: &resource::bucket::CorsRule) -> CorsRule {
+    let get_strings = |values: &Option<Vec<serde_yaml::Value>>| -> Vec<String> {
+        values
+            .iter()
+            .flatten()
+            .filter_map(get_scalar_string)
+            .collect()
+    };
+
+    CorsRule {
+        allowed_headers: get_strings(cors_rule.get_allowed_headers()),
+        allowed_methods: cors_rule
+            .get_allowed_methods()
+            .iter()
+            .filter_map(get_scalar_string)
+            .collect(),
+        allowed_origins: cors_rule
+            .get_allowed_origins()
+            .iter()
+            .filter_map(get_scalar_string)
+            .collect(),
+        exposed_headers: get_strings(cors_rule.get_exposed_headers()),
+        max_age: cors_rule
+            .get_max_age()
+            .as_ref()
+            .and_then(get_scalar_string)
+            .and_then(|max_age| max_age.parse().ok()),
+    }
+}
+
+/// Creates the filter for which of the bucket's notifications the destination is sent
+fn create_notification_filter(
+    destination: &str,
+    events: &serde_yaml::Value,
+    filter: &Option<resource::bucket::NotificationFilter>,
+) -> NotificationFilter {
+    let (prefix, suffix) = get_key_filter_rules(filter);
+
+    NotificationFilter {
+        destination: destination.to_string(),
+        events: get_notification_events(events),
+        prefix,
+        suffix,
+    }
+}
+
+/// Notification event types can be a single event or a list of them
+pub fn get_notification_events(events: &serde_yaml::Value) -> Vec<String> {
+    match events {
+        serde_yaml::Value::Sequence(events) => {
+            events.iter().filter_map(get_scalar_string).collect()
+        }
+        event => get_scalar_string(event).into_iter().collect(),
+    }
+}
+
+/// Takes the `prefix` and `suffix` rules from a notification's key filter
+pub fn get_key_filter_rules(
+    filter: &Option<resource::bucket::NotificationFilter>,
+) -> (Option<String>, Option<String>) {
+    let mut prefix = None;
+    let mut suffix = None;
+    let rules = filter
+        .as_ref()
+        .and_then(|filter| filter.get_s3_key().as_ref())
+        .and_then(|s3_key| s3_key.get_rules().as_ref());
+
+    for rule in rules.into_iter().flatten() {
+        let name = get_scalar_string(rule.get_name()).unwrap_or_default();
+        let value = get_scalar_string(rule.get_value());
+        match name.to_lowercase().as_str() {
+            "prefix" => prefix = value,
+            "suffix" => suffix = value,
+            _ => warn!("Unsupported S3 key filter rule: {}. Skipping", name),
+        }
+    }
+
+    (prefix, suffix)
+}
+
+/// The name the bucket is created with locally. Buckets without a name in the template use their
+/// resource name, lowercased as bucket names have to be.
+pub fn get_bucket_name(resource: &Bucket, resource_name: &str) -> String {
+    match resource.get_bucket_name().as_str() {
+        Some(name) if !name.is_empty() => name.to_string(),
+        Some(_) => {
+            warn!(
+                "Unable to parse bucket name for S3 resource: {}. Defaulting to resource name",
+                resource_name
+            );
+            resource_name.to_lowercase()
+        }
+        None => {
+            debug!(
+                "No bucket name for S3 resource: {}. Defaulting to resource name",
+                resource_name
+            );
+            resource_name.to_lowercase()
+        }
+    }
+}
+
+/// Creates an infrastructure object from an API resource (REST or HTTP). Only Lambda, JWT and
+/// Cognito authorizers are emulated - any other authorizer is skipped with a warning, as is a default
+/// authorizer that doesn't point at one of the emulated authorizers.
+fn create_infrastructure_from_api_resource(
+    api_type: ApiType,
+    stage_name: &Option<serde_yaml::Value>,
+    auth: &Option<ApiAuth>,
+    cors: Option<Cors>,
+    models: Option<&HashMap<String, serde_yaml::Value>>,
+    resource_name: &str,
+    template_name: &str,
+) -> Result<Infrastructure> {
+    debug!(
+        "Creating infrastructure from API resource: {}",
+        resource_name
+    );
+    let mut api_builder = ApiBuilder::new()
+        .name(resource_name.to_string())
+        .template_name(template_name.to_string())
+        .api_type(api_type);
+
+    if let Some(cors) = cors {
+        api_builder = api_builder.cors(cors);
+    }
+
+    if let Some(stage_name) = stage_name.as_ref().and_then(get_scalar_string) {
+        api_builder = api_builder.stage_name(stage_name);
+    }
+
+    for (model_name, schema) in models.into_iter().flatten() {
+        api_builder = api_builder.model(model_name.to_string(), schema.clone());
+    }
+
+    if let Some(api_key_required) = auth
+        .as_ref()
+        .and_then(|auth| auth.get_api_key_required().as_ref())
+        .and_then(|v| v.as_bool())
+    {
+        api_builder = api_builder.api_key_required(api_key_required);
+    }
+
+    let Some(auth) = auth else {
+        return Ok(Infrastructure::Api(ResourceContainer::new(
+            api_builder.build()?,
+        )));
+    };
+
+    let mut authorizer_names = vec![];
+    if let Some(authorizers) = auth.get_authorizers() {
+        for (authorizer_name, authorizer) in authorizers.iter() {
+            if let Some(jwt_authorizer) = create_jwt_authorizer(authorizer) {
+                authorizer_names.push(authorizer_name.to_string());
+                api_builder = api_builder.authorizer(authorizer_name.to_string(), jwt_authorizer);
+                continue;
+            }
+
+            let Some(function_name) = authorizer
+                .get_function_arn()
+                .as_ref()
+                .and_then(get_referenced_resource)
+            else {
+                warn!(
+                    "Authorizer {} on API {} is not a Lambda, JWT or Cognito authorizer so won't be emulated. Skipping",
+                    authorizer_name, resource_name
+                );
+                continue;
+            };
+
+            let authorizer_type = match api_type {
+                // HTTP API Lambda authorizers always receive the full request
+                ApiType::Http => LambdaAuthorizerType::Request,
+                ApiType::Rest => match authorizer
+                    .get_function_payload_type()
+                    .as_ref()
+                    .and_then(get_scalar_string)
+                {
+                    Some(payload_type) if payload_type.eq_ignore_ascii_case("REQUEST") => {
+                        LambdaAuthorizerType::Request
+                    }
+                    _ => LambdaAuthorizerType::Token,
+                },
+            };
+
+            let mut authorizer_builder = LambdaAuthorizerBuilder::new()
+                .function_name(function_name)
+                .authorizer_type(authorizer_type);
+
+            if let Some(identity) = authorizer.get_identity() {
+                if let Some(header) = identity.get_header().as_ref().and_then(get_scalar_string) {
+                    authorizer_builder =
+                        authorizer_builder.identity_source(IdentitySource::Header(header));
+                }
+                for header in identity.get_headers().iter().flatten() {
+                    if let Some(header) = get_scalar_string(header) {
+                        authorizer_builder =
+                            authorizer_builder.identity_source(IdentitySource::Header(header));
+                    }
+                }
+                for query_string in identity.get_query_strings().iter().flatten() {
+                    if let Some(query_string) = get_scalar_string(query_string) {
+                        authorizer_builder = authorizer_builder
+                            .identity_source(IdentitySource::QueryString(query_string));
+                    }
+                }
+                if let Some(validation_expression) = identity
+                    .get_validation_expression()
+                    .as_ref()
+                    .and_then(get_scalar_string)
+                {
+                    authorizer_builder =
+                        authorizer_builder.validation_expression(validation_expression);
+                }
+                if let Some(ttl) = identity
+                    .get_reauthorize_every()
+                    .as_ref()
+                    .and_then(get_scalar_string)
+                    .and_then(|ttl| ttl.parse::<u64>().ok())
+                {
+                    authorizer_builder = authorizer_builder.result_ttl_seconds(ttl);
+                }
+            }
+
+            if let Some(payload_format_version) = authorizer
+                .get_authorizer_payload_format_version()
+                .as_ref()
+                .and_then(get_scalar_string)
+            {
+                authorizer_builder =
+                    authorizer_builder.payload_format_version(payload_format_version);
+            }
+
+            if let Some(enable_simple_responses) = authorizer
+                .get_enable_simple_responses()
+                .as_ref()
+                .and_then(|v| v.as_bool())
+            {
+                authorizer_builder =
+                    authorizer_builder.enable_simple_responses(enable_simple_responses);
+            }
+
+            authorizer_names.push(authorizer_name.to_string());
+            api_builder = api_builder.authorizer(
+                authorizer_name.to_string(),
+                Authorizer::Lambda(authorizer_builder.build()?),
+            );
+        }
+    }
+
+    if let Some(default_authorizer) = auth
+        .get_default_authorizer()
+        .as_ref()
+        .and_then(get_scalar_string)
+    {
+        if default_authorizer == NO_AUTHORIZER || authorizer_names.contains(&default_authorizer) {
+            api_builder = api_builder.default_authorizer(default_authorizer);
+        } else {
+            warn!(
+                "Default authorizer {} on API {} is not emulated locally. Routes will not be authorized by default",
+                default_authorizer, resource_name
+            );
+        }
+    }
+
+    Ok(Infrastructure::Api(ResourceContainer::new(
+        api_builder.build()?,
+    )))
+}
+
+/// Creates an infrastructure object from a usage plan. The API keys linked to the plan are found
+/// from the `AWS::ApiGateway::UsagePlanKey` resources that reference it.
+fn create_infrastructure_from_usage_plan(
+    usage_plan: &UsagePlan,
+    resource_name: &str,
+    template_name: &str,
+    resources: &HashMap<String, ResourceWithTemplate>,
+) -> Result<Infrastructure> {
+    debug!("Creating infrastructure from usage plan: {}", resource_name);
+    let mut usage_plan_builder = UsagePlanBuilder::new()
+        .name(resource_name.to_string())
+        .template_name(template_name.to_string());
+
+    for api_stage in usage_plan.api_stages.iter().flatten() {
+        if let Some(api_name) = api_stage.api_id.as_ref().and_then(get_referenced_resource) {
+            usage_plan_builder = usage_plan_builder.api(api_name);
+        } else {
+            warn!(
+                "Unable to parse API for usage plan: {}. Skipping stage",
+                resource_name
+            );
+        }
+    }
+
+    for (key_resource_name, key_resource) in resources.iter() {
+        if key_resource.get_resources().resource_type != ResourceType::UsagePlanKey {
+            continue;
+        }
+
+        let Ok(usage_plan_key) =
+            serde_yaml::from_value::<UsagePlanKey>(key_resource.get_resources().properties.clone())
+        else {
+            warn!(
+                "Unable to parse usage plan key properties for: {}. Skipping",
+                key_resource_name
+            );
+            continue;
+        };
+
+        if get_referenced_resource(&usage_plan_key.usage_plan_id).as_deref() != Some(resource_name)
+        {
+            continue;
+        }
+
+        if let Some(api_key_name) = get_referenced_resource(&usage_plan_key.key_id) {
+            usage_plan_builder = usage_plan_builder.api_key(api_key_name);
+        }
+    }
+
+    if let Some(throttle) = &usage_plan.throttle {
+        let burst_limit = throttle.burst_limit.as_ref().and_then(|v| v.as_u64());
+        let rate_limit = throttle.rate_limit.as_ref().and_then(|v| v.as_f64());
+
+        // A throttle with only one of the limits set has the other default to match it, so the
+        // bucket holds a second's worth of requests
+        let limits = match (burst_limit, rate_limit) {
+            (Some(burst_limit), Some(rate_limit)) => Some((burst_limit, rate_limit)),
+            (Some(burst_limit), None) => {
+                warn!(
+                    "Usage plan {} has no rate limit. Using the burst limit of {} requests per second",
+                    resource_name, burst_limit
+                );
+                Some((burst_limit, burst_limit as f64))
+            }
+            (None, Some(rate_limit)) => {
+                let burst_limit = (rate_limit.ceil() as u64).max(1);
+                warn!(
+                    "Usage plan {} has no burst limit. Using a burst limit of {} requests from the rate limit",
+                    resource_name, burst_limit
+                );
+                Some((burst_limit, rate_limit))
+            }
+            (None, None) => {
+                warn!(
+                    "Usage plan {} has a throttle without a burst or rate limit so won't be throttled locally",
+                    resource_name
+                );
+                None
+            }
+        };
+
+        if let Some((burst_limit, rate_limit)) = limits {
+            // No request fits in a burst of 0, so the bucket holds a second's worth of requests
+            let burst_limit = match burst_limit {
+                0 => {
+                    warn!(
+                        "Usage plan {} has a burst limit of 0, which would throttle every request. Using the rate limit instead",
+                        resource_name
+                    );
+                    (rate_limit.ceil() as u64).max(1)
+                }
+                burst_limit => burst_limit,
+            };
+
+            usage_plan_builder = usage_plan_builder.throttle(Throttle {
+                burst_limit,
+                rate_limit,
+            });
+        }
+    }
+
+    if let Some(quota) = &usage_plan.quota {
+        let limit = quota.limit.as_ref().and_then(|v| v.as_u64());
+        let period = quota
+            .period
+            .as_ref()
+            .and_then(get_scalar_string)
+            .and_then(|p| QuotaPeriod::from_cloud_formation(&p));
+
+        if let (Some(limit), Some(period)) = (limit, period) {
+            usage_plan_builder = usage_plan_builder.quota(Quota { limit, period });
+        } else {
+            warn!(
+                "Unable to parse quota for usage plan: {}. Skipping quota",
+                resource_name
+            );
+        }
+    }
+
+    Ok(Infrastructure::UsagePlan(ResourceContainer::new(
+        usage_plan_builder.build()?,
+    )))
+}
+
+/// Keeps the values of API keys from the existing config so generated (or manually set) keys
+/// don't change each time the environment is built
+pub fn keep_api_key_values(infrastructure: &mut [Infrastructure], existing: &[Infrastructure]) {
+    for infra in infrastructure.iter_mut() {
+        let Infrastructure::ApiKey(api_key) = infra else {
+            continue;
+        };
+
+        let existing_value = existing.iter().find_map(|i| match i {
+            Infrastructure::ApiKey(existing_key)
+                if existing_key.properties.name == api_key.properties.name =>
+            {
+                Some(existing_key.properties.value.to_string())
+            }
+            _ => None,
+        });
+
+        if let Some(existing_value) = existing_value {
+            debug!(
+                "Keeping existing value for API key: {}",
+                api_key.properties.name
+            );
+            api_key.properties.value = existing_value;
+        }
+    }
+}
+
+/// Creates a WebSocket API from an `AWS::ApiGatewayV2::Api`. Its routes, and the functions they
+/// are integrated with, come from the `AWS::ApiGatewayV2::Route` and `Integration` resources that
+/// reference the API. Routes that aren't integrated with a function are skipped.
+fn create_infrastructure_from_websocket_api(
+    api: &ApiGatewayV2Api,
+    resource_name: &str,
+    template_name: &str,
+    resources: &HashMap<String, ResourceWithTemplate>,
+) -> Result<Infrastructure> {
+    debug!(
+        "Creating infrastructure from WebSocket API: {}",
+        resource_name
+    );
+    let mut websocket_api_builder = WebSocketApiBuilder::new()
+        .name(resource_name.to_string())
+        .template_name(template_name.to_string());
+
+    if let Some(route_selection_expression) = api
+        .get_route_selection_expression()
+        .as_ref()
+        .and_then(get_scalar_string)
+    {
+        websocket_api_builder =
+            websocket_api_builder.route_selection_expression(route_selection_expression);
+    }
+
+    for (route_resource_name, route_resource) in resources.iter() {
+        match route_resource.get_resources().resource_type {
+            ResourceType::ApiGatewayV2Route => {
+                let Ok(route) = serde_yaml::from_value::<ApiGatewayV2Route>(
+                    route_resource.get_resources().properties.clone(),
+                ) else {
+                    warn!(
+                        "Unable to parse route properties for: {}. Skipping",
+                        route_resource_name
+                    );
+                    continue;
+                };
+
+                if get_referenced_resource(route.get_api_id()).as_deref() != Some(resource_name) {
+                    continue;
+                }
+
+                let Some(route_key) = get_scalar_string(route.get_route_key()) else {
+                    warn!(
+                        "Unable to parse route key for: {}. Skipping",
+                        route_resource_name
+                    );
+                    continue;
+                };
+
+                let Some(function_name) = route
+                    .get_target()
+                    .as_ref()
+                    .and_then(get_referenced_resource)
+                    .and_then(|target| {
+                        let integration_name = target.trim_start_matches("integrations/");
+                        resources.get(integration_name)
+                    })
+                    .and_then(|integration| {
+                        serde_yaml::from_value::<ApiGatewayV2Integration>(
+                            integration.get_resources().properties.clone(),
+                        )
+                        .ok()
+                    })
+                    .and_then(|integration| {
+                        integration
+                            .get_integration_uri()
+                            .as_ref()
+                            .and_then(get_referenced_resource)
+                    })
+                else {
+                    warn!(
+                        "Route {} on WebSocket API {} is not integrated with a function. Skipping",
+                        route_key, resource_name
+                    );
+                    continue;
+                };
+
+                debug!("Route {} integrated with: {}", route_key, function_name);
+                let two_way = route.get_route_response_selection_expression().is_some();
+                websocket_api_builder =
+                    websocket_api_builder.route(route_key, function_name, two_way);
+            }
+            ResourceType::ApiGatewayV2Stage => {
+                let Ok(stage) = serde_yaml::from_value::<ApiGatewayV2Stage>(
+                    route_resource.get_resources().properties.clone(),
+                ) else {
+                    continue;
+                };
+
+                if get_referenced_resource(stage.get_api_id()).as_deref() != Some(resource_name) {
+                    continue;
+                }
+
+                if let Some(stage_name) = get_scalar_string(stage.get_stage_name()) {
+                    websocket_api_builder = websocket_api_builder.stage_name(stage_name);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(Infrastructure::WebSocketApi(ResourceContainer::new(
+        websocket_api_builder.build()?,
+    )))
+}
+
+/// Creates a function URL from either the `FunctionUrlConfig` of a function or an
+/// `AWS::Lambda::Url`, which share the same properties
+fn create_infrastructure_from_function_url(
+    function_name: &str,
+    template_name: &str,
+    auth_type: &serde_yaml::Value,
+    cors: &Option<serde_yaml::Value>,
+    invoke_mode: &Option<serde_yaml::Value>,
+) -> Result<Infrastructure> {
+    debug!(
+        "Creating infrastructure from function URL: {}",
+        function_name
+    );
+    let mut function_url_builder = FunctionUrlBuilder::new()
+        .function_name(function_name.to_string())
+        .template_name(template_name.to_string());
+
+    match get_scalar_string(auth_type).as_deref() {
+        Some("NONE") => {}
+        Some("AWS_IAM") => {
+            warn!(
+                "Function URL for {} uses AWS_IAM auth. Requests must be signed but the signature is not checked locally",
+                function_name
+            );
+            function_url_builder = function_url_builder.auth_type(FunctionUrlAuthType::AwsIam);
+        }
+        _ => {
+            warn!(
+                "Unrecognised AuthType on function URL for {}, defaulting to NONE",
+                function_name
+            );
+        }
+    }
+
+    if let Some(invoke_mode) = invoke_mode.as_ref().and_then(get_scalar_string) {
+        if invoke_mode == "RESPONSE_STREAM" {
+            function_url_builder = function_url_builder.invoke_mode(InvokeMode::ResponseStream);
+        }
+    }
+
+    // Function URLs take the same CORS settings as HTTP APIs
+    if let Some(cors) = cors
+        .as_ref()
+        .and_then(|cors| create_http_api_cors(cors, function_name))
+    {
+        function_url_builder = function_url_builder.cors(cors);
+    }
+
+    Ok(Infrastructure::FunctionUrl(ResourceContainer::new(
+        function_url_builder.build()?,
+    )))
+}
+
+/// Keeps the port (and host) of each API and function URL from the existing config, as these can
+/// be changed by hand to serve an API on its own port or host
+pub fn keep_api_endpoints(infrastructure: &mut [Infrastructure], existing: &[Infrastructure]) {
+    for infra in infrastructure.iter_mut() {
+        match infra {
+            Infrastructure::Api(api) => {
+                let existing_api = existing.iter().find_map(|i| match i {
+                    Infrastructure::Api(existing_api)
+                        if existing_api.properties.name == api.properties.name =>
+                    {
+                        Some(&existing_api.properties)
+                    }
+                    _ => None,
+                });
+
+                if let Some(existing_api) = existing_api {
+                    debug!(
+                        "Keeping existing port and host for API: {}",
+                        api.properties.name
+                    );
+                    api.properties.port = existing_api.port;
+                    if existing_api.host.is_some() {
+                        api.properties.host = existing_api.host.clone();
+                    }
+                }
+            }
+            Infrastructure::WebSocketApi(websocket_api) => {
+                let existing_port = existing.iter().find_map(|i| match i {
+                    Infrastructure::WebSocketApi(existing_api)
+                        if existing_api.properties.name == websocket_api.properties.name =>
+                    {
+                        Some(existing_api.properties.port)
+                    }
+                    _ => None,
+                });
+
+                if let Some(existing_port) = existing_port {
+                    debug!(
+                        "Keeping existing port for WebSocket API: {}",
+                        websocket_api.properties.name
+                    );
+                    websocket_api.properties.port = existing_port;
+                }
+            }
+            Infrastructure::FunctionUrl(function_url) => {
+                let existing_port = existing.iter().find_map(|i| match i {
+                    Infrastructure::FunctionUrl(existing_url)
+                        if existing_url.properties.function_name
+                            == function_url.properties.function_name =>
+                    {
+                        Some(existing_url.properties.port)
+                    }
+                    _ => None,
+                });
+
+                if let Some(existing_port) = existing_port {
+                    debug!(
+                        "Keeping existing port for function URL: {}",
+                        function_url.properties.function_name
+                    );
+                    function_url.properties.port = existing_port;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Ports the invoker's other listeners are served on, which APIs can't use
+const RESERVED_PORTS: [(u16, &str); 6] = [
+    (3001, "the S3 webhook"),
+    (3002, "the EventBridge source"),
+    (3003, "the local token issuer"),
+    (3030, "the invoker"),
+    (9000, "the S3 store"),
+    (9324, "the local queue"),
+];
+
+/// Checks that each port is only used by one listener once the ports from the existing config
+/// have been kept. APIs can share a port with each other, but only with different hosts as the host
+/// is what picks the API.
+pub fn check_api_endpoints(infrastructure: &[Infrastructure]) -> Result<()> {
+    debug!("Checking API ports for conflicts...");
+    let mut ports: HashMap<u16, String> = RESERVED_PORTS
+        .iter()
+        .map(|(port, name)| (*port, name.to_string()))
+        .collect();
+    let mut api_hosts: HashMap<(u16, String), &str> = HashMap::new();
+
+    for infra in infrastructure {
+        let (port, name) = match infra {
+            Infrastructure::Api(api) => {
+                let api = &api.properties;
+                let port = api.get_port();
+                let host = api.get_host().to_lowercase();
+                if let Some(other_api) = api_hosts.insert((port, host.clone()), &api.name) {
+                    return Err(Error::msg(format!(
+                        "APIs {} and {} are both served on port {} with the host {}. Change the Port or Host of one in .sam-e/sam-e-config.yaml and rebuild",
+                        other_api, api.name, port, host
+                    )));
+                }
+                if api_hosts
+                    .keys()
+                    .filter(|(api_port, _)| *api_port == port)
+                    .count()
+                    > 1
+                {
+                    continue;
+                }
+
+                (port, format!("API {}", api.name))
+            }
+            // These are given out from 3004 without knowing which ports the APIs were set to
+            Infrastructure::WebSocketApi(websocket_api) => (
+                websocket_api.properties.port,
+                format!("WebSocket API {}", websocket_api.properties.name),
+            ),
+            // As are function URL ports from 3010
+            Infrastructure::FunctionUrl(function_url) => (
+                function_url.properties.port,
+                format!(
+                    "the function URL of {}",
+                    function_url.properties.function_name
+                ),
+            ),
+            _ => continue,
+        };
+
+        if let Some(existing) = ports.insert(port, name.clone()) {
+            return Err(Error::msg(format!(
+                "{} and {} are both set to port {}. Change the Port in .sam-e/sam-e-config.yaml and rebuild",
+                existing, name, port
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Creates the CORS settings of a REST API from its `Cors` property. This is either just the
+/// allowed origin or a mapping of settings, with each value wrapped in single quotes (i.e.
+/// "'https://example.com'") as API Gateway uses them as header values as is.
+fn create_rest_api_cors(cors: &serde_yaml::Value, resource_name: &str) -> Option<Cors> {
+    let unquote = |value: &serde_yaml::Value| {
+        get_scalar_string(value).map(|v| v.trim_matches('\'').to_string())
+    };
+    let split = |value: &str| -> Vec<String> {
+        value
+            .split(',')
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .collect()
+    };
+
+    let mut cors_builder = CorsBuilder::new();
+    let allow_origin = match cors.as_mapping() {
+        Some(_) => cors.get("AllowOrigin").and_then(unquote),
+        None => unquote(cors),
+    };
+    if let Some(allow_origin) = allow_origin {
+        cors_builder = cors_builder.allow_origin(allow_origin);
+    }
+
+    // SAM allows every method the path uses when none are given
+    let allow_methods = cors
+        .get("AllowMethods")
+        .and_then(unquote)
+        .unwrap_or_else(|| "DELETE,GET,HEAD,OPTIONS,PATCH,POST,PUT".to_string());
+    for allow_method in split(&allow_methods) {
+        cors_builder = cors_builder.allow_method(allow_method);
+    }
+
+    if let Some(allow_headers) = cors.get("AllowHeaders").and_then(unquote) {
+        for allow_header in split(&allow_headers) {
+            cors_builder = cors_builder.allow_header(allow_header);
+        }
+    }
+
+    if let Some(max_age) = cors
+        .get("MaxAge")
+        .and_then(unquote)
+        .and_then(|v| v.parse::<u64>().ok())
+    {
+        cors_builder = cors_builder.max_age(max_age);
+    }
+
+    if let Some(allow_credentials) = cors.get("AllowCredentials").and_then(|v| v.as_bool()) {
+        cors_builder = cors_builder.allow_credentials(allow_credentials);
+    }
+
+    match cors_builder.build() {
+        Ok(cors) => Some(cors),
+        Err(e) => {
+            warn!(
+                "Unable to create CORS settings for API {}: {}. Skipping",
+                resource_name, e
+            );
+            None
+        }
+    }
+}
+
+/// Creates the CORS settings of an HTTP API from its `CorsConfiguration` property. Setting this to
+/// `true` allows any origin, method and header.
+fn create_http_api_cors(cors: &serde_yaml::Value, resource_name: &str) -> Option<Cors> {
+    if let Some(enabled) = cors.as_bool() {
+        if !enabled {
+            return None;
+        }
+
+        return CorsBuilder::new()
+            .allow_origin("*".to_string())
+            .allow_method("*".to_string())
+            .allow_header("*".to_string())
+            .build()
+            .ok();
+    }
+
+    let list = |key: &str| -> Vec<String> {
+        cors.get(key)
+            .and_then(|v| v.as_sequence())
+            .map(|values| values.iter().filter_map(get_scalar_string).collect())
+            .unwrap_or_default()
+    };
+
+    let mut cors_builder = CorsBuilder::new();
+    for allow_origin in list("AllowOrigins") {
+        cors_builder = cors_builder.allow_origin(allow_origin);
+    }
+    for allow_method in list("AllowMethods") {
+        cors_builder = cors_builder.allow_method(allow_method);
+    }
+    for allow_header in list("AllowHeaders") {
+        cors_builder = cors_builder.allow_header(allow_header);
+    }
+    for expose_header in list("ExposeHeaders") {
+        cors_builder = cors_builder.expose_header(expose_header);
+    }
+
+    if let Some(max_age) = cors.get("MaxAge").and_then(|v| v.as_u64()) {
+        cors_builder = cors_builder.max_age(max_age);
+    }
+
+    if let Some(allow_credentials) = cors.get("AllowCredentials").and_then(|v| v.as_bool()) {
+        cors_builder = cors_builder.allow_credentials(allow_credentials);
+    }
+
+    match cors_builder.build() {
+        Ok(cors) => Some(cors),
+        Err(e) => {
+            warn!(
+                "Unable to create CORS settings for HTTP API {}: {}. Skipping",
+                resource_name, e
+            );
+            None
+        }
+    }
+}
+
+/// Creates a JWT authorizer (HTTP APIs) or Cognito authorizer (REST APIs) if the authorizer is
+/// one of these types. Returns `None` for any other type of authorizer.
+fn create_jwt_authorizer(authorizer: &ApiAuthorizer) -> Option<Authorizer> {
+    if let Some(jwt_configuration) = authorizer.get_jwt_configuration() {
+        // HTTP APIs specify the identity source as a mapping expression
+        // (i.e. $request.header.Authorization)
+        let mut jwt_builder = JwtAuthorizerBuilder::new();
+        if let Some(identity_header) = authorizer
+            .get_identity_source()
+            .as_ref()
+            .and_then(get_scalar_string)
+            .and_then(|source| {
+                source
+                    .strip_prefix("$request.header.")
+                    .map(|header| header.to_string())
+            })
+        {
+            jwt_builder = jwt_builder.identity_header(identity_header);
+        }
+
+        if let Some(issuer) = jwt_configuration
+            .get_issuer()
+            .as_ref()
+            .and_then(get_scalar_string)
+        {
+            jwt_builder = jwt_builder.issuer(issuer);
+        }
+
+        for audience in jwt_configuration.get_audience().iter().flatten() {
+            if let Some(audience) = get_scalar_string(audience) {
+                jwt_builder = jwt_builder.audience(audience);
+            }
+        }
+
+        return Some(Authorizer::Jwt(jwt_builder.build()));
+    }
+
+    if authorizer.get_user_pool_arn().is_some() {
+        let mut jwt_builder = JwtAuthorizerBuilder::new();
+        if let Some(identity_header) = authorizer
+            .get_identity()
+            .as_ref()
+            .and_then(|identity| identity.get_header().as_ref())
+            .and_then(get_scalar_string)
+        {
+            jwt_builder = jwt_builder.identity_header(identity_header);
+        }
+
+        return Some(Authorizer::Cognito(jwt_builder.build()));
+    }
+
+    None
+}
+
+/// Takes the dead-letter queue and max receive count from a queue's redrive policy, which can be
+/// given as a mapping or as a JSON string
+fn get_redrive_policy(redrive_policy: &serde_yaml::Value) -> Option<(String, u32)> {
+    let redrive_policy = match redrive_policy {
+        serde_yaml::Value::String(policy) => serde_yaml::from_str(policy).ok()?,
+        policy => policy.clone(),
+    };
+
+    let Some(dead_letter_queue) = redrive_policy
+        .get("deadLetterTargetArn")
+        .and_then(get_referenced_resource)
+    else {
+        warn!("Unable to find the dead-letter queue in redrive policy. Skipping");
+        return None;
+    };
+    let max_receive_count = redrive_policy
+        .get("maxReceiveCount")
+        .and_then(get_scalar_string)
+        .and_then(|count| count.parse::<u32>().ok())
+        .unwrap_or(10);
+
+    Some((dead_letter_queue, max_receive_count))
+}
+
 /// Creates the infrastructure files required for the local environment. This includes the
-/// Dockerfile and entrypoint.sh for S3 and the custom.conf for SQS. This is done by using Tera to
+/// Dockerfile and entrypoint.sh for S3 and the docker-compose files. This is done by using Tera to
 /// render the templates with the context provided by the config. The files are then written to the
 /// .sam-e directory. All files are embedded in the binary so no need to worry about them being lost.
 pub fn create_infrastructure_files(config: &Config) -> anyhow::Result<()> {
@@ -290,6 +1563,11 @@ pub fn create_infrastructure_files(config: &Config) -> anyhow::Result<()> {
         context.insert("frontend", frontend);
     }
 
+    if let Some((certificate, key)) = get_certificate(config.get_runtime())? {
+        context.insert("https_certificate", &certificate);
+        context.insert("https_key", &key);
+    }
+
     if let Some(s3_dockerfile) = Asset::get("local-s3/entrypoint.sh") {
         let raw_data = s3_dockerfile.data;
         tera.add_raw_template("s3-dockerfile", &String::from_utf8_lossy(&raw_data))?;
@@ -298,14 +1576,6 @@ pub fn create_infrastructure_files(config: &Config) -> anyhow::Result<()> {
         return Err(Error::msg("Failed to find S3 Dockerfile template"));
     };
 
-    if let Some(queue_config) = Asset::get("local-queue/custom.conf") {
-        let raw_data = queue_config.data;
-        tera.add_raw_template("queue-config", &String::from_utf8_lossy(&raw_data))?;
-    } else {
-        error!("Failed to find queue config template");
-        return Err(Error::msg("Failed to find queue config template"));
-    };
-
     if let Some(docker_template) = Asset::get("docker-compose.yaml") {
         let raw_data = docker_template.data;
         tera.add_raw_template("docker-compose", &String::from_utf8_lossy(&raw_data))?;
@@ -322,16 +1592,13 @@ pub fn create_infrastructure_files(config: &Config) -> anyhow::Result<()> {
         return Err(Error::msg("Failed to find docker-compose dev template"));
     };
 
-    let mut has_s3 = false;
-    let mut has_queue = false;
+    let has_s3 = infrastructure
+        .iter()
+        .any(|i| matches!(i, Infrastructure::S3(_)));
 
-    infrastructure.iter().for_each(|i| match i {
-        Infrastructure::S3(_) => has_s3 = true,
-        Infrastructure::Sqs(_) => has_queue = true,
-        _ => (),
-    });
-
-    if has_s3 {
+    if has_s3 && config.get_runtime().get_use_s3_store() {
+        debug!("Using the local S3 store. Skipping creation of S3 Dockerfile");
+    } else if has_s3 {
         info!("Detected S3 infrastructure. Creating required files within .sam-e directory");
         fs::create_dir_all(format!("{}/local-s3", SAM_E_DIRECTORY))?;
         create_s3_dockerfile(&tera, &context)?;
@@ -339,14 +1606,6 @@ pub fn create_infrastructure_files(config: &Config) -> anyhow::Result<()> {
         debug!("No S3 infrastructure detected. Skipping creation of S3 Dockerfile");
     }
 
-    if has_queue {
-        info!("Detected SQS infrastructure. Creating required files within .sam-e directory");
-        fs::create_dir_all(format!("{}/local-queue", SAM_E_DIRECTORY))?;
-        create_queue_config(&tera, &context)?;
-    } else {
-        debug!("No SQS infrastructure detected. Skipping creation of queue config");
-    }
-
     create_docker_compose(&tera, &context, false)?;
     create_docker_compose(&tera, &context, true)?;
     Ok(())
@@ -417,17 +1676,3 @@ fn create_s3_dockerfile(tera: &Tera, context: &Context) -> anyhow::Result<()> {
 
     Ok(())
 }
-
-/// Actually writes the queue config file to the .sam-e directory after rendering via Tera
-/// template with the context provided. NOTE: this doesn't work with VMs becuase the file is written
-/// to the host machine so can't be passed as volume in dockerfile...
-fn create_queue_config(tera: &Tera, context: &Context) -> anyhow::Result<()> {
-    let result = tera.render("queue-config", &context)?;
-
-    fs::write(
-        format!("{}/local-queue/custom.conf", SAM_E_DIRECTORY),
-        result,
-    )?;
-
-    Ok(())
-}
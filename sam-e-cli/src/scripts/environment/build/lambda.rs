@@ -1,15 +1,35 @@
-use crate::scripts::environment::build::ResourceWithTemplate;
+use crate::scripts::environment::build::{
+    infrastructure::{get_bucket_name, get_key_filter_rules, get_notification_events},
+    ResourceWithTemplate,
+};
 use anyhow::Result;
 use sam_e_types::{
-    cloudformation::resource::{
-        self,
-        function::event::{ApiEvent, Event as LambdaEvent, EventType, SqsEvent},
-        Function, ResourceType,
+    cloudformation::{
+        intrinsic::{get_referenced_resource, get_scalar_string},
+        resource::{
+            self,
+            apigw::ApiAuth,
+            function::event::{
+                ApiEvent, ApiEventAuth, ApiEventRequestModel, Event as LambdaEvent, EventType,
+                HttpApiEvent, S3Event, SqsEvent,
+            },
+            ApiGateway, Bucket, Function, HttpApi, ResourceType,
+        },
+    },
+    config::{
+        infrastructure::{
+            api::{ApiType, IMPLICIT_HTTP_API, IMPLICIT_REST_API},
+            Infrastructure,
+        },
+        lambda::{
+            docker::DockerBuildBuilder,
+            event::{Event, EventApiProperties},
+            Lambda, PackageType,
+        },
     },
-    config::lambda::{docker::DockerBuildBuilder, event::Event, Lambda, PackageType},
 };
 use std::collections::HashMap;
-use tracing::{debug, error, trace, warn};
+use tracing::{debug, error, info, trace, warn};
 
 /// Takes a hashmap of the resources within CloudFormation template and returns each of the Lambdas
 /// specified in a vector.
@@ -54,9 +74,216 @@ pub fn get_lambdas_from_resources(
         }
     }
 
+    add_openapi_routes(&mut lambdas, resources);
+
     Ok(lambdas)
 }
 
+/// APIs can declare their routes in an OpenAPI `DefinitionBody` rather than with function events.
+/// Each operation with a Lambda proxy integration (`x-amazon-apigateway-integration`) is added to
+/// the function it's integrated with, as if it were an event on that function.
+fn add_openapi_routes(lambdas: &mut [Lambda], resources: &HashMap<String, ResourceWithTemplate>) {
+    for (api_id, resource) in resources.iter() {
+        let properties = resource.get_resources().properties.clone();
+        let authorizer_names = |auth: &Option<ApiAuth>| -> Vec<String> {
+            auth.as_ref()
+                .and_then(|auth| auth.get_authorizers().as_ref())
+                .map(|authorizers| authorizers.keys().cloned().collect())
+                .unwrap_or_default()
+        };
+        let (definition_body, authorizers) = match resource.get_resources().resource_type {
+            ResourceType::ApiGateway => match serde_yaml::from_value::<ApiGateway>(properties) {
+                Ok(api) => (
+                    api.get_definition_body().clone(),
+                    authorizer_names(api.get_auth()),
+                ),
+                Err(_) => continue,
+            },
+            ResourceType::HttpApi => match serde_yaml::from_value::<HttpApi>(properties) {
+                Ok(api) => (
+                    api.get_definition_body().clone(),
+                    authorizer_names(api.get_auth()),
+                ),
+                Err(_) => continue,
+            },
+            _ => continue,
+        };
+        let Some(definition_body) = definition_body else {
+            continue;
+        };
+        let Some(paths) = definition_body.get("paths").and_then(|p| p.as_mapping()) else {
+            warn!(
+                "No paths found in the OpenAPI document for API: {}. Skipping",
+                api_id
+            );
+            continue;
+        };
+
+        debug!(
+            "Adding routes from the OpenAPI document for API: {}",
+            api_id
+        );
+        let base_path = get_base_path(api_id, resources);
+
+        for (path, operations) in paths.iter() {
+            let (Some(path), Some(operations)) = (path.as_str(), operations.as_mapping()) else {
+                continue;
+            };
+
+            for (method, operation) in operations.iter() {
+                // Paths can also hold parameters, summaries etc. alongside the operations
+                let method = match method.as_str() {
+                    Some("x-amazon-apigateway-any-method") => "ANY".to_string(),
+                    Some(method) if OPENAPI_METHODS.contains(&method.to_lowercase().as_str()) => {
+                        method.to_uppercase()
+                    }
+                    _ => continue,
+                };
+
+                let Some(integration) = operation.get("x-amazon-apigateway-integration") else {
+                    warn!(
+                        "No integration found for {} {} on API {}. Skipping",
+                        method, path, api_id
+                    );
+                    continue;
+                };
+                let integration_type = integration
+                    .get("type")
+                    .and_then(|t| t.as_str())
+                    .unwrap_or_default();
+                if integration_type.eq_ignore_ascii_case("mock") {
+                    trace!("Skipping mock integration for {} {}", method, path);
+                    continue;
+                }
+                if !integration_type.eq_ignore_ascii_case("aws_proxy") {
+                    warn!(
+                        "Only Lambda proxy integrations are supported but {} {} on API {} uses {}. Skipping",
+                        method, path, api_id, integration_type
+                    );
+                    continue;
+                }
+
+                let function_name = integration.get("uri").and_then(get_integrated_function);
+                let Some(lambda) = function_name
+                    .as_ref()
+                    .and_then(|name| lambdas.iter_mut().find(|l| l.get_name() == name))
+                else {
+                    warn!(
+                        "Unable to find the function integrated with {} {} on API {}. Skipping",
+                        method, path, api_id
+                    );
+                    continue;
+                };
+
+                // SAM adds routes from events to the OpenAPI document so the same route may be
+                // declared both ways
+                let already_routed = lambda
+                    .get_events()
+                    .iter()
+                    .filter_map(|e| e.get_api_properties())
+                    .any(|api_props| {
+                        api_props.get_api_id().map(|id| id.as_str()) == Some(api_id.as_str())
+                            && api_props.get_path() == path
+                            && api_props.get_method().eq_ignore_ascii_case(&method)
+                    });
+                if already_routed {
+                    trace!(
+                        "Route {} {} is already an event on the function",
+                        method,
+                        path
+                    );
+                    continue;
+                }
+
+                debug!(
+                    "Route {} {} integrated with: {}",
+                    method,
+                    path,
+                    lambda.get_name()
+                );
+                let mut event = Event::new(None);
+                event.set_api_properties(path.to_string(), base_path.clone(), method);
+                set_api_route_details(&mut event, api_id.to_string(), None);
+                set_openapi_security(&mut event, operation, &definition_body, &authorizers);
+                lambda.add_event(event);
+            }
+        }
+    }
+}
+
+const OPENAPI_METHODS: [&str; 7] = ["get", "put", "post", "delete", "options", "head", "patch"];
+
+/// Integration URIs wrap the function's ARN (i.e.
+/// `arn:aws:apigateway:<region>:lambda:path/2015-03-31/functions/<function arn>/invocations`).
+/// These are usually built with `!Sub` and `${Function.Arn}`.
+fn get_integrated_function(uri: &serde_yaml::Value) -> Option<String> {
+    match uri.as_str() {
+        Some(uri) => {
+            let function_arn = uri
+                .split_once("functions/")
+                .map(|(_, arn)| arn.trim_end_matches("/invocations"))
+                .unwrap_or(uri);
+            get_referenced_resource(&serde_yaml::Value::String(function_arn.to_string()))
+        }
+        None => get_referenced_resource(uri),
+    }
+}
+
+/// Takes the authorizer (and scopes) or API key requirement from the operation's `security`, or
+/// the document's where the operation doesn't set one. Authorizers are only used where they are
+/// also in the API's `Auth` as those defined with `x-amazon-apigateway-authorizer` aren't emulated.
+fn set_openapi_security(
+    event: &mut Event,
+    operation: &serde_yaml::Value,
+    definition_body: &serde_yaml::Value,
+    authorizers: &[String],
+) {
+    let Some(requirement) = operation
+        .get("security")
+        .or_else(|| definition_body.get("security"))
+        .and_then(|security| security.as_sequence())
+        .and_then(|security| security.first())
+        .and_then(|requirement| requirement.as_mapping())
+    else {
+        return;
+    };
+    let Some(api_properties) = event.get_api_properties_mut() else {
+        return;
+    };
+
+    for (scheme_name, scopes) in requirement.iter() {
+        let Some(scheme_name) = scheme_name.as_str() else {
+            continue;
+        };
+
+        // Security schemes are under securityDefinitions in Swagger 2.0
+        let scheme_type = definition_body
+            .get("components")
+            .and_then(|components| components.get("securitySchemes"))
+            .or_else(|| definition_body.get("securityDefinitions"))
+            .and_then(|schemes| schemes.get(scheme_name))
+            .and_then(|scheme| scheme.get("type"))
+            .and_then(|t| t.as_str());
+        let is_api_key =
+            scheme_type == Some("apiKey") && !authorizers.iter().any(|a| a.as_str() == scheme_name);
+        if is_api_key {
+            api_properties.set_api_key_required(true);
+        } else if authorizers.iter().any(|a| a.as_str() == scheme_name) {
+            api_properties.set_authorizer(scheme_name.to_string());
+            let scopes = scopes
+                .as_sequence()
+                .map(|scopes| scopes.iter().filter_map(get_scalar_string).collect())
+                .unwrap_or_default();
+            api_properties.set_authorization_scopes(scopes);
+        } else {
+            warn!(
+                "Authorizer {} is not in the API's Auth so won't be used locally",
+                scheme_name
+            );
+        }
+    }
+}
+
 fn parse_function(
     function_name: &str,
     template_name: &str,
@@ -140,15 +367,13 @@ fn parse_events(
                         }
                     };
 
-                    let base_path = if let Some(api_id) = event_props.get_rest_api_id() {
-                        if let Some(api_id) = api_id.as_str() {
-                            get_base_path(&api_id, &resources)
-                        } else {
-                            None
-                        }
-                    } else {
-                        None
-                    };
+                    let api_id = event_props
+                        .get_rest_api_id()
+                        .as_ref()
+                        .and_then(get_referenced_resource)
+                        .unwrap_or_else(|| IMPLICIT_REST_API.to_string());
+
+                    let base_path = get_base_path(&api_id, resources);
 
                     let mut event = Event::new(None);
                     event.set_api_properties(
@@ -156,6 +381,54 @@ fn parse_events(
                         base_path,
                         event_props.get_method().as_str().unwrap().to_string(),
                     );
+                    set_api_route_details(&mut event, api_id, event_props.get_auth().as_ref());
+                    set_api_request_validation(
+                        &mut event,
+                        event_props.get_request_model().as_ref(),
+                        event_props.get_request_parameters().as_ref(),
+                    );
+
+                    event
+                }
+                EventType::HttpApi => {
+                    let event_data =
+                        serde_yaml::from_value::<HttpApiEvent>(event_data.properties.clone());
+                    let event_props = match event_data {
+                        Ok(event_props) => event_props,
+                        Err(e) => {
+                            error!("Error parsing HTTP API event properties: {}", e);
+                            warn!(
+                                "Unable to parse HTTP API event properties for: {}. Skipping",
+                                function_name
+                            );
+                            return None;
+                        }
+                    };
+
+                    let api_id = event_props
+                        .get_api_id()
+                        .as_ref()
+                        .and_then(get_referenced_resource)
+                        .unwrap_or_else(|| IMPLICIT_HTTP_API.to_string());
+
+                    // Events without a path or method are attached to the $default route which
+                    // catches any request not matched by another route
+                    let path = event_props
+                        .get_path()
+                        .as_ref()
+                        .and_then(|p| p.as_str())
+                        .unwrap_or("/{proxy+}")
+                        .to_string();
+                    let method = event_props
+                        .get_method()
+                        .as_ref()
+                        .and_then(|m| m.as_str())
+                        .unwrap_or("ANY")
+                        .to_string();
+
+                    let mut event = Event::new(None);
+                    event.set_api_properties(path, None, method);
+                    set_api_route_details(&mut event, api_id, event_props.get_auth().as_ref());
 
                     event
                 }
@@ -174,14 +447,64 @@ fn parse_events(
                         }
                     };
 
-                    let queue = event_props
-                        .get_queue()
-                        .as_str()
-                        .unwrap_or_default()
-                        .to_string();
+                    let queue =
+                        get_referenced_resource(event_props.get_queue()).unwrap_or_default();
 
                     let mut event = Event::new(None);
                     event.set_sqs_properties(queue);
+                    set_sqs_batching(&mut event, &event_props, function_name);
+                    set_sqs_filters(&mut event, &event_props, function_name);
+
+                    let report_batch_item_failures = event_props
+                        .get_function_response_types()
+                        .iter()
+                        .flatten()
+                        .filter_map(get_scalar_string)
+                        .any(|response_type| response_type == "ReportBatchItemFailures");
+                    if let Some(sqs_props) = event.get_sqs_properties_mut() {
+                        sqs_props.set_report_batch_item_failures(report_batch_item_failures);
+                    }
+
+                    event
+                }
+                EventType::S3 => {
+                    let event_data =
+                        serde_yaml::from_value::<S3Event>(event_data.properties.clone());
+                    let event_props = match event_data {
+                        Ok(event_props) => event_props,
+                        Err(e) => {
+                            error!("Error parsing S3 event properties: {}", e);
+                            warn!(
+                                "Unable to parse S3 event properties for: {}. Skipping",
+                                function_name
+                            );
+                            return None;
+                        }
+                    };
+
+                    let Some(bucket) =
+                        get_referenced_bucket_name(event_props.get_bucket(), resources)
+                    else {
+                        warn!(
+                            "Unable to find the bucket for S3 event of: {}. Skipping",
+                            function_name
+                        );
+                        return None;
+                    };
+
+                    let mut event = Event::new(None);
+                    event.set_s3_properties(bucket);
+                    if let Some(s3_props) = event.get_s3_properties_mut() {
+                        s3_props.set_events(get_notification_events(event_props.get_events()));
+
+                        let (prefix, suffix) = get_key_filter_rules(event_props.get_filter());
+                        if let Some(prefix) = prefix {
+                            s3_props.set_prefix(prefix);
+                        }
+                        if let Some(suffix) = suffix {
+                            s3_props.set_suffix(suffix);
+                        }
+                    }
 
                     event
                 }
@@ -201,6 +524,96 @@ fn parse_events(
     events_vec
 }
 
+/// SAM S3 events reference a bucket in the same template, so the bucket's name is taken from its
+/// resource
+fn get_referenced_bucket_name(
+    bucket: &serde_yaml::Value,
+    resources: &HashMap<String, ResourceWithTemplate>,
+) -> Option<String> {
+    let resource_name = get_referenced_resource(bucket)?;
+    let Some(resource) = resources.get(&resource_name) else {
+        warn!("Bucket: {} not found in the template", resource_name);
+        return None;
+    };
+
+    let bucket =
+        serde_yaml::from_value::<Bucket>(resource.get_resources().properties.clone()).ok()?;
+
+    Some(get_bucket_name(&bucket, &resource_name))
+}
+
+/// Sets how messages are batched up for the function from the SQS event, keeping to the limits
+/// Lambda has for each setting
+fn set_sqs_batching(event: &mut Event, event_props: &SqsEvent, function_name: &str) {
+    let Some(sqs_props) = event.get_sqs_properties_mut() else {
+        return;
+    };
+
+    let get_number = |value: &Option<serde_yaml::Value>| {
+        value
+            .as_ref()
+            .and_then(get_scalar_string)
+            .and_then(|v| v.parse::<u16>().ok())
+    };
+
+    if let Some(batch_size) = get_number(event_props.get_batch_size()) {
+        sqs_props.set_batch_size(batch_size.clamp(1, 10000));
+    }
+
+    if let Some(window) = get_number(event_props.get_maximum_batching_window_in_seconds()) {
+        sqs_props.set_maximum_batching_window(window.min(300));
+    }
+
+    // Batches of more than 10 messages need a batching window in AWS
+    if sqs_props.get_batch_size() > 10 && sqs_props.get_maximum_batching_window() == 0 {
+        warn!(
+            "SQS event for {} has a BatchSize above 10 without a MaximumBatchingWindowInSeconds. This would fail to deploy",
+            function_name
+        );
+    }
+
+    if let Some(maximum_concurrency) = event_props
+        .get_scaling_config()
+        .as_ref()
+        .and_then(|scaling_config| get_number(scaling_config.get_maximum_concurrency()))
+    {
+        sqs_props.set_maximum_concurrency(maximum_concurrency.clamp(2, 1000));
+    }
+}
+
+/// Sets the filter patterns from the SQS event's filter criteria. Patterns are JSON strings in the
+/// template, though a mapping is accepted too.
+fn set_sqs_filters(event: &mut Event, event_props: &SqsEvent, function_name: &str) {
+    let Some(sqs_props) = event.get_sqs_properties_mut() else {
+        return;
+    };
+
+    let filter_patterns = event_props
+        .get_filter_criteria()
+        .iter()
+        .filter_map(|filter_criteria| filter_criteria.get_filters().as_ref())
+        .flatten()
+        .filter_map(|filter| {
+            let pattern = match filter.get_pattern() {
+                serde_yaml::Value::String(pattern) => serde_yaml::from_str(pattern).ok(),
+                pattern => Some(pattern.clone()),
+            };
+            match pattern {
+                Some(pattern @ serde_yaml::Value::Mapping(_)) => Some(pattern),
+                _ => {
+                    warn!(
+                        "Invalid filter pattern found on SQS event for: {}. Skipping",
+                        function_name
+                    );
+                    None
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    sqs_props.set_filter_patterns(filter_patterns);
+}
+
 pub fn select_lambdas(lambdas: Vec<Lambda>) -> Vec<Lambda> {
     let lambdas_select = dialoguer::MultiSelect::new()
         .with_prompt("Select which new lambdas you would like to spin up in your environment:")
@@ -307,6 +720,255 @@ pub fn add_build_settings(lambdas: Vec<Lambda>) -> Vec<Lambda> {
     lambdas
 }
 
+/// Checks the API routes across all lambdas before the environment is built. Routes that resolve
+/// to the same path and method, or REST routes that use different parameter names at the same
+/// position, can't be deployed to API Gateway and will error. Overlapping routes that are resolved by precedence
+/// (e.g. `/users/{id}` and `/{proxy+}`) are only logged, as are routes on different APIs that share
+/// a port, which are picked between by the host.
+pub fn check_api_routes(lambdas: &[Lambda], infrastructure: &[Infrastructure]) -> Result<()> {
+    debug!("Checking API routes for conflicts...");
+    let routes: Vec<(&str, &EventApiProperties)> = lambdas
+        .iter()
+        .flat_map(|lambda| {
+            lambda.get_events().iter().filter_map(move |event| {
+                event
+                    .get_api_properties()
+                    .map(|api| (lambda.get_name(), api))
+            })
+        })
+        .collect();
+
+    let mut conflicts: Vec<String> = vec![];
+    let mut parameter_names: HashMap<String, (String, String, &str)> = HashMap::new();
+
+    for (index, (lambda_name, route)) in routes.iter().enumerate() {
+        for (other_lambda_name, other_route) in routes.iter().skip(index + 1) {
+            if route.get_route_template() != other_route.get_route_template() {
+                continue;
+            }
+
+            // Each API has its own route table so routes on different APIs never conflict. Requests
+            // without a host matching an API are routed across every API on the port though.
+            if route.get_api_id() != other_route.get_api_id() {
+                let api = get_api_endpoint(infrastructure, route.get_api_id());
+                let other_api = get_api_endpoint(infrastructure, other_route.get_api_id());
+                let is_same_method = route
+                    .get_method()
+                    .eq_ignore_ascii_case(other_route.get_method());
+                if let (Some((port, host)), Some((other_port, other_host))) = (api, other_api) {
+                    if is_same_method && port == other_port {
+                        warn!(
+                            "{} {} ({}) and {} {} ({}) are on APIs sharing port {}. Requests need the host {} or {} to reach the right one",
+                            route.get_method(),
+                            route.get_full_path(),
+                            lambda_name,
+                            other_route.get_method(),
+                            other_route.get_full_path(),
+                            other_lambda_name,
+                            port,
+                            host,
+                            other_host
+                        );
+                    }
+                }
+                continue;
+            }
+
+            if route
+                .get_method()
+                .eq_ignore_ascii_case(other_route.get_method())
+            {
+                conflicts.push(format!(
+                    "{} {} ({}) conflicts with {} {} ({})",
+                    route.get_method(),
+                    route.get_full_path(),
+                    lambda_name,
+                    other_route.get_method(),
+                    other_route.get_full_path(),
+                    other_lambda_name
+                ));
+            } else if route.is_any_method() || other_route.is_any_method() {
+                info!(
+                    "Routes {} {} ({}) and {} {} ({}) overlap. The specific method will take precedence over ANY",
+                    route.get_method(),
+                    route.get_full_path(),
+                    lambda_name,
+                    other_route.get_method(),
+                    other_route.get_full_path(),
+                    other_lambda_name
+                );
+            }
+        }
+
+        // REST APIs don't allow sibling path parameters with different names (i.e. /users/{id}
+        // and /users/{userId}/orders or /users/{proxy+}) as each one is a separate resource. HTTP
+        // APIs pick between them by precedence, so only REST routes are checked.
+        if get_api_type(infrastructure, route.get_api_id()) != ApiType::Rest {
+            continue;
+        }
+
+        let full_path = route.get_full_path();
+        let mut prefix = route.get_api_id().cloned().unwrap_or_default();
+        for segment in full_path.split('/').filter(|s| !s.is_empty()) {
+            if segment.starts_with('{') {
+                match parameter_names.get(&prefix) {
+                    Some((existing, existing_path, existing_lambda)) if existing != segment => {
+                        conflicts.push(format!(
+                            "{} ({}) uses path parameter {} where {} ({}) uses {}",
+                            full_path,
+                            lambda_name,
+                            segment,
+                            existing_path,
+                            existing_lambda,
+                            existing
+                        ));
+                    }
+                    Some(_) => {}
+                    None => {
+                        parameter_names.insert(
+                            prefix.clone(),
+                            (segment.to_string(), full_path.clone(), lambda_name),
+                        );
+                    }
+                }
+                prefix.push_str("/{}");
+            } else {
+                prefix.push('/');
+                prefix.push_str(segment);
+            }
+        }
+    }
+
+    if conflicts.is_empty() {
+        debug!("No API route conflicts found");
+        return Ok(());
+    }
+
+    for conflict in conflicts.iter() {
+        error!("API route conflict: {}", conflict);
+    }
+
+    Err(anyhow::anyhow!(
+        "Found {} conflicting API route(s). Please fix these in your template(s) before building",
+        conflicts.len()
+    ))
+}
+
+/// Records which API a route belongs to and the auth settings (authorizer, scopes and API key)
+/// set on the route itself
+fn set_api_route_details(event: &mut Event, api_id: String, auth: Option<&ApiEventAuth>) {
+    let Some(api_properties) = event.get_api_properties_mut() else {
+        return;
+    };
+
+    api_properties.set_api_id(api_id);
+
+    let authorizer = auth
+        .and_then(|auth| auth.get_authorizer().as_ref())
+        .and_then(get_scalar_string);
+    if let Some(authorizer) = authorizer {
+        api_properties.set_authorizer(authorizer);
+    }
+
+    let authorization_scopes: Vec<String> = auth
+        .and_then(|auth| auth.get_authorization_scopes().as_ref())
+        .map(|scopes| scopes.iter().filter_map(get_scalar_string).collect())
+        .unwrap_or_default();
+    api_properties.set_authorization_scopes(authorization_scopes);
+
+    if let Some(api_key_required) = auth
+        .and_then(|auth| auth.get_api_key_required().as_ref())
+        .and_then(|v| v.as_bool())
+    {
+        api_properties.set_api_key_required(api_key_required);
+    }
+}
+
+/// The port and host the route's API is served on
+fn get_api_endpoint(
+    infrastructure: &[Infrastructure],
+    api_id: Option<&String>,
+) -> Option<(u16, String)> {
+    infrastructure.iter().find_map(|infra| match infra {
+        Infrastructure::Api(api) if Some(&api.properties.name) == api_id => {
+            Some((api.properties.get_port(), api.properties.get_host()))
+        }
+        _ => None,
+    })
+}
+
+/// The type of the route's API. The implicit APIs aren't part of the infrastructure so are known
+/// by their name.
+fn get_api_type(infrastructure: &[Infrastructure], api_id: Option<&String>) -> ApiType {
+    infrastructure
+        .iter()
+        .find_map(|infra| match infra {
+            Infrastructure::Api(api) if Some(&api.properties.name) == api_id => {
+                Some(api.properties.api_type)
+            }
+            _ => None,
+        })
+        .unwrap_or_else(|| match api_id {
+            Some(api_id) if api_id == IMPLICIT_HTTP_API => ApiType::Http,
+            _ => ApiType::Rest,
+        })
+}
+
+/// Records what the request to a REST API route is validated against before reaching the
+/// function. As with SAM, requests are only validated when the request model sets `ValidateBody`
+/// or `ValidateParameters`, with the other defaulting to false.
+fn set_api_request_validation(
+    event: &mut Event,
+    request_model: Option<&ApiEventRequestModel>,
+    request_parameters: Option<&Vec<serde_yaml::Value>>,
+) {
+    let Some(api_properties) = event.get_api_properties_mut() else {
+        return;
+    };
+    let Some(request_model) = request_model else {
+        return;
+    };
+    let is_enabled = |setting: &Option<serde_yaml::Value>| {
+        setting.as_ref().and_then(|v| v.as_bool()).unwrap_or(false)
+    };
+
+    if is_enabled(request_model.get_validate_body()) {
+        if let Some(model) = get_scalar_string(request_model.get_model()) {
+            api_properties.set_request_model(model);
+        }
+        if let Some(required) = request_model
+            .get_required()
+            .as_ref()
+            .and_then(|v| v.as_bool())
+        {
+            api_properties.set_request_body_required(required);
+        }
+    }
+    if !is_enabled(request_model.get_validate_parameters()) {
+        return;
+    }
+
+    let required_parameters: Vec<String> = request_parameters
+        .into_iter()
+        .flatten()
+        .filter_map(|parameter| match parameter.as_mapping() {
+            Some(parameter) => parameter.iter().find_map(|(name, settings)| {
+                let required = settings
+                    .get("Required")
+                    .and_then(|r| r.as_bool())
+                    .unwrap_or(false);
+                if required {
+                    get_scalar_string(name)
+                } else {
+                    None
+                }
+            }),
+            None => None,
+        })
+        .collect();
+    api_properties.set_required_parameters(required_parameters);
+}
+
 /// If a Lambda is linked to an API gateway with a base path, this will be returned as an Option.
 fn get_base_path(
     api_id: &str,
@@ -324,17 +986,9 @@ fn get_base_path(
                     );
                     return false;
                 };
-                let rest_api_id = properties.get_rest_api_id();
+                let rest_api_id = get_referenced_resource(properties.get_rest_api_id());
 
-                if let Some(rest_api_id) = rest_api_id.as_str() {
-                    if rest_api_id == api_id {
-                        true
-                    } else {
-                        false
-                    }
-                } else {
-                    false
-                }
+                rest_api_id.as_deref() == Some(api_id)
             }
             _ => false,
         }
@@ -367,3 +1021,125 @@ fn get_base_path(
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sam_e_types::config::infrastructure::{ApiBuilder, ResourceContainer};
+
+    fn lambda_with_route(name: &str, api_id: &str, path: &str, method: &str) -> Lambda {
+        let mut event = Event::new(None);
+        event.set_api_properties(path.to_string(), None, method.to_string());
+        set_api_route_details(&mut event, api_id.to_string(), None);
+
+        Lambda::new(
+            name.to_string(),
+            name.to_lowercase(),
+            HashMap::new(),
+            vec![event],
+            "template.yaml",
+            PackageType::Image,
+            None,
+        )
+    }
+
+    fn api(name: &str, api_type: ApiType) -> Infrastructure {
+        Infrastructure::Api(ResourceContainer::new(
+            ApiBuilder::new()
+                .name(name.to_string())
+                .template_name("template.yaml".to_string())
+                .api_type(api_type)
+                .build()
+                .unwrap(),
+        ))
+    }
+
+    #[test]
+    fn same_method_and_path_conflict() {
+        let lambdas = vec![
+            lambda_with_route("GetUser", IMPLICIT_REST_API, "/users/{id}", "GET"),
+            lambda_with_route("GetUserById", IMPLICIT_REST_API, "/users/{id}", "get"),
+        ];
+
+        assert!(check_api_routes(&lambdas, &[]).is_err());
+    }
+
+    #[test]
+    fn specific_method_and_any_overlap() {
+        let lambdas = vec![
+            lambda_with_route("GetUser", IMPLICIT_REST_API, "/users/{id}", "GET"),
+            lambda_with_route("AnyUser", IMPLICIT_REST_API, "/users/{id}", "ANY"),
+        ];
+
+        assert!(check_api_routes(&lambdas, &[]).is_ok());
+    }
+
+    #[test]
+    fn same_route_on_different_apis() {
+        let lambdas = vec![
+            lambda_with_route("GetUser", "UsersApi", "/users/{id}", "GET"),
+            lambda_with_route("GetAdmin", "AdminApi", "/users/{id}", "GET"),
+        ];
+        let infrastructure = vec![
+            api("UsersApi", ApiType::Rest),
+            api("AdminApi", ApiType::Rest),
+        ];
+
+        assert!(check_api_routes(&lambdas, &infrastructure).is_ok());
+    }
+
+    #[test]
+    fn rest_sibling_parameters_with_different_names_conflict() {
+        let lambdas = vec![
+            lambda_with_route("GetUser", IMPLICIT_REST_API, "/users/{id}", "GET"),
+            lambda_with_route(
+                "GetOrders",
+                IMPLICIT_REST_API,
+                "/users/{userId}/orders",
+                "GET",
+            ),
+        ];
+
+        assert!(check_api_routes(&lambdas, &[]).is_err());
+    }
+
+    #[test]
+    fn rest_sibling_parameter_and_greedy_parameter_conflict() {
+        let lambdas = vec![
+            lambda_with_route("GetUser", "UsersApi", "/users/{id}", "GET"),
+            lambda_with_route("Proxy", "UsersApi", "/users/{proxy+}", "ANY"),
+        ];
+
+        assert!(check_api_routes(&lambdas, &[api("UsersApi", ApiType::Rest)]).is_err());
+    }
+
+    #[test]
+    fn rest_sibling_parameters_with_the_same_name() {
+        let lambdas = vec![
+            lambda_with_route("GetUser", IMPLICIT_REST_API, "/users/{id}", "GET"),
+            lambda_with_route("GetOrders", IMPLICIT_REST_API, "/users/{id}/orders", "GET"),
+        ];
+
+        assert!(check_api_routes(&lambdas, &[]).is_ok());
+    }
+
+    #[test]
+    fn http_sibling_parameter_and_greedy_parameter() {
+        let lambdas = vec![
+            lambda_with_route("GetUser", IMPLICIT_HTTP_API, "/users/{id}", "GET"),
+            lambda_with_route("Proxy", IMPLICIT_HTTP_API, "/users/{proxy+}", "ANY"),
+        ];
+
+        assert!(check_api_routes(&lambdas, &[]).is_ok());
+    }
+
+    #[test]
+    fn http_sibling_parameters_with_different_names() {
+        let lambdas = vec![
+            lambda_with_route("GetUser", "UsersApi", "/users/{id}", "GET"),
+            lambda_with_route("GetOrders", "UsersApi", "/users/{userId}/orders", "GET"),
+        ];
+
+        assert!(check_api_routes(&lambdas, &[api("UsersApi", ApiType::Http)]).is_ok());
+    }
+}
@@ -6,12 +6,17 @@ use std::{
 
 use anyhow::Result;
 use fancy_regex::Regex;
-use tracing::{debug, trace};
+use tracing::{debug, trace, warn};
 
 use crate::scripts::environment::build::ResourceWithTemplate;
 use sam_e_types::{
-    cloudformation::Template, config::runtime::template::Template as ConfigTemplate,
+    cloudformation::{resource::ResourceType, Resource, Template},
+    config::{
+        infrastructure::api::{IMPLICIT_HTTP_API, IMPLICIT_REST_API},
+        runtime::template::Template as ConfigTemplate,
+    },
 };
+use serde_yaml::{Mapping, Value};
 
 /// Takes the vec of template locations (i.e. file paths to the YAML files) and returns a hashmap
 /// of the resources section of the CloudFormation template.
@@ -45,9 +50,16 @@ pub fn build_template(
     let yaml_file = fs::read_to_string(template_path)?;
     debug!("YAML file read successfully");
 
-    let template_value: Template = serde_yaml::from_str(&yaml_file)?;
+    let mut template_value: Template = serde_yaml::from_str(&yaml_file)?;
     debug!("Template value: {:#?}", template_value);
 
+    add_implicit_apis(&mut template_value.resources);
+    if let Some(globals) = template_value.get_globals().clone() {
+        apply_api_globals(&mut template_value.resources, &globals);
+    }
+    let template_dir = template_path.parent().unwrap_or(Path::new("."));
+    inline_api_definitions(&mut template_value.resources, template_dir);
+
     let template_resources = template_value.resources;
     let mut resources_with_template: HashMap<String, ResourceWithTemplate> = HashMap::new();
     template_resources.into_iter().for_each(|(k, v)| {
@@ -60,6 +72,153 @@ pub fn build_template(
     Ok(resources_with_template)
 }
 
+/// SAM creates an API for any Api or HttpApi events that don't reference one (RestApiId or ApiId).
+/// The same is done here so these events have an API that Globals (i.e. CORS) can be applied to.
+fn add_implicit_apis(resources: &mut HashMap<String, Resource>) {
+    let event_types: Vec<String> = resources
+        .values()
+        .filter(|resource| resource.resource_type == ResourceType::Function)
+        .filter_map(|resource| resource.properties.get("Events"))
+        .filter_map(|events| events.as_mapping())
+        .flat_map(|events| events.values())
+        .filter_map(|event| {
+            let event_type = event.get("Type")?.as_str()?;
+            let api_key = match event_type {
+                "Api" => "RestApiId",
+                "HttpApi" => "ApiId",
+                _ => return None,
+            };
+            match event.get("Properties").and_then(|p| p.get(api_key)) {
+                Some(_) => None,
+                None => Some(event_type.to_string()),
+            }
+        })
+        .collect();
+
+    let implicit_apis = [
+        ("Api", IMPLICIT_REST_API, ResourceType::ApiGateway, "Prod"),
+        (
+            "HttpApi",
+            IMPLICIT_HTTP_API,
+            ResourceType::HttpApi,
+            "$default",
+        ),
+    ];
+    for (event_type, api_name, resource_type, stage_name) in implicit_apis {
+        if resources.contains_key(api_name) || !event_types.iter().any(|t| t == event_type) {
+            continue;
+        }
+
+        debug!("Adding implicit API: {}", api_name);
+        let mut properties = Mapping::new();
+        properties.insert(
+            Value::String("StageName".to_string()),
+            Value::String(stage_name.to_string()),
+        );
+        resources.insert(
+            api_name.to_string(),
+            Resource {
+                resource_type,
+                properties: Value::Mapping(properties),
+            },
+        );
+    }
+}
+
+/// Copies the Api and HttpApi sections of Globals onto each API resource. Properties set on the
+/// resource itself take priority over the globals.
+fn apply_api_globals(resources: &mut HashMap<String, Resource>, globals: &Value) {
+    for (resource_name, resource) in resources.iter_mut() {
+        let globals_key = match resource.resource_type {
+            ResourceType::ApiGateway => "Api",
+            ResourceType::HttpApi => "HttpApi",
+            _ => continue,
+        };
+        let Some(api_globals) = globals.get(globals_key).and_then(|g| g.as_mapping()) else {
+            continue;
+        };
+
+        trace!("Applying {} globals to: {}", globals_key, resource_name);
+        if resource.properties.is_null() {
+            resource.properties = Value::Mapping(Mapping::new());
+        }
+        let Some(properties) = resource.properties.as_mapping_mut() else {
+            continue;
+        };
+        for (key, value) in api_globals.iter() {
+            if !properties.contains_key(key) {
+                properties.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// APIs can keep their OpenAPI document in a separate file, either with `DefinitionUri` or an
+/// `AWS::Include` transform as the `DefinitionBody`. Local files (relative to the template) are
+/// read into the `DefinitionBody` so the routes can be found later on. Documents kept in S3 are
+/// not supported.
+fn inline_api_definitions(resources: &mut HashMap<String, Resource>, template_dir: &Path) {
+    for (resource_name, resource) in resources.iter_mut() {
+        if !matches!(
+            resource.resource_type,
+            ResourceType::ApiGateway | ResourceType::HttpApi
+        ) {
+            continue;
+        }
+        let Some(properties) = resource.properties.as_mapping_mut() else {
+            continue;
+        };
+
+        let location = match properties.get("DefinitionUri") {
+            Some(definition_uri) => definition_uri.as_str(),
+            None => properties
+                .get("DefinitionBody")
+                .and_then(|body| body.get("Fn::Transform"))
+                .filter(|transform| {
+                    transform.get("Name").and_then(|n| n.as_str()) == Some("AWS::Include")
+                })
+                .and_then(|transform| transform.get("Parameters")?.get("Location")?.as_str()),
+        };
+        let Some(location) = location.map(|l| l.to_string()) else {
+            if properties.contains_key("DefinitionUri") {
+                warn!(
+                    "DefinitionUri for API {} is not a local file. Routes will only be taken from function events",
+                    resource_name
+                );
+            }
+            continue;
+        };
+        if location.starts_with("s3://") {
+            warn!(
+                "OpenAPI document for API {} is kept in S3 which is not supported. Routes will only be taken from function events",
+                resource_name
+            );
+            continue;
+        }
+
+        let definition_path = template_dir.join(&location);
+        debug!(
+            "Reading OpenAPI document for API {} from: {:?}",
+            resource_name, definition_path
+        );
+        let definition = fs::read_to_string(&definition_path)
+            .map_err(anyhow::Error::from)
+            .and_then(|d| serde_yaml::from_str::<Value>(&d).map_err(anyhow::Error::from));
+        match definition {
+            Ok(definition) => {
+                properties.remove("DefinitionUri");
+                properties.insert(Value::String("DefinitionBody".to_string()), definition);
+            }
+            Err(e) => {
+                warn!(
+                    "Unable to read OpenAPI document for API {}: {}. Skipping",
+                    resource_name, e
+                );
+            }
+        }
+    }
+}
+
 /// Recursively goes through directories to find all files that match a specific regex pattern.
 pub fn find_all_files(path: &impl AsRef<Path>, to_find: &str) -> std::io::Result<Vec<PathBuf>> {
     let mut buf = vec![];
@@ -44,7 +44,6 @@ pub async fn start(args: StartArgs) -> anyhow::Result<()> {
 
             let mut use_s3 = false;
             let mut use_postgres = false;
-            let mut use_sqs = false;
             for service in infrastructure {
                 match service {
                     Infrastructure::S3(_) => {
@@ -60,10 +59,7 @@ pub async fn start(args: StartArgs) -> anyhow::Result<()> {
                         use_postgres = true;
                     }
                     Infrastructure::Sqs(_) => {
-                        if !use_sqs {
-                            cmd_str.push_str("sqs-local ");
-                        }
-                        use_sqs = true;
+                        debug!("Queues are run within the invoker. Skipping...");
                     }
                     _ => {
                         warn!("Unsupported infrastructure type detected. Skipping...");
@@ -0,0 +1,15 @@
+mod redrive;
+
+use redrive::redrive;
+
+use crate::data::cli::QueueCommand;
+
+use tracing::debug;
+
+pub async fn get_queue_script(subcommand: QueueCommand) -> anyhow::Result<()> {
+    debug!("Getting queue script for subcommand: {:?}", subcommand);
+
+    match subcommand {
+        QueueCommand::Redrive => redrive().await,
+    }
+}
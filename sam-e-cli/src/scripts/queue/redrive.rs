@@ -0,0 +1,196 @@
+use crate::scripts::utils::{check_init, get_config};
+
+use aws_sdk_sqs::{
+    config::{Credentials, Region},
+    types::{DeleteMessageBatchRequestEntry, MessageSystemAttributeName},
+    Client,
+};
+use sam_e_types::config::infrastructure::Infrastructure;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// The local queue as exposed to the host by the environment
+const LOCAL_QUEUE_ENDPOINT: &str = "http://localhost:9324";
+const MAX_DEDUPLICATION_ID_LENGTH: usize = 128;
+
+/// Moves every message from a queue's dead-letter queue back to the queue, keeping their bodies
+/// and message attributes. Works against the running environment.
+pub async fn redrive() -> anyhow::Result<()> {
+    info!("Redriving messages from a dead-letter queue");
+
+    check_init()?;
+    let config = get_config()?;
+
+    let queues = config
+        .get_infrastructure()
+        .iter()
+        .filter_map(|infrastructure| match infrastructure {
+            Infrastructure::Sqs(queue) => Some(&queue.properties),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    // FIFO queues are named with a .fifo suffix, so the dead-letter queue's own properties are
+    // needed for its name
+    let redrive_queues = queues
+        .iter()
+        .filter_map(|queue| {
+            let redrive_policy = queue.redrive_policy.as_ref()?;
+            let dead_letter_queue_name = queues
+                .iter()
+                .find(|dead_letter_queue| dead_letter_queue.name == redrive_policy.dead_letter_queue)
+                .map(|dead_letter_queue| dead_letter_queue.get_queue_name())
+                .unwrap_or_else(|| redrive_policy.dead_letter_queue.to_string());
+            Some((queue.get_queue_name(), dead_letter_queue_name))
+        })
+        .collect::<Vec<_>>();
+
+    if redrive_queues.is_empty() {
+        warn!("No queues with a dead-letter queue found in the configuration");
+        return Ok(());
+    }
+
+    let queue_choice = dialoguer::Select::new()
+        .with_prompt("Select the queue to move its dead-letter queue messages back to")
+        .items(
+            &redrive_queues
+                .iter()
+                .map(|(queue, dead_letter_queue)| format!("{} (from {})", queue, dead_letter_queue))
+                .collect::<Vec<_>>(),
+        )
+        .interact()?;
+    let (queue, dead_letter_queue) = &redrive_queues[queue_choice];
+
+    // The local queue doesn't check credentials but the client needs some to sign requests with
+    let region = config.get_runtime().get_region().to_string();
+    let sdk_config = aws_config::from_env()
+        .region(Region::new(region))
+        .endpoint_url(LOCAL_QUEUE_ENDPOINT)
+        .credentials_provider(Credentials::new("local", "local", None, None, "sam-e"))
+        .load()
+        .await;
+    let client = Client::new(&sdk_config);
+
+    let queue_url = get_queue_url(&client, queue).await?;
+    let dead_letter_queue_url = get_queue_url(&client, dead_letter_queue).await?;
+
+    let mut moved = 0;
+    loop {
+        let messages = client
+            .receive_message()
+            .queue_url(&dead_letter_queue_url)
+            .max_number_of_messages(10)
+            .message_attribute_names("All")
+            .message_system_attribute_names(MessageSystemAttributeName::All)
+            .send()
+            .await?
+            .messages
+            .unwrap_or_default();
+
+        if messages.is_empty() {
+            break;
+        }
+
+        let mut delete_entries = vec![];
+        for (index, message) in messages.iter().enumerate() {
+            let mut send_message = client
+                .send_message()
+                .queue_url(&queue_url)
+                .set_message_body(message.body.clone())
+                .set_message_attributes(message.message_attributes.clone());
+
+            // FIFO queues need the message group to send to. The message was already sent with its
+            // deduplication ID (or content), so it's given a new one or it would be taken as a
+            // duplicate and dropped.
+            if let Some(attributes) = &message.attributes {
+                if let Some(message_group_id) =
+                    attributes.get(&MessageSystemAttributeName::MessageGroupId)
+                {
+                    let message_deduplication_id = attributes
+                        .get(&MessageSystemAttributeName::MessageDeduplicationId)
+                        .or(message.message_id.as_ref())
+                        .map(String::as_str)
+                        .unwrap_or_default();
+                    send_message = send_message
+                        .message_group_id(message_group_id)
+                        .message_deduplication_id(get_redrive_deduplication_id(
+                            message_deduplication_id,
+                        ));
+                }
+            }
+
+            // Messages are only removed from the dead-letter queue once they're on the queue again
+            let sent_message_id = match send_message.send().await {
+                Ok(output) => output.message_id,
+                Err(e) => {
+                    warn!("Failed to move message: {:?}: {}", message.message_id, e);
+                    continue;
+                }
+            };
+            if sent_message_id.is_none() || sent_message_id == message.message_id {
+                warn!(
+                    "Message: {:?} was taken as a duplicate and not moved",
+                    message.message_id
+                );
+                continue;
+            }
+            debug!("Moved message: {:?}", message.message_id);
+
+            delete_entries.push(
+                DeleteMessageBatchRequestEntry::builder()
+                    .id(index.to_string())
+                    .set_receipt_handle(message.receipt_handle.clone())
+                    .build()?,
+            );
+        }
+
+        if delete_entries.is_empty() {
+            // The messages that weren't moved stay hidden until their visibility timeout passes
+            continue;
+        }
+        moved += delete_entries.len();
+        client
+            .delete_message_batch()
+            .queue_url(&dead_letter_queue_url)
+            .set_entries(Some(delete_entries))
+            .send()
+            .await?;
+    }
+
+    info!(
+        "Moved {} messages from {} back to {}",
+        moved, dead_letter_queue, queue
+    );
+
+    Ok(())
+}
+
+/// A new deduplication ID for the message, based on its original one. Deduplication IDs can be up
+/// to 128 characters, so the original is shortened to fit.
+fn get_redrive_deduplication_id(message_deduplication_id: &str) -> String {
+    let suffix = Uuid::new_v4().simple().to_string();
+    let prefix = message_deduplication_id
+        .chars()
+        .take(MAX_DEDUPLICATION_ID_LENGTH - suffix.len() - 1)
+        .collect::<String>();
+
+    format!("{}-{}", prefix, suffix)
+}
+
+async fn get_queue_url(client: &Client, queue_name: &str) -> anyhow::Result<String> {
+    let queue_url = client
+        .get_queue_url()
+        .queue_name(queue_name)
+        .send()
+        .await
+        .map_err(|e| {
+            anyhow::anyhow!(
+                "Unable to find queue {}. Is the environment running? {}",
+                queue_name,
+                e
+            )
+        })?
+        .queue_url;
+
+    queue_url.ok_or_else(|| anyhow::anyhow!("No URL found for queue: {}", queue_name))
+}
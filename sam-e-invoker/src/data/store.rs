@@ -1,12 +1,15 @@
 use std::{collections::HashMap, sync::Arc};
 
-use parking_lot::RwLock;
+use axum::body::Bytes;
+use parking_lot::{Mutex, RwLock};
 use sam_e_types::{
     config::lambda::Lambda,
     invocation::Invocation,
 };
 use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::Receiver;
 use tracing::{debug, trace};
+use uuid::Uuid;
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct InvocationQueue {
@@ -30,9 +33,14 @@ impl InvocationQueue {
 
 pub type InvocationQueues = HashMap<String, InvocationQueue>;
 
+/// The chunks of a response the function is still streaming
+pub type ResponseStream = Receiver<Result<Bytes, axum::Error>>;
+
 #[derive(Clone, Debug)]
 pub struct Store {
     pub queues: Arc<RwLock<InvocationQueues>>,
+    /// Streamed responses, by request ID, waiting to be picked up by the invoke request
+    pub response_streams: Arc<Mutex<HashMap<Uuid, ResponseStream>>>,
 }
 
 impl Store {
@@ -49,6 +57,15 @@ impl Store {
 
         Store {
             queues: Arc::new(RwLock::new(invocation_queues)),
+            response_streams: Arc::new(Mutex::new(HashMap::new())),
         }
     }
+
+    pub fn add_response_stream(&self, request_id: Uuid, response_stream: ResponseStream) {
+        self.response_streams.lock().insert(request_id, response_stream);
+    }
+
+    pub fn take_response_stream(&self, request_id: &Uuid) -> Option<ResponseStream> {
+        self.response_streams.lock().remove(request_id)
+    }
 }
@@ -6,8 +6,9 @@ pub mod utils;
 
 use aws_lambda_events::encodings;
 use axum::{
+    body::Body,
     extract::State,
-    http::{HeaderMap, HeaderName, HeaderValue, StatusCode},
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
     response::{Html, IntoResponse},
     Json,
 };
@@ -18,7 +19,7 @@ use crate::{
     data::api::ApiState,
     api_response::AppError,
 };
-use sam_e_types::invocation::Invocation;
+use sam_e_types::invocation::{EventRequest, Invocation};
 
 pub async fn invoke(
     State(api_state): State<ApiState>,
@@ -60,7 +61,16 @@ pub async fn invoke(
     info!("Returning response with headers: {:?}", header_map);
     info!("Headers in response: {:?}", &res_body.headers);
 
-    let response = if let Some(response_body) = &res_body.body {
+    let mut response = if let Some(response_stream) = store.take_response_stream(&request_id) {
+        debug!("Detected streamed response. Returning the body as it is streamed.");
+        let chunks = futures_util::stream::unfold(response_stream, |mut response_stream| async {
+            response_stream
+                .recv()
+                .await
+                .map(|chunk| (chunk, response_stream))
+        });
+        (status_code, Body::from_stream(chunks)).into_response()
+    } else if let Some(response_body) = &res_body.body {
         trace!("Returning response with body: {:?}", response_body);
         match response_body {
             encodings::Body::Text(text) => {
@@ -93,5 +103,29 @@ pub async fn invoke(
         "No Response body found".into_response()
     };
 
+    // Passes on the headers set by the function so the source can return them to the caller. The
+    // content type has already been set from the body above.
+    let response_headers = response.headers_mut();
+    for (key, value) in res_body.headers.iter() {
+        if key != header::CONTENT_TYPE && key != header::CONTENT_LENGTH {
+            response_headers.insert(key.clone(), value.clone());
+        }
+    }
+    for (key, value) in res_body.multi_value_headers.iter() {
+        if key != header::CONTENT_TYPE
+            && key != header::CONTENT_LENGTH
+            && !res_body.headers.contains_key(key)
+        {
+            response_headers.append(key.clone(), value.clone());
+        }
+    }
+
+    // Function URLs return the body with whatever content type the function gave it
+    if let EventRequest::FunctionUrl(_) = processed_invocation.get_request() {
+        if let Some(content_type) = res_body.headers.get(header::CONTENT_TYPE) {
+            response_headers.insert(header::CONTENT_TYPE, content_type.clone());
+        }
+    }
+
     Ok(response)
 }
@@ -1,16 +1,21 @@
-use crate::data::api::ApiState;
+use crate::data::{api::ApiState, store::InvocationQueue};
+use sam_e_types::invocation::{EventRequest, Status};
 
+use aws_lambda_events::{apigw::ApiGatewayProxyResponse, encodings::Body};
 use axum::{
     extract::{Json, Path, State},
     http::HeaderMap,
 };
-use tracing::{debug, info};
+use tracing::{debug, error, info};
 use uuid::Uuid;
 
+/// Handles an error reported by the Lambda runtime. The invocation is marked as processed with a
+/// 502 response so that whoever requested it isn't left waiting on a response that will never
+/// arrive.
 pub async fn response_handler(
     headers: HeaderMap,
     Path((container_name, request_id)): Path<(String, Uuid)>,
-    State(_api_state): State<ApiState>,
+    State(api_state): State<ApiState>,
     body: Option<Json<serde_json::Value>>,
 ) -> Json<serde_json::Value> {
     info!("Error with invocation. See logs for details");
@@ -18,6 +23,43 @@ pub async fn response_handler(
     debug!("Container name: {:?}", container_name);
     debug!("Request ID: {:?}", request_id);
     debug!("Error Body: {:?}", body);
+
+    let error_body = body.map(|b| b.0).unwrap_or(serde_json::Value::Null);
+
+    let store = api_state.get_store();
+    let mut store_queues = store.queues.write();
+
+    match store_queues
+        .entry(container_name)
+        .or_insert(InvocationQueue::new())
+        .get_invocations_mut()
+        .iter_mut()
+        .find(|invocation| invocation.get_request_id() == &request_id)
+    {
+        Some(invocation) => {
+            let response_body = match invocation.get_request() {
+                // API Gateway hides the details of the error from the caller
+                EventRequest::Api(_) => serde_json::json!({
+                    "message": "Internal server error"
+                }),
+                EventRequest::FunctionUrl(_) => serde_json::json!({
+                    "Message": "Internal Server Error"
+                }),
+                _ => error_body,
+            };
+
+            invocation.set_response(ApiGatewayProxyResponse {
+                status_code: 502,
+                body: Some(Body::Text(response_body.to_string())),
+                ..Default::default()
+            });
+            invocation.set_status(Status::Processed);
+        }
+        None => {
+            error!("No invocation found for the reported error");
+        }
+    }
+
     Json(serde_json::json!({
         "error": "Invocation error. See logs for details"
     }))
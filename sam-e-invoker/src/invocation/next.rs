@@ -3,7 +3,7 @@ use crate::data::{
     store::InvocationQueue,
 };
 
-use sam_e_types::invocation::{EventRequest, Status};
+use sam_e_types::invocation::{AuthorizerRequest, EventRequest, Status};
 
 use axum::{
     extract::{Path, State},
@@ -99,7 +99,7 @@ pub async fn request_handler(
 
                 let data_as_value = serde_json::to_value(api_request).unwrap();
 
-                return (
+                (
                     StatusCode::OK,
                     [
                         (
@@ -109,14 +109,14 @@ pub async fn request_handler(
                         ("lambda-runtime-deadline-ms", dt.timestamp().to_string()),
                     ],
                     Json(data_as_value),
-                );
+                )
             }
             EventRequest::Sqs(sqs_request) => {
                 debug!("Processing an SQS invocation");
 
                 let data_as_value = serde_json::to_value(sqs_request).unwrap();
 
-                return (
+                (
                     StatusCode::OK,
                     [
                         (
@@ -126,11 +126,84 @@ pub async fn request_handler(
                         ("lambda-runtime-deadline-ms", dt.timestamp().to_string()),
                     ],
                     Json(data_as_value),
-                );
+                )
+            }
+            EventRequest::Authorizer(authorizer_request) => {
+                debug!("Processing an authorizer invocation");
+
+                let data_as_value = match authorizer_request {
+                    AuthorizerRequest::Token(request) => serde_json::to_value(request),
+                    AuthorizerRequest::Request(request) => serde_json::to_value(request),
+                    AuthorizerRequest::HttpV2(request) => serde_json::to_value(request),
+                }
+                .unwrap();
+
+                (
+                    StatusCode::OK,
+                    [
+                        (
+                            "lambda-runtime-aws-request-id",
+                            invocation_data.get_request_id().to_string(),
+                        ),
+                        ("lambda-runtime-deadline-ms", dt.timestamp().to_string()),
+                    ],
+                    Json(data_as_value),
+                )
+            }
+            EventRequest::WebSocket(websocket_request) => {
+                debug!("Processing a WebSocket invocation");
+
+                let data_as_value = serde_json::to_value(websocket_request).unwrap();
+
+                (
+                    StatusCode::OK,
+                    [
+                        (
+                            "lambda-runtime-aws-request-id",
+                            invocation_data.get_request_id().to_string(),
+                        ),
+                        ("lambda-runtime-deadline-ms", dt.timestamp().to_string()),
+                    ],
+                    Json(data_as_value),
+                )
+            }
+            EventRequest::FunctionUrl(function_url_request) => {
+                debug!("Processing a function URL invocation");
+
+                let data_as_value = serde_json::to_value(function_url_request).unwrap();
+
+                (
+                    StatusCode::OK,
+                    [
+                        (
+                            "lambda-runtime-aws-request-id",
+                            invocation_data.get_request_id().to_string(),
+                        ),
+                        ("lambda-runtime-deadline-ms", dt.timestamp().to_string()),
+                    ],
+                    Json(data_as_value),
+                )
+            }
+            EventRequest::S3(s3_request) => {
+                debug!("Processing an S3 invocation");
+
+                let data_as_value = serde_json::to_value(s3_request).unwrap();
+
+                (
+                    StatusCode::OK,
+                    [
+                        (
+                            "lambda-runtime-aws-request-id",
+                            invocation_data.get_request_id().to_string(),
+                        ),
+                        ("lambda-runtime-deadline-ms", dt.timestamp().to_string()),
+                    ],
+                    Json(data_as_value),
+                )
             }
         }
     } else {
-        return (
+        (
             StatusCode::OK,
             [
                 (
@@ -142,6 +215,6 @@ pub async fn request_handler(
             Json(serde_json::json!({
                 "message": "No pending invocations found"
             })),
-        );
+        )
     }
 }
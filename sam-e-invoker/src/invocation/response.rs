@@ -1,26 +1,52 @@
-use crate::data::store::InvocationQueue; 
+use crate::data::store::{InvocationQueue, Store};
 use sam_e_types::invocation::{EventRequest, Status};
 
-use aws_lambda_events::apigw::ApiGatewayProxyResponse;
+use aws_lambda_events::{apigw::ApiGatewayProxyResponse, encodings::Body};
 use axum::{
-    body::Bytes,
+    body::{to_bytes, Body as RequestBody, Bytes},
     debug_handler,
     extract::{Path, State},
-    http::{HeaderMap, StatusCode},
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
     response::IntoResponse,
 };
+use base64::{engine::general_purpose::STANDARD, Engine};
+use futures_util::StreamExt;
+use serde::Deserialize;
 use std::{collections::HashMap, str};
+use tokio::sync::mpsc;
 use tracing::{debug, error, info, trace};
 use uuid::Uuid;
 
 use crate::data::api::ApiState;
 
+/// The content type of a streamed response that starts with a prelude of the status code, headers
+/// and cookies
+const HTTP_INTEGRATION_RESPONSE: &str = "application/vnd.awslambda.http-integration-response";
+
+/// The number of streamed chunks held for the invoke request before the function has to wait
+const RESPONSE_STREAM_BUFFER: usize = 16;
+
+/// A function URL response, or the prelude of a streamed one
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FunctionUrlResponse {
+    #[serde(default)]
+    status_code: u16,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    #[serde(default)]
+    cookies: Vec<String>,
+    body: Option<String>,
+    #[serde(default)]
+    is_base64_encoded: bool,
+}
+
 #[debug_handler]
 pub async fn response_handler(
     headers: HeaderMap,
     Path((container_name, request_id)): Path<(String, Uuid)>,
     State(api_state): State<ApiState>,
-    body: Bytes,
+    body: RequestBody,
 ) -> impl IntoResponse {
     info!(
         "Response detected from lambda runtime for container: {}",
@@ -36,6 +62,29 @@ pub async fn response_handler(
         })
         .collect();
 
+    let is_streamed = headers
+        .get("lambda-runtime-function-response-mode")
+        .is_some_and(|mode| mode == "streaming");
+    if is_streamed {
+        return stream_function_url_response(
+            store,
+            &container_name,
+            request_id,
+            &headers,
+            headers_hashmap,
+            body,
+        )
+        .await;
+    }
+
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => {
+            error!("Unable to read the response from the lambda runtime: {}", e);
+            return StatusCode::BAD_REQUEST;
+        }
+    };
+
     // Write the response record
     let write_store = store.clone();
     let write_queue = InvocationQueue::new();
@@ -75,8 +124,66 @@ pub async fn response_handler(
                 }
                 EventRequest::Sqs(_) => {
                     debug!("Detected event source as SQS");
+                    // The SQS source deletes the messages once the function has succeeded, other
+                    // than any batch item failures it returns
+                    let response_data = ApiGatewayProxyResponse {
+                        status_code: 200,
+                        body: Some(Body::Text(String::from_utf8_lossy(&body).to_string())),
+                        ..Default::default()
+                    };
+
+                    invocation.set_response(response_data);
+                    invocation.set_response_headers(headers_hashmap);
+                }
+                EventRequest::S3(_) => {
+                    debug!("Detected event source as S3");
+                    // S3 invokes functions asynchronously so nothing is done with the response
+                    // other than reporting it back to the S3 source
+                    let response_data = ApiGatewayProxyResponse {
+                        status_code: 200,
+                        body: Some(Body::Text(String::from_utf8_lossy(&body).to_string())),
+                        ..Default::default()
+                    };
+
+                    invocation.set_response(response_data);
+                    invocation.set_response_headers(headers_hashmap);
+                }
+                EventRequest::WebSocket(_) => {
+                    debug!("Detected event source as a WebSocket API");
+                    // WebSocket functions don't have to return anything, in which case the route
+                    // is treated as successful
+                    let mut response_data = serde_json::from_slice::<ApiGatewayProxyResponse>(&body)
+                        .unwrap_or_default();
+                    if response_data.status_code == 0 {
+                        response_data.status_code = 200;
+                    }
+                    if response_data.body.is_none() {
+                        response_data.body = Some(Body::Text(String::new()));
+                    }
+
+                    invocation.set_response(response_data);
+                    invocation.set_response_headers(headers_hashmap);
+                }
+                EventRequest::FunctionUrl(_) => {
+                    debug!("Detected event source as a function URL");
+                    let response_data = function_url_response(&body);
+                    set_function_url_headers(&mut headers_hashmap, &response_data);
+
+                    invocation.set_response(response_data);
+                    invocation.set_response_headers(headers_hashmap);
+                }
+                EventRequest::Authorizer(_) => {
+                    debug!("Detected event source as an authorizer");
+                    // The authorizer response is a policy (or simple response) rather than an
+                    // API response so is passed back to the API source untouched
+                    let response_data = ApiGatewayProxyResponse {
+                        status_code: 200,
+                        body: Some(Body::Text(String::from_utf8_lossy(&body).to_string())),
+                        ..Default::default()
+                    };
+
+                    invocation.set_response(response_data);
                     invocation.set_response_headers(headers_hashmap);
-                    // TODO: delete the message on success: currently happens on invocation
                 }
             }
 
@@ -90,3 +197,182 @@ pub async fn response_handler(
         }
     }
 }
+
+/// Function URLs take either a full response (anything with a status code) or any other JSON
+/// value, which is returned as the body
+fn function_url_response(body: &Bytes) -> ApiGatewayProxyResponse {
+    let (function_url_response, payload) =
+        match serde_json::from_slice::<serde_json::Value>(body) {
+            Ok(value) if value.get("statusCode").is_some() => {
+                let mut full_response =
+                    serde_json::from_value::<FunctionUrlResponse>(value).unwrap_or_default();
+                let body = full_response.body.take().unwrap_or_default();
+                let payload = if full_response.is_base64_encoded {
+                    STANDARD.decode(&body).unwrap_or_else(|e| {
+                        error!("Unable to decode base64 function URL response: {}", e);
+                        body.into_bytes()
+                    })
+                } else {
+                    body.into_bytes()
+                };
+                (full_response, payload)
+            }
+            _ => {
+                let value_response = FunctionUrlResponse {
+                    headers: HashMap::from([(
+                        header::CONTENT_TYPE.to_string(),
+                        "application/json".to_string(),
+                    )]),
+                    ..Default::default()
+                };
+                (value_response, body.to_vec())
+            }
+        };
+
+    let body = match String::from_utf8(payload) {
+        Ok(text) => Body::Text(text),
+        Err(e) => Body::Binary(e.into_bytes()),
+    };
+
+    into_proxy_response(function_url_response, Some(body))
+}
+
+/// Functions with an InvokeMode of RESPONSE_STREAM send a prelude (the same as a full response
+/// without the body) then eight null bytes then the body. Once the prelude has been read the
+/// invocation is processed, and the rest of the body is passed on to the invoke request as the
+/// function sends it.
+async fn stream_function_url_response(
+    store: &Store,
+    container_name: &str,
+    request_id: Uuid,
+    headers: &HeaderMap,
+    mut headers_hashmap: HashMap<String, String>,
+    body: RequestBody,
+) -> StatusCode {
+    debug!("Function URL response is being streamed");
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("application/json");
+    let mut chunks = body.into_data_stream();
+
+    let (prelude, first_chunk) = if content_type == HTTP_INTEGRATION_RESPONSE {
+        let mut buffer: Vec<u8> = vec![];
+        loop {
+            match chunks.next().await {
+                Some(Ok(chunk)) => {
+                    buffer.extend_from_slice(&chunk);
+                    if let Some(position) = buffer.windows(8).position(|w| w == [0; 8]) {
+                        let prelude = serde_json::from_slice::<FunctionUrlResponse>(
+                            &buffer[..position],
+                        )
+                        .unwrap_or_default();
+                        break (prelude, Bytes::copy_from_slice(&buffer[position + 8..]));
+                    }
+                }
+                Some(Err(e)) => {
+                    error!("Unable to read the streamed function URL response: {}", e);
+                    return StatusCode::BAD_REQUEST;
+                }
+                None => {
+                    let prelude =
+                        serde_json::from_slice::<FunctionUrlResponse>(&buffer).unwrap_or_default();
+                    break (prelude, Bytes::new());
+                }
+            }
+        }
+    } else {
+        let plain_response = FunctionUrlResponse {
+            headers: HashMap::from([(header::CONTENT_TYPE.to_string(), content_type.to_string())]),
+            ..Default::default()
+        };
+        (plain_response, Bytes::new())
+    };
+
+    let (sender, receiver) = mpsc::channel(RESPONSE_STREAM_BUFFER);
+    store.add_response_stream(request_id, receiver);
+
+    let response_data = into_proxy_response(prelude, None);
+    set_function_url_headers(&mut headers_hashmap, &response_data);
+
+    {
+        let mut store_queues = store.queues.write();
+        let Some(invocation) = store_queues
+            .entry(container_name.to_string())
+            .or_insert_with(InvocationQueue::new)
+            .get_invocations_mut()
+            .iter_mut()
+            .find(|invocation| invocation.get_request_id() == &request_id)
+        else {
+            error!("No invocation found to stream the response to");
+            store.take_response_stream(&request_id);
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        };
+
+        invocation.set_response(response_data);
+        invocation.set_response_headers(headers_hashmap);
+        invocation.set_status(Status::Processed);
+        trace!("Streaming invocation... {:?}", invocation);
+    }
+
+    if !first_chunk.is_empty() && sender.send(Ok(first_chunk)).await.is_err() {
+        debug!("Function URL request closed before the response finished streaming");
+        return StatusCode::OK;
+    }
+    while let Some(chunk) = chunks.next().await {
+        if sender.send(chunk).await.is_err() {
+            debug!("Function URL request closed before the response finished streaming");
+            break;
+        }
+    }
+
+    StatusCode::OK
+}
+
+/// Passes the function's headers on in place of the runtime's, whose content type says nothing
+/// about the function's response
+fn set_function_url_headers(
+    headers_hashmap: &mut HashMap<String, String>,
+    response_data: &ApiGatewayProxyResponse,
+) {
+    headers_hashmap.remove(header::CONTENT_TYPE.as_str());
+    for (key, value) in response_data.headers.iter() {
+        let value_string: &str = str::from_utf8(value.as_bytes()).unwrap_or("unknown");
+        headers_hashmap.insert(key.to_string(), value_string.to_owned());
+    }
+}
+
+/// Converts a function URL response, or the prelude of a streamed one, into the response the
+/// invoke request reads
+fn into_proxy_response(
+    function_url_response: FunctionUrlResponse,
+    body: Option<Body>,
+) -> ApiGatewayProxyResponse {
+    let mut response_headers = HeaderMap::new();
+    for (key, value) in function_url_response.headers.iter() {
+        if let (Ok(key), Ok(value)) = (
+            HeaderName::try_from(key.as_str()),
+            HeaderValue::try_from(value.as_str()),
+        ) {
+            response_headers.insert(key, value);
+        }
+    }
+
+    let mut multi_value_headers = HeaderMap::new();
+    for cookie in function_url_response.cookies.iter() {
+        if let Ok(cookie) = HeaderValue::try_from(cookie.as_str()) {
+            multi_value_headers.append(header::SET_COOKIE, cookie);
+        }
+    }
+
+    ApiGatewayProxyResponse {
+        status_code: match function_url_response.status_code {
+            0 => 200,
+            status_code => status_code.into(),
+        },
+        headers: response_headers,
+        multi_value_headers,
+        body,
+        is_base64_encoded: false,
+    }
+}
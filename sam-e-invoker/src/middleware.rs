@@ -1,7 +1,7 @@
 use aws_lambda_events::apigw::ApiGatewayProxyResponse;
 use axum::{
     body::{to_bytes, Body, Bytes},
-    http::{header, Method, Request, StatusCode},
+    http::{Request, StatusCode},
     middleware::Next,
     response::IntoResponse,
 };
@@ -11,8 +11,8 @@ use tracing::trace;
 pub fn cors_layer() -> CorsLayer {
     CorsLayer::new()
         .allow_origin(Any)
-        .allow_methods([Method::GET, Method::POST, Method::PATCH, Method::DELETE])
-        .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE])
+        .allow_methods(Any)
+        .allow_headers(Any)
 }
 
 // Adds headers to the request if they're in body and not in request itself - Something that
@@ -20,6 +20,15 @@ pub fn cors_layer() -> CorsLayer {
 pub async fn headers_mw(req: Request<Body>, next: Next) -> impl IntoResponse {
     trace!("Request = {:#?}", req);
 
+    // Streamed responses are passed on as they arrive so can't be read here
+    if req
+        .headers()
+        .get("lambda-runtime-function-response-mode")
+        .is_some_and(|mode| mode == "streaming")
+    {
+        return next.run(req).await;
+    }
+
     let (mut parts, body) = req.into_parts();
     let body_bytes = body_to_bytes("middleware request", body).await.unwrap();
 
@@ -0,0 +1,338 @@
+pub mod store;
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The protocol a request was made with, which the response has to use too. Newer SDKs use the
+/// JSON protocol while older SDKs and tools still send query (form encoded) requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    Json,
+    Query,
+}
+
+#[derive(Debug)]
+pub struct SqsRequest {
+    pub protocol: Protocol,
+    /// The host the request was sent to, used for the queue URLs given back so they work from
+    /// wherever the request came from
+    pub host: String,
+    pub action: SqsAction,
+}
+
+#[derive(Debug)]
+pub enum SqsAction {
+    CreateQueue(CreateQueueRequest),
+    GetQueueUrl(GetQueueUrlRequest),
+    ListQueues(ListQueuesRequest),
+    DeleteQueue(QueueUrlRequest),
+    PurgeQueue(QueueUrlRequest),
+    GetQueueAttributes(GetQueueAttributesRequest),
+    SetQueueAttributes(SetQueueAttributesRequest),
+    TagQueue(TagQueueRequest),
+    UntagQueue(UntagQueueRequest),
+    ListQueueTags(QueueUrlRequest),
+    SendMessage(SendMessageRequest),
+    SendMessageBatch(SendMessageBatchRequest),
+    ReceiveMessage(ReceiveMessageRequest),
+    DeleteMessage(DeleteMessageRequest),
+    DeleteMessageBatch(DeleteMessageBatchRequest),
+    ChangeMessageVisibility(ChangeMessageVisibilityRequest),
+    ChangeMessageVisibilityBatch(ChangeMessageVisibilityBatchRequest),
+}
+
+impl SqsAction {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            SqsAction::CreateQueue(_) => "CreateQueue",
+            SqsAction::GetQueueUrl(_) => "GetQueueUrl",
+            SqsAction::ListQueues(_) => "ListQueues",
+            SqsAction::DeleteQueue(_) => "DeleteQueue",
+            SqsAction::PurgeQueue(_) => "PurgeQueue",
+            SqsAction::GetQueueAttributes(_) => "GetQueueAttributes",
+            SqsAction::SetQueueAttributes(_) => "SetQueueAttributes",
+            SqsAction::TagQueue(_) => "TagQueue",
+            SqsAction::UntagQueue(_) => "UntagQueue",
+            SqsAction::ListQueueTags(_) => "ListQueueTags",
+            SqsAction::SendMessage(_) => "SendMessage",
+            SqsAction::SendMessageBatch(_) => "SendMessageBatch",
+            SqsAction::ReceiveMessage(_) => "ReceiveMessage",
+            SqsAction::DeleteMessage(_) => "DeleteMessage",
+            SqsAction::DeleteMessageBatch(_) => "DeleteMessageBatch",
+            SqsAction::ChangeMessageVisibility(_) => "ChangeMessageVisibility",
+            SqsAction::ChangeMessageVisibilityBatch(_) => "ChangeMessageVisibilityBatch",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct CreateQueueRequest {
+    pub queue_name: String,
+    #[serde(default)]
+    pub attributes: HashMap<String, String>,
+    /// Lowercase in JSON requests, unlike every other field
+    #[serde(default, rename = "tags", alias = "Tags")]
+    pub tags: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct GetQueueUrlRequest {
+    pub queue_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ListQueuesRequest {
+    pub queue_name_prefix: Option<String>,
+    pub max_results: Option<usize>,
+}
+
+/// Any request that only needs the queue it's for
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct QueueUrlRequest {
+    pub queue_url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct GetQueueAttributesRequest {
+    pub queue_url: String,
+    #[serde(default)]
+    pub attribute_names: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SetQueueAttributesRequest {
+    pub queue_url: String,
+    pub attributes: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TagQueueRequest {
+    pub queue_url: String,
+    pub tags: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct UntagQueueRequest {
+    pub queue_url: String,
+    pub tag_keys: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct MessageAttributeValue {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub string_value: Option<String>,
+    /// Base64 encoded, as binary values are sent in both protocols
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub binary_value: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub string_list_values: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub binary_list_values: Vec<String>,
+    pub data_type: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SendMessageRequest {
+    pub queue_url: String,
+    #[serde(flatten)]
+    pub message: SendMessageEntry,
+}
+
+/// The message being sent, shared by single and batch sends
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SendMessageEntry {
+    pub message_body: String,
+    pub delay_seconds: Option<u64>,
+    #[serde(default)]
+    pub message_attributes: HashMap<String, MessageAttributeValue>,
+    #[serde(default)]
+    pub message_system_attributes: HashMap<String, MessageAttributeValue>,
+    pub message_group_id: Option<String>,
+    pub message_deduplication_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SendMessageBatchRequest {
+    pub queue_url: String,
+    pub entries: Vec<SendMessageBatchRequestEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SendMessageBatchRequestEntry {
+    pub id: String,
+    #[serde(flatten)]
+    pub message: SendMessageEntry,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ReceiveMessageRequest {
+    pub queue_url: String,
+    pub max_number_of_messages: Option<usize>,
+    pub visibility_timeout: Option<u64>,
+    pub wait_time_seconds: Option<u64>,
+    /// The older way of asking for system attributes, still sent by some SDKs
+    #[serde(default)]
+    pub attribute_names: Vec<String>,
+    #[serde(default)]
+    pub message_system_attribute_names: Vec<String>,
+    #[serde(default)]
+    pub message_attribute_names: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct DeleteMessageRequest {
+    pub queue_url: String,
+    pub receipt_handle: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct DeleteMessageBatchRequest {
+    pub queue_url: String,
+    pub entries: Vec<DeleteMessageBatchRequestEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct DeleteMessageBatchRequestEntry {
+    pub id: String,
+    pub receipt_handle: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ChangeMessageVisibilityRequest {
+    pub queue_url: String,
+    pub receipt_handle: String,
+    pub visibility_timeout: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ChangeMessageVisibilityBatchRequest {
+    pub queue_url: String,
+    pub entries: Vec<ChangeMessageVisibilityBatchRequestEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ChangeMessageVisibilityBatchRequestEntry {
+    pub id: String,
+    pub receipt_handle: String,
+    pub visibility_timeout: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct QueueUrlResponse {
+    pub queue_url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ListQueuesResponse {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub queue_urls: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct GetQueueAttributesResponse {
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub attributes: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ListQueueTagsResponse {
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub tags: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SendMessageResponse {
+    pub message_id: String,
+    #[serde(rename = "MD5OfMessageBody")]
+    pub md5_of_message_body: String,
+    #[serde(
+        rename = "MD5OfMessageAttributes",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub md5_of_message_attributes: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sequence_number: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SendMessageBatchResultEntry {
+    pub id: String,
+    #[serde(flatten)]
+    pub result: SendMessageResponse,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ReceivedMessage {
+    pub message_id: String,
+    pub receipt_handle: String,
+    #[serde(rename = "MD5OfBody")]
+    pub md5_of_body: String,
+    pub body: String,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub attributes: HashMap<String, String>,
+    #[serde(
+        rename = "MD5OfMessageAttributes",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub md5_of_message_attributes: Option<String>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub message_attributes: HashMap<String, MessageAttributeValue>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ReceiveMessageResponse {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub messages: Vec<ReceivedMessage>,
+}
+
+/// The result of a batch request. Entries that failed are listed separately rather than failing
+/// the whole request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct BatchResponse<T> {
+    pub successful: Vec<T>,
+    pub failed: Vec<BatchResultErrorEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct BatchResultEntry {
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct BatchResultErrorEntry {
+    pub id: String,
+    pub sender_fault: bool,
+    pub code: String,
+    pub message: String,
+}
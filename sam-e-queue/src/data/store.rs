@@ -0,0 +1,838 @@
+use crate::{
+    data::{
+        CreateQueueRequest, MessageAttributeValue, ReceiveMessageRequest, ReceivedMessage,
+        SendMessageEntry, SendMessageResponse,
+    },
+    response::SqsError,
+    utils::{get_md5_of_message_attributes, get_message_attributes_size, md5_hex, sha256_hex},
+};
+use sam_e_types::config::Config;
+
+use parking_lot::RwLock;
+use serde_json::Value;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+use tracing::{debug, trace, warn};
+use uuid::Uuid;
+
+/// Attributes every queue has, with the values SQS gives them when they aren't set
+const DEFAULT_QUEUE_ATTRIBUTES: [(&str, &str); 5] = [
+    ("VisibilityTimeout", "30"),
+    ("DelaySeconds", "0"),
+    ("MessageRetentionPeriod", "345600"),
+    ("MaximumMessageSize", "262144"),
+    ("ReceiveMessageWaitTimeSeconds", "0"),
+];
+
+/// Attributes that can be set but aren't used locally
+const OTHER_QUEUE_ATTRIBUTES: [&str; 10] = [
+    "RedrivePolicy",
+    "RedriveAllowPolicy",
+    "FifoQueue",
+    "ContentBasedDeduplication",
+    "DeduplicationScope",
+    "FifoThroughputLimit",
+    "Policy",
+    "KmsMasterKeyId",
+    "KmsDataKeyReusePeriodSeconds",
+    "SqsManagedSseEnabled",
+];
+
+/// The system attributes a message can be received with
+const MESSAGE_SYSTEM_ATTRIBUTES: [&str; 9] = [
+    "SenderId",
+    "SentTimestamp",
+    "ApproximateReceiveCount",
+    "ApproximateFirstReceiveTimestamp",
+    "SequenceNumber",
+    "MessageDeduplicationId",
+    "MessageGroupId",
+    "AWSTraceHeader",
+    "DeadLetterQueueSourceArn",
+];
+
+/// How long a FIFO queue remembers deduplication IDs for
+const DEDUPLICATION_INTERVAL_MILLIS: i64 = 5 * 60 * 1000;
+
+const MAX_VISIBILITY_TIMEOUT: u64 = 43200;
+const MAX_DELAY_SECONDS: u64 = 900;
+
+#[derive(Debug, Clone)]
+struct StoredMessage {
+    message_id: String,
+    body: String,
+    md5_of_body: String,
+    message_attributes: HashMap<String, MessageAttributeValue>,
+    aws_trace_header: Option<String>,
+    sent_timestamp: i64,
+    /// When the message can next be received, after its delay or visibility timeout
+    visible_at: i64,
+    receive_count: u32,
+    first_receive_timestamp: Option<i64>,
+    /// The handle from the latest time the message was received
+    receipt_handle: Option<String>,
+    message_group_id: Option<String>,
+    message_deduplication_id: Option<String>,
+    sequence_number: Option<String>,
+    dead_letter_queue_source_arn: Option<String>,
+}
+
+impl StoredMessage {
+    /// Received but not yet deleted or made visible again
+    fn is_in_flight(&self, now: i64) -> bool {
+        self.receive_count > 0 && self.visible_at > now
+    }
+
+    fn is_delayed(&self, now: i64) -> bool {
+        self.receive_count == 0 && self.visible_at > now
+    }
+
+    fn to_received_message(
+        &self,
+        sender_id: &str,
+        system_attribute_names: &[String],
+        message_attribute_names: &[String],
+    ) -> ReceivedMessage {
+        let all_system_attributes = system_attribute_names.iter().any(|name| name == "All");
+        let attributes = [
+            ("SenderId", Some(sender_id.to_string())),
+            ("SentTimestamp", Some(self.sent_timestamp.to_string())),
+            (
+                "ApproximateReceiveCount",
+                Some(self.receive_count.to_string()),
+            ),
+            (
+                "ApproximateFirstReceiveTimestamp",
+                self.first_receive_timestamp.map(|t| t.to_string()),
+            ),
+            ("SequenceNumber", self.sequence_number.clone()),
+            (
+                "MessageDeduplicationId",
+                self.message_deduplication_id.clone(),
+            ),
+            ("MessageGroupId", self.message_group_id.clone()),
+            ("AWSTraceHeader", self.aws_trace_header.clone()),
+            (
+                "DeadLetterQueueSourceArn",
+                self.dead_letter_queue_source_arn.clone(),
+            ),
+        ]
+        .into_iter()
+        .filter(|(name, _)| {
+            all_system_attributes || system_attribute_names.iter().any(|n| n == name)
+        })
+        .filter_map(|(name, value)| Some((name.to_string(), value?)))
+        .collect::<HashMap<_, _>>();
+
+        let message_attributes = self
+            .message_attributes
+            .iter()
+            .filter(|(name, _)| {
+                message_attribute_names.iter().any(|requested| {
+                    requested == "All"
+                        || requested == ".*"
+                        || requested == *name
+                        || requested
+                            .strip_suffix(".*")
+                            .is_some_and(|prefix| name.starts_with(prefix))
+                })
+            })
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect::<HashMap<_, _>>();
+        let md5_of_message_attributes = (!message_attributes.is_empty())
+            .then(|| get_md5_of_message_attributes(&message_attributes));
+
+        ReceivedMessage {
+            message_id: self.message_id.clone(),
+            receipt_handle: self.receipt_handle.clone().unwrap_or_default(),
+            md5_of_body: self.md5_of_body.clone(),
+            body: self.body.clone(),
+            attributes,
+            md5_of_message_attributes,
+            message_attributes,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Queue {
+    name: String,
+    attributes: HashMap<String, String>,
+    tags: HashMap<String, String>,
+    created_timestamp: i64,
+    last_modified_timestamp: i64,
+    messages: Vec<StoredMessage>,
+    sequence_number: u64,
+    /// Deduplication IDs sent in the deduplication interval, with when they were sent and the
+    /// message that was sent with them
+    deduplication_ids: HashMap<String, (i64, SendMessageResponse)>,
+}
+
+impl Queue {
+    fn new(
+        name: String,
+        attributes: HashMap<String, String>,
+        tags: HashMap<String, String>,
+    ) -> Self {
+        let mut queue_attributes = DEFAULT_QUEUE_ATTRIBUTES
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect::<HashMap<_, _>>();
+        queue_attributes.extend(attributes);
+        let now = chrono::Utc::now().timestamp();
+
+        Self {
+            name,
+            attributes: queue_attributes,
+            tags,
+            created_timestamp: now,
+            last_modified_timestamp: now,
+            messages: vec![],
+            sequence_number: 0,
+            deduplication_ids: HashMap::new(),
+        }
+    }
+
+    pub fn is_fifo(&self) -> bool {
+        self.attributes
+            .get("FifoQueue")
+            .is_some_and(|v| v == "true")
+    }
+
+    fn is_content_based_deduplication(&self) -> bool {
+        self.attributes
+            .get("ContentBasedDeduplication")
+            .is_some_and(|v| v == "true")
+    }
+
+    fn get_number_attribute(&self, name: &str) -> u64 {
+        self.attributes
+            .get(name)
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or_default()
+    }
+
+    /// The name of the dead-letter queue and the max receive count from the redrive policy
+    fn get_redrive_policy(&self) -> Option<(String, u32)> {
+        let redrive_policy =
+            serde_json::from_str::<Value>(self.attributes.get("RedrivePolicy")?).ok()?;
+        let dead_letter_queue = redrive_policy
+            .get("deadLetterTargetArn")?
+            .as_str()?
+            .rsplit(':')
+            .next()?
+            .to_string();
+        let max_receive_count = match redrive_policy.get("maxReceiveCount")? {
+            Value::String(count) => count.parse::<u32>().ok()?,
+            count => count.as_u64()? as u32,
+        };
+
+        Some((dead_letter_queue, max_receive_count))
+    }
+
+    fn remove_expired_messages(&mut self, now: i64) {
+        let retention_period = self.get_number_attribute("MessageRetentionPeriod") as i64 * 1000;
+        self.messages
+            .retain(|message| now - message.sent_timestamp < retention_period);
+    }
+
+    fn send_message(&mut self, message: SendMessageEntry) -> Result<SendMessageResponse, SqsError> {
+        let now = chrono::Utc::now().timestamp_millis();
+
+        if message.message_body.is_empty() {
+            return Err(SqsError::invalid_parameter_value(
+                "The request must contain the parameter MessageBody.",
+            ));
+        }
+        let maximum_message_size = self.get_number_attribute("MaximumMessageSize") as usize;
+        let message_size =
+            message.message_body.len() + get_message_attributes_size(&message.message_attributes);
+        if message_size > maximum_message_size {
+            return Err(SqsError::invalid_parameter_value(format!(
+                "One or more parameters are invalid. Reason: Message must be shorter than {} bytes.",
+                maximum_message_size
+            )));
+        }
+
+        let delay_seconds = match message.delay_seconds {
+            Some(delay_seconds) if self.is_fifo() && delay_seconds > 0 => {
+                return Err(SqsError::invalid_parameter_value(format!(
+                    "Value {} for parameter DelaySeconds is invalid. Reason: The request include parameter that is not valid for this queue type.",
+                    delay_seconds
+                )));
+            }
+            Some(delay_seconds) => delay_seconds,
+            None => self.get_number_attribute("DelaySeconds"),
+        };
+        if delay_seconds > MAX_DELAY_SECONDS {
+            return Err(SqsError::invalid_parameter_value(format!(
+                "Value {} for parameter DelaySeconds is invalid. Reason: must be between 0 and 900, if provided.",
+                delay_seconds
+            )));
+        }
+
+        let md5_of_message_body = md5_hex(message.message_body.as_bytes());
+        let md5_of_message_attributes = (!message.message_attributes.is_empty())
+            .then(|| get_md5_of_message_attributes(&message.message_attributes));
+
+        let (message_group_id, message_deduplication_id, sequence_number) = if self.is_fifo() {
+            let Some(message_group_id) = message.message_group_id else {
+                return Err(SqsError::invalid_parameter_value(
+                    "The request must contain the parameter MessageGroupId.",
+                ));
+            };
+            let message_deduplication_id = match message.message_deduplication_id {
+                Some(message_deduplication_id) => message_deduplication_id,
+                None if self.is_content_based_deduplication() => {
+                    sha256_hex(message.message_body.as_bytes())
+                }
+                None => {
+                    return Err(SqsError::invalid_parameter_value(
+                        "The queue should either have ContentBasedDeduplication enabled or MessageDeduplicationId provided explicitly",
+                    ));
+                }
+            };
+
+            // A message with the same deduplication ID is accepted but not sent again
+            self.deduplication_ids
+                .retain(|_, (sent, _)| now - *sent < DEDUPLICATION_INTERVAL_MILLIS);
+            if let Some((_, response)) = self.deduplication_ids.get(&message_deduplication_id) {
+                debug!(
+                    "Message with deduplication ID: {} already sent",
+                    message_deduplication_id
+                );
+                return Ok(response.clone());
+            }
+
+            self.sequence_number += 1;
+            (
+                Some(message_group_id),
+                Some(message_deduplication_id),
+                Some(format!("{:020}", self.sequence_number)),
+            )
+        } else {
+            (None, None, None)
+        };
+
+        let response = SendMessageResponse {
+            message_id: Uuid::new_v4().to_string(),
+            md5_of_message_body: md5_of_message_body.clone(),
+            md5_of_message_attributes,
+            sequence_number: sequence_number.clone(),
+        };
+        if let Some(message_deduplication_id) = &message_deduplication_id {
+            self.deduplication_ids.insert(
+                message_deduplication_id.to_string(),
+                (now, response.clone()),
+            );
+        }
+
+        self.messages.push(StoredMessage {
+            message_id: response.message_id.clone(),
+            body: message.message_body,
+            md5_of_body: md5_of_message_body,
+            message_attributes: message.message_attributes,
+            aws_trace_header: message
+                .message_system_attributes
+                .get("AWSTraceHeader")
+                .and_then(|attribute| attribute.string_value.clone()),
+            sent_timestamp: now,
+            visible_at: now + delay_seconds as i64 * 1000,
+            receive_count: 0,
+            first_receive_timestamp: None,
+            receipt_handle: None,
+            message_group_id,
+            message_deduplication_id,
+            sequence_number,
+            dead_letter_queue_source_arn: None,
+        });
+        trace!("Message sent to queue: {}", self.name);
+
+        Ok(response)
+    }
+
+    fn delete_message(&mut self, receipt_handle: &str) -> Result<(), SqsError> {
+        if receipt_handle.is_empty() {
+            return Err(SqsError::receipt_handle_is_invalid(receipt_handle));
+        }
+
+        // Deleting a message that's already gone succeeds, as it does in SQS
+        self.messages
+            .retain(|message| message.receipt_handle.as_deref() != Some(receipt_handle));
+
+        Ok(())
+    }
+
+    fn change_message_visibility(
+        &mut self,
+        receipt_handle: &str,
+        visibility_timeout: u64,
+    ) -> Result<(), SqsError> {
+        check_visibility_timeout(visibility_timeout)?;
+
+        let now = chrono::Utc::now().timestamp_millis();
+        let Some(message) = self
+            .messages
+            .iter_mut()
+            .find(|message| message.receipt_handle.as_deref() == Some(receipt_handle))
+        else {
+            return Err(SqsError::receipt_handle_is_invalid(receipt_handle));
+        };
+        if !message.is_in_flight(now) {
+            return Err(SqsError::message_not_inflight());
+        }
+
+        message.visible_at = now + visibility_timeout as i64 * 1000;
+        Ok(())
+    }
+
+    /// The queue's attributes, including the message counts worked out when they're asked for
+    fn get_attributes(&self, queue_arn: String) -> HashMap<String, String> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let visible = self.messages.iter().filter(|m| m.visible_at <= now).count();
+        let in_flight = self.messages.iter().filter(|m| m.is_in_flight(now)).count();
+        let delayed = self.messages.iter().filter(|m| m.is_delayed(now)).count();
+
+        let mut attributes = self.attributes.clone();
+        attributes.extend([
+            (
+                "ApproximateNumberOfMessages".to_string(),
+                visible.to_string(),
+            ),
+            (
+                "ApproximateNumberOfMessagesNotVisible".to_string(),
+                in_flight.to_string(),
+            ),
+            (
+                "ApproximateNumberOfMessagesDelayed".to_string(),
+                delayed.to_string(),
+            ),
+            (
+                "CreatedTimestamp".to_string(),
+                self.created_timestamp.to_string(),
+            ),
+            (
+                "LastModifiedTimestamp".to_string(),
+                self.last_modified_timestamp.to_string(),
+            ),
+            ("QueueArn".to_string(), queue_arn),
+        ]);
+
+        attributes
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct QueueStore {
+    pub queues: Arc<RwLock<HashMap<String, Queue>>>,
+    pub account_id: String,
+    pub region: String,
+}
+
+impl QueueStore {
+    pub fn from_config(config: &Config) -> Self {
+        debug!("Building the queue store from config...");
+        let runtime = config.get_runtime();
+
+        Self {
+            queues: Arc::new(RwLock::new(HashMap::new())),
+            account_id: runtime.get_account_id().to_string(),
+            region: runtime.get_region().to_string(),
+        }
+    }
+
+    /// Queue URLs use the host the request was sent to, so they work from the host machine and
+    /// from other containers alike
+    pub fn get_queue_url(&self, host: &str, queue_name: &str) -> String {
+        format!("http://{}/{}/{}", host, self.account_id, queue_name)
+    }
+
+    pub fn get_queue_arn(&self, queue_name: &str) -> String {
+        format!(
+            "arn:aws:sqs:{}:{}:{}",
+            self.region, self.account_id, queue_name
+        )
+    }
+
+    /// Runs the function against the queue at the URL
+    fn with_queue<T>(
+        &self,
+        queue_url: &str,
+        f: impl FnOnce(&mut Queue) -> Result<T, SqsError>,
+    ) -> Result<T, SqsError> {
+        let mut queues = self.queues.write();
+        let queue = queues
+            .get_mut(get_queue_name_from_url(queue_url))
+            .ok_or_else(SqsError::queue_does_not_exist)?;
+
+        f(queue)
+    }
+
+    /// Creates the queue, or returns the existing queue when its attributes match
+    pub fn create_queue(&self, request: CreateQueueRequest) -> Result<String, SqsError> {
+        let queue_name = request.queue_name;
+        check_queue_name(&queue_name, &request.attributes)?;
+        check_queue_attributes(&request.attributes)?;
+
+        let mut queues = self.queues.write();
+        if let Some(queue) = queues.get(&queue_name) {
+            let attributes_match = request
+                .attributes
+                .iter()
+                .all(|(name, value)| queue.attributes.get(name) == Some(value));
+            if !attributes_match {
+                return Err(SqsError::queue_name_exists(&queue_name));
+            }
+
+            debug!("Queue: {} already exists", queue_name);
+            return Ok(queue_name);
+        }
+
+        debug!("Creating queue: {}", queue_name);
+        queues.insert(
+            queue_name.clone(),
+            Queue::new(queue_name.clone(), request.attributes, request.tags),
+        );
+
+        Ok(queue_name)
+    }
+
+    pub fn check_queue_exists(&self, queue_name: &str) -> Result<(), SqsError> {
+        if self.queues.read().contains_key(queue_name) {
+            Ok(())
+        } else {
+            Err(SqsError::queue_does_not_exist())
+        }
+    }
+
+    pub fn list_queues(&self, queue_name_prefix: Option<&str>, max_results: usize) -> Vec<String> {
+        let mut queue_names = self
+            .queues
+            .read()
+            .keys()
+            .filter(|name| queue_name_prefix.is_none_or(|prefix| name.starts_with(prefix)))
+            .cloned()
+            .collect::<Vec<_>>();
+        queue_names.sort();
+        queue_names.truncate(max_results);
+
+        queue_names
+    }
+
+    pub fn delete_queue(&self, queue_url: &str) -> Result<(), SqsError> {
+        let queue_name = get_queue_name_from_url(queue_url);
+        debug!("Deleting queue: {}", queue_name);
+        self.queues
+            .write()
+            .remove(queue_name)
+            .map(|_| ())
+            .ok_or_else(SqsError::queue_does_not_exist)
+    }
+
+    pub fn purge_queue(&self, queue_url: &str) -> Result<(), SqsError> {
+        self.with_queue(queue_url, |queue| {
+            debug!("Purging queue: {}", queue.name);
+            queue.messages.clear();
+            Ok(())
+        })
+    }
+
+    pub fn get_queue_attributes(
+        &self,
+        queue_url: &str,
+        attribute_names: &[String],
+    ) -> Result<HashMap<String, String>, SqsError> {
+        self.with_queue(queue_url, |queue| {
+            queue.remove_expired_messages(chrono::Utc::now().timestamp_millis());
+            let attributes = queue.get_attributes(self.get_queue_arn(&queue.name));
+            if attribute_names.iter().any(|name| name == "All") {
+                return Ok(attributes);
+            }
+
+            Ok(attributes
+                .into_iter()
+                .filter(|(name, _)| attribute_names.contains(name))
+                .collect())
+        })
+    }
+
+    pub fn set_queue_attributes(
+        &self,
+        queue_url: &str,
+        attributes: HashMap<String, String>,
+    ) -> Result<(), SqsError> {
+        check_queue_attributes(&attributes)?;
+
+        self.with_queue(queue_url, |queue| {
+            // A queue can't be changed to or from a FIFO queue once it's created
+            if attributes
+                .get("FifoQueue")
+                .is_some_and(|fifo_queue| *fifo_queue != queue.is_fifo().to_string())
+            {
+                return Err(SqsError::invalid_attribute_name("FifoQueue"));
+            }
+
+            debug!("Setting attributes on queue: {}", queue.name);
+            queue.attributes.extend(attributes);
+            queue.last_modified_timestamp = chrono::Utc::now().timestamp();
+            Ok(())
+        })
+    }
+
+    pub fn tag_queue(
+        &self,
+        queue_url: &str,
+        tags: HashMap<String, String>,
+    ) -> Result<(), SqsError> {
+        self.with_queue(queue_url, |queue| {
+            queue.tags.extend(tags);
+            Ok(())
+        })
+    }
+
+    pub fn untag_queue(&self, queue_url: &str, tag_keys: &[String]) -> Result<(), SqsError> {
+        self.with_queue(queue_url, |queue| {
+            queue.tags.retain(|key, _| !tag_keys.contains(key));
+            Ok(())
+        })
+    }
+
+    pub fn list_queue_tags(&self, queue_url: &str) -> Result<HashMap<String, String>, SqsError> {
+        self.with_queue(queue_url, |queue| Ok(queue.tags.clone()))
+    }
+
+    /// How long receives wait for messages when the request doesn't say
+    pub fn get_receive_wait_time(&self, queue_url: &str) -> Result<u64, SqsError> {
+        self.with_queue(queue_url, |queue| {
+            Ok(queue.get_number_attribute("ReceiveMessageWaitTimeSeconds"))
+        })
+    }
+
+    pub fn send_message(
+        &self,
+        queue_url: &str,
+        message: SendMessageEntry,
+    ) -> Result<SendMessageResponse, SqsError> {
+        self.with_queue(queue_url, |queue| queue.send_message(message))
+    }
+
+    pub fn delete_message(&self, queue_url: &str, receipt_handle: &str) -> Result<(), SqsError> {
+        self.with_queue(queue_url, |queue| queue.delete_message(receipt_handle))
+    }
+
+    pub fn change_message_visibility(
+        &self,
+        queue_url: &str,
+        receipt_handle: &str,
+        visibility_timeout: u64,
+    ) -> Result<(), SqsError> {
+        self.with_queue(queue_url, |queue| {
+            queue.change_message_visibility(receipt_handle, visibility_timeout)
+        })
+    }
+
+    /// Receives the messages that are visible, hiding them for the visibility timeout. Messages
+    /// received more than the redrive policy's max receive count are moved to the dead-letter queue
+    /// instead, and a FIFO queue only gives messages from groups with nothing in flight.
+    pub fn receive_messages(
+        &self,
+        request: &ReceiveMessageRequest,
+    ) -> Result<Vec<ReceivedMessage>, SqsError> {
+        let max_number_of_messages = request.max_number_of_messages.unwrap_or(1);
+        if !(1..=10).contains(&max_number_of_messages) {
+            return Err(SqsError::invalid_parameter_value(format!(
+                "Value {} for parameter MaxNumberOfMessages is invalid. Reason: Must be between 1 and 10, if provided.",
+                max_number_of_messages
+            )));
+        }
+        if let Some(visibility_timeout) = request.visibility_timeout {
+            check_visibility_timeout(visibility_timeout)?;
+        }
+
+        let queue_name = get_queue_name_from_url(&request.queue_url);
+        let queue_arn = self.get_queue_arn(queue_name);
+        let mut queues = self.queues.write();
+
+        let redrive_policy = queues
+            .get(queue_name)
+            .ok_or_else(SqsError::queue_does_not_exist)?
+            .get_redrive_policy()
+            .filter(|(dead_letter_queue, _)| {
+                let exists = queues.contains_key(dead_letter_queue);
+                if !exists {
+                    warn!(
+                        "Dead-letter queue: {} not found for queue: {}",
+                        dead_letter_queue, queue_name
+                    );
+                }
+                exists
+            });
+        let Some(queue) = queues.get_mut(queue_name) else {
+            return Err(SqsError::queue_does_not_exist());
+        };
+
+        let now = chrono::Utc::now().timestamp_millis();
+        queue.remove_expired_messages(now);
+        let visibility_timeout = request
+            .visibility_timeout
+            .unwrap_or_else(|| queue.get_number_attribute("VisibilityTimeout"));
+
+        let blocked_groups = if queue.is_fifo() {
+            queue
+                .messages
+                .iter()
+                .filter(|message| message.is_in_flight(now))
+                .filter_map(|message| message.message_group_id.clone())
+                .collect::<HashSet<_>>()
+        } else {
+            HashSet::new()
+        };
+
+        let mut received = vec![];
+        let mut dead_letters = vec![];
+        let mut index = 0;
+        while index < queue.messages.len() && received.len() < max_number_of_messages {
+            let message = &queue.messages[index];
+            let is_blocked = message
+                .message_group_id
+                .as_ref()
+                .is_some_and(|group| blocked_groups.contains(group));
+            if message.visible_at > now || is_blocked {
+                index += 1;
+                continue;
+            }
+
+            if redrive_policy
+                .as_ref()
+                .is_some_and(|(_, max_receive_count)| message.receive_count >= *max_receive_count)
+            {
+                dead_letters.push(queue.messages.remove(index));
+                continue;
+            }
+
+            let message = &mut queue.messages[index];
+            message.receive_count += 1;
+            message.first_receive_timestamp.get_or_insert(now);
+            message.visible_at = now + visibility_timeout as i64 * 1000;
+            message.receipt_handle = Some(Uuid::new_v4().to_string());
+            received.push(message.clone());
+            index += 1;
+        }
+
+        if let Some((dead_letter_queue_name, _)) = redrive_policy {
+            if let Some(dead_letter_queue) = queues.get_mut(&dead_letter_queue_name) {
+                for mut message in dead_letters {
+                    debug!(
+                        "Moving message: {} to dead-letter queue: {}",
+                        message.message_id, dead_letter_queue_name
+                    );
+                    message.visible_at = now;
+                    message.receive_count = 0;
+                    message.first_receive_timestamp = None;
+                    message.receipt_handle = None;
+                    message.dead_letter_queue_source_arn = Some(queue_arn.clone());
+                    dead_letter_queue.messages.push(message);
+                }
+            }
+        }
+
+        let system_attribute_names = request
+            .attribute_names
+            .iter()
+            .chain(request.message_system_attribute_names.iter())
+            .filter(|name| *name == "All" || MESSAGE_SYSTEM_ATTRIBUTES.contains(&name.as_str()))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        Ok(received
+            .iter()
+            .map(|message| {
+                message.to_received_message(
+                    &self.account_id,
+                    &system_attribute_names,
+                    &request.message_attribute_names,
+                )
+            })
+            .collect())
+    }
+}
+
+/// Queue URLs end with the queue's name
+pub fn get_queue_name_from_url(queue_url: &str) -> &str {
+    queue_url
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or(queue_url)
+}
+
+fn check_queue_name(
+    queue_name: &str,
+    attributes: &HashMap<String, String>,
+) -> Result<(), SqsError> {
+    let is_fifo = attributes.get("FifoQueue").is_some_and(|v| v == "true");
+    let name = match (is_fifo, queue_name.strip_suffix(".fifo")) {
+        (true, Some(name)) => name,
+        (false, None) => queue_name,
+        _ => {
+            return Err(SqsError::invalid_parameter_value(
+                "The name of a FIFO queue can only include alphanumeric characters, hyphens, or underscores, must end with .fifo suffix and be 1 to 80 in length.",
+            ));
+        }
+    };
+
+    let is_valid = !name.is_empty()
+        && queue_name.len() <= 80
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !is_valid {
+        return Err(SqsError::invalid_parameter_value(
+            "Can only include alphanumeric characters, hyphens, or underscores. 1 to 80 in length",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Checks the attributes are ones a queue can have, and numbers are within the limits SQS has
+fn check_queue_attributes(attributes: &HashMap<String, String>) -> Result<(), SqsError> {
+    for (name, value) in attributes {
+        let limits = match name.as_str() {
+            "VisibilityTimeout" => Some((0, MAX_VISIBILITY_TIMEOUT)),
+            "DelaySeconds" => Some((0, MAX_DELAY_SECONDS)),
+            "MessageRetentionPeriod" => Some((60, 1209600)),
+            "MaximumMessageSize" => Some((1024, 262144)),
+            "ReceiveMessageWaitTimeSeconds" => Some((0, 20)),
+            name if OTHER_QUEUE_ATTRIBUTES.contains(&name) => None,
+            name => return Err(SqsError::invalid_attribute_name(name)),
+        };
+
+        if let Some((min, max)) = limits {
+            let is_valid = value
+                .parse::<u64>()
+                .is_ok_and(|value| (min..=max).contains(&value));
+            if !is_valid {
+                return Err(SqsError::invalid_parameter_value(format!(
+                    "Invalid value for the parameter {}.",
+                    name
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn check_visibility_timeout(visibility_timeout: u64) -> Result<(), SqsError> {
+    if visibility_timeout > MAX_VISIBILITY_TIMEOUT {
+        return Err(SqsError::invalid_parameter_value(format!(
+            "Value {} for parameter VisibilityTimeout is invalid. Reason: Must be between 0 and 43200.",
+            visibility_timeout
+        )));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+mod data;
+mod middleware;
+mod request;
+mod response;
+mod utils;
+
+use axum::Router;
+
+use tracing::{debug, info};
+use tracing_subscriber::EnvFilter;
+
+use sam_e_types::config::{Config, Infrastructure};
+
+const QUEUE_PORT: u16 = 9324;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .with_ansi(false)
+        .without_time()
+        .init();
+
+    info!("Starting the SAM-E local queue...");
+
+    debug!("Reading the current configuration");
+    let config_env_string = std::env::var("CONFIG").expect("CONFIG env variable not found");
+    let config: Config = serde_yaml::from_str(&config_env_string)?;
+    debug!("Configuration read successfully");
+
+    let has_queue = config
+        .get_infrastructure()
+        .iter()
+        .any(|infrastructure| matches!(infrastructure, Infrastructure::Sqs(_)));
+    if !has_queue {
+        debug!("No queues found in config, exiting...");
+        return Ok(());
+    }
+
+    debug!("Setting up the queue store");
+    let queue_store = data::store::QueueStore::from_config(&config);
+
+    // Every action is sent to the same endpoint, or the queue's URL for query requests
+    let app = Router::new()
+        .fallback(request::handler)
+        .with_state(queue_store);
+
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", QUEUE_PORT))
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to bind the queue to port {}: {}", QUEUE_PORT, e))?;
+    info!("Listening on: {}", listener.local_addr()?);
+    axum::serve(listener, app).await?;
+
+    Ok(())
+}
@@ -0,0 +1,247 @@
+use crate::{
+    data::{Protocol, SqsAction, SqsRequest},
+    response::{SqsError, SqsResponse},
+};
+
+use axum::{
+    async_trait,
+    body::Bytes,
+    extract::{FromRequest, Request},
+    http::header,
+};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use tracing::{debug, trace};
+
+/// Query parameters that are numbers in the JSON protocol
+const NUMBER_PARAMETERS: [&str; 5] = [
+    "DelaySeconds",
+    "VisibilityTimeout",
+    "MaxNumberOfMessages",
+    "WaitTimeSeconds",
+    "MaxResults",
+];
+
+#[async_trait]
+impl<S> FromRequest<S> for SqsRequest
+where
+    S: Send + Sync,
+{
+    type Rejection = SqsResponse;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        debug!("Parsing the SQS request middleware...");
+        let host = req
+            .headers()
+            .get(header::HOST)
+            .and_then(|h| h.to_str().ok())
+            .unwrap_or("localhost:9324")
+            .to_string();
+        let target = req
+            .headers()
+            .get("X-Amz-Target")
+            .and_then(|h| h.to_str().ok())
+            .map(|h| h.to_string());
+        let path = req.uri().path().to_string();
+        let query = req.uri().query().unwrap_or_default().to_string();
+        let protocol = match target {
+            Some(_) => Protocol::Json,
+            None => Protocol::Query,
+        };
+        let reject = |action: &str, error: SqsError| SqsResponse {
+            protocol,
+            action: action.to_string(),
+            result: Err(error),
+        };
+
+        let body = Bytes::from_request(req, state)
+            .await
+            .map_err(|e| reject("", SqsError::invalid_parameter_value(e.to_string())))?;
+
+        let (action, params) = if let Some(target) = target {
+            debug!("Detected a JSON protocol request: {}", target);
+            let action = target
+                .strip_prefix("AmazonSQS.")
+                .unwrap_or(&target)
+                .to_string();
+            let params = if body.is_empty() {
+                Value::Object(Map::new())
+            } else {
+                serde_json::from_slice(&body).map_err(|e| {
+                    reject(&action, SqsError::invalid_parameter_value(e.to_string()))
+                })?
+            };
+            (action, params)
+        } else {
+            debug!("Detected a query protocol request");
+            let mut form: Vec<(String, String)> =
+                serde_urlencoded::from_str(&query).unwrap_or_default();
+            form.extend(
+                serde_urlencoded::from_bytes::<Vec<(String, String)>>(&body).unwrap_or_default(),
+            );
+
+            let Some(action) = form
+                .iter()
+                .find(|(key, _)| key == "Action")
+                .map(|(_, action)| action.to_string())
+            else {
+                return Err(reject(
+                    "",
+                    SqsError::invalid_parameter_value("No Action given in the request"),
+                ));
+            };
+
+            let mut params = query_params_to_json(form);
+            // Query requests can be sent to the queue's URL rather than naming it
+            if params.get("QueueUrl").is_none() && path.trim_matches('/').contains('/') {
+                params["QueueUrl"] = Value::String(format!("http://{}{}", host, path));
+            }
+            (action, params)
+        };
+        trace!("{} request parameters: {}", action, params);
+
+        let action = parse_action(&action, params).map_err(|error| reject(&action, error))?;
+
+        Ok(SqsRequest {
+            protocol,
+            host,
+            action,
+        })
+    }
+}
+
+fn parse_action(action: &str, params: Value) -> Result<SqsAction, SqsError> {
+    let action = match action {
+        "CreateQueue" => SqsAction::CreateQueue(from_params(params)?),
+        "GetQueueUrl" => SqsAction::GetQueueUrl(from_params(params)?),
+        "ListQueues" => SqsAction::ListQueues(from_params(params)?),
+        "DeleteQueue" => SqsAction::DeleteQueue(from_params(params)?),
+        "PurgeQueue" => SqsAction::PurgeQueue(from_params(params)?),
+        "GetQueueAttributes" => SqsAction::GetQueueAttributes(from_params(params)?),
+        "SetQueueAttributes" => SqsAction::SetQueueAttributes(from_params(params)?),
+        "TagQueue" => SqsAction::TagQueue(from_params(params)?),
+        "UntagQueue" => SqsAction::UntagQueue(from_params(params)?),
+        "ListQueueTags" => SqsAction::ListQueueTags(from_params(params)?),
+        "SendMessage" => SqsAction::SendMessage(from_params(params)?),
+        "SendMessageBatch" => SqsAction::SendMessageBatch(from_params(params)?),
+        "ReceiveMessage" => SqsAction::ReceiveMessage(from_params(params)?),
+        "DeleteMessage" => SqsAction::DeleteMessage(from_params(params)?),
+        "DeleteMessageBatch" => SqsAction::DeleteMessageBatch(from_params(params)?),
+        "ChangeMessageVisibility" => SqsAction::ChangeMessageVisibility(from_params(params)?),
+        "ChangeMessageVisibilityBatch" => {
+            SqsAction::ChangeMessageVisibilityBatch(from_params(params)?)
+        }
+        action => return Err(SqsError::invalid_action(action)),
+    };
+
+    Ok(action)
+}
+
+fn from_params<T: DeserializeOwned>(params: Value) -> Result<T, SqsError> {
+    serde_json::from_value(params).map_err(|e| SqsError::invalid_parameter_value(e.to_string()))
+}
+
+/// Query requests flatten everything into numbered parameters, i.e. `Attribute.1.Name`. These are
+/// put back into the shape JSON requests have so both can be read the same way.
+fn query_params_to_json(params: Vec<(String, String)>) -> Value {
+    let mut tree = Value::Object(Map::new());
+    for (key, value) in params {
+        if key == "Action" || key == "Version" {
+            continue;
+        }
+
+        let parts = key.split('.').collect::<Vec<_>>();
+        let mut node = &mut tree;
+        for (index, part) in parts.iter().enumerate() {
+            let Value::Object(fields) = node else {
+                break;
+            };
+            if index == parts.len() - 1 {
+                fields.insert(part.to_string(), Value::String(value.clone()));
+                break;
+            }
+            node = fields
+                .entry(part.to_string())
+                .or_insert_with(|| Value::Object(Map::new()));
+        }
+    }
+
+    reshape_query_value(tree)
+}
+
+fn reshape_query_value(value: Value) -> Value {
+    let Value::Object(fields) = value else {
+        return value;
+    };
+
+    // Objects keyed by position are lists
+    if !fields.is_empty() && fields.keys().all(|key| key.parse::<usize>().is_ok()) {
+        let mut items = fields
+            .into_iter()
+            .map(|(key, value)| (key.parse::<usize>().unwrap_or_default(), value))
+            .collect::<Vec<_>>();
+        items.sort_by_key(|(position, _)| *position);
+        return Value::Array(
+            items
+                .into_iter()
+                .map(|(_, value)| reshape_query_value(value))
+                .collect(),
+        );
+    }
+
+    let mut reshaped = Map::new();
+    for (key, value) in fields {
+        let value = reshape_query_value(value);
+        let (key, value) = match key.as_str() {
+            "Attribute" => (
+                "Attributes".to_string(),
+                list_to_map(value, "Name", "Value"),
+            ),
+            "Tag" => ("Tags".to_string(), list_to_map(value, "Key", "Value")),
+            "MessageAttribute" => (
+                "MessageAttributes".to_string(),
+                list_to_map(value, "Name", "Value"),
+            ),
+            "MessageSystemAttribute" => (
+                "MessageSystemAttributes".to_string(),
+                list_to_map(value, "Name", "Value"),
+            ),
+            "AttributeName"
+            | "MessageAttributeName"
+            | "MessageSystemAttributeName"
+            | "StringListValue"
+            | "BinaryListValue" => (format!("{}s", key), value),
+            "TagKey" => ("TagKeys".to_string(), value),
+            key if key.ends_with("RequestEntry") => ("Entries".to_string(), value),
+            key if NUMBER_PARAMETERS.contains(&key) => {
+                let value = match value.as_str().and_then(|v| v.parse::<u64>().ok()) {
+                    Some(number) => Value::from(number),
+                    None => value,
+                };
+                (key.to_string(), value)
+            }
+            _ => (key, value),
+        };
+        reshaped.insert(key, value);
+    }
+
+    Value::Object(reshaped)
+}
+
+/// Turns a list of name and value pairs into a map
+fn list_to_map(value: Value, name_key: &str, value_key: &str) -> Value {
+    let Value::Array(items) = value else {
+        return value;
+    };
+
+    let map = items
+        .into_iter()
+        .filter_map(|mut item| {
+            let name = item.get(name_key)?.as_str()?.to_string();
+            let value = item.get_mut(value_key).map(Value::take)?;
+            Some((name, value))
+        })
+        .collect::<Map<_, _>>();
+
+    Value::Object(map)
+}
@@ -0,0 +1,95 @@
+mod messages;
+mod queues;
+
+use crate::{
+    data::{store::QueueStore, SqsAction, SqsRequest},
+    response::{SqsError, SqsResponse},
+};
+
+use axum::extract::State;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tracing::debug;
+
+// The SQS request is parsed to the correct action via the middleware
+pub async fn handler(
+    State(queue_store): State<QueueStore>,
+    sqs_request: SqsRequest,
+) -> SqsResponse {
+    let action = sqs_request.action.get_name().to_string();
+    debug!("Recognised a {} request", action);
+
+    let host = &sqs_request.host;
+    let result = match sqs_request.action {
+        SqsAction::CreateQueue(request) => {
+            to_result(queues::create_queue(request, host, &queue_store))
+        }
+        SqsAction::GetQueueUrl(request) => {
+            to_result(queues::get_queue_url(request, host, &queue_store))
+        }
+        SqsAction::ListQueues(request) => {
+            to_result(Ok(queues::list_queues(request, host, &queue_store)))
+        }
+        SqsAction::DeleteQueue(request) => {
+            to_empty_result(queue_store.delete_queue(&request.queue_url))
+        }
+        SqsAction::PurgeQueue(request) => {
+            to_empty_result(queue_store.purge_queue(&request.queue_url))
+        }
+        SqsAction::GetQueueAttributes(request) => {
+            to_result(queues::get_queue_attributes(request, &queue_store))
+        }
+        SqsAction::SetQueueAttributes(request) => to_empty_result(
+            queue_store.set_queue_attributes(&request.queue_url, request.attributes),
+        ),
+        SqsAction::TagQueue(request) => {
+            to_empty_result(queue_store.tag_queue(&request.queue_url, request.tags))
+        }
+        SqsAction::UntagQueue(request) => {
+            to_empty_result(queue_store.untag_queue(&request.queue_url, &request.tag_keys))
+        }
+        SqsAction::ListQueueTags(request) => {
+            to_result(queues::list_queue_tags(request, &queue_store))
+        }
+        SqsAction::SendMessage(request) => {
+            to_result(queue_store.send_message(&request.queue_url, request.message))
+        }
+        SqsAction::SendMessageBatch(request) => {
+            to_result(messages::send_message_batch(request, &queue_store))
+        }
+        SqsAction::ReceiveMessage(request) => {
+            to_result(messages::receive_message(request, &queue_store).await)
+        }
+        SqsAction::DeleteMessage(request) => {
+            to_empty_result(queue_store.delete_message(&request.queue_url, &request.receipt_handle))
+        }
+        SqsAction::DeleteMessageBatch(request) => {
+            to_result(messages::delete_message_batch(request, &queue_store))
+        }
+        SqsAction::ChangeMessageVisibility(request) => {
+            to_empty_result(queue_store.change_message_visibility(
+                &request.queue_url,
+                &request.receipt_handle,
+                request.visibility_timeout,
+            ))
+        }
+        SqsAction::ChangeMessageVisibilityBatch(request) => to_result(
+            messages::change_message_visibility_batch(request, &queue_store),
+        ),
+    };
+
+    SqsResponse {
+        protocol: sqs_request.protocol,
+        action,
+        result,
+    }
+}
+
+fn to_result<T: Serialize>(result: Result<T, SqsError>) -> Result<Value, SqsError> {
+    result.map(|response| serde_json::to_value(response).unwrap_or_default())
+}
+
+/// Actions that don't give anything back still respond with an empty object
+fn to_empty_result(result: Result<(), SqsError>) -> Result<Value, SqsError> {
+    result.map(|_| Value::Object(Map::new()))
+}
@@ -0,0 +1,152 @@
+use crate::{
+    data::{
+        store::{get_queue_name_from_url, QueueStore},
+        BatchResponse, BatchResultEntry, BatchResultErrorEntry,
+        ChangeMessageVisibilityBatchRequest, DeleteMessageBatchRequest, ReceiveMessageRequest,
+        ReceiveMessageResponse, SendMessageBatchRequest, SendMessageBatchResultEntry,
+    },
+    response::SqsError,
+};
+
+use std::collections::HashSet;
+use tokio::time::{sleep, Duration, Instant};
+use tracing::{debug, trace};
+
+const MAX_BATCH_ENTRIES: usize = 10;
+const MAX_WAIT_TIME_SECONDS: u64 = 20;
+
+/// How often a long poll checks for new messages
+const LONG_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Receives messages from the queue, waiting up to the wait time for some to arrive when the
+/// queue is empty
+pub async fn receive_message(
+    request: ReceiveMessageRequest,
+    queue_store: &QueueStore,
+) -> Result<ReceiveMessageResponse, SqsError> {
+    let wait_time_seconds = match request.wait_time_seconds {
+        Some(wait_time_seconds) => wait_time_seconds,
+        None => queue_store.get_receive_wait_time(&request.queue_url)?,
+    };
+    if wait_time_seconds > MAX_WAIT_TIME_SECONDS {
+        return Err(SqsError::invalid_parameter_value(format!(
+            "Value {} for parameter WaitTimeSeconds is invalid. Reason: Must be >= 0 and <= 20, if provided.",
+            wait_time_seconds
+        )));
+    }
+
+    let deadline = Instant::now() + Duration::from_secs(wait_time_seconds);
+    loop {
+        let messages = queue_store.receive_messages(&request)?;
+        if !messages.is_empty() || Instant::now() >= deadline {
+            trace!("Received {} messages", messages.len());
+            return Ok(ReceiveMessageResponse { messages });
+        }
+
+        sleep(LONG_POLL_INTERVAL).await;
+    }
+}
+
+pub fn send_message_batch(
+    request: SendMessageBatchRequest,
+    queue_store: &QueueStore,
+) -> Result<BatchResponse<SendMessageBatchResultEntry>, SqsError> {
+    check_batch(request.entries.iter().map(|entry| entry.id.as_str()))?;
+    queue_store.check_queue_exists(get_queue_name_from_url(&request.queue_url))?;
+
+    let mut response = BatchResponse {
+        successful: vec![],
+        failed: vec![],
+    };
+    for entry in request.entries {
+        match queue_store.send_message(&request.queue_url, entry.message) {
+            Ok(result) => response.successful.push(SendMessageBatchResultEntry {
+                id: entry.id,
+                result,
+            }),
+            Err(error) => response.failed.push(to_error_entry(entry.id, error)),
+        }
+    }
+
+    debug!(
+        "Sent {} messages, {} failed",
+        response.successful.len(),
+        response.failed.len()
+    );
+    Ok(response)
+}
+
+pub fn delete_message_batch(
+    request: DeleteMessageBatchRequest,
+    queue_store: &QueueStore,
+) -> Result<BatchResponse<BatchResultEntry>, SqsError> {
+    check_batch(request.entries.iter().map(|entry| entry.id.as_str()))?;
+    queue_store.check_queue_exists(get_queue_name_from_url(&request.queue_url))?;
+
+    let mut response = BatchResponse {
+        successful: vec![],
+        failed: vec![],
+    };
+    for entry in request.entries {
+        match queue_store.delete_message(&request.queue_url, &entry.receipt_handle) {
+            Ok(_) => response.successful.push(BatchResultEntry { id: entry.id }),
+            Err(error) => response.failed.push(to_error_entry(entry.id, error)),
+        }
+    }
+
+    Ok(response)
+}
+
+pub fn change_message_visibility_batch(
+    request: ChangeMessageVisibilityBatchRequest,
+    queue_store: &QueueStore,
+) -> Result<BatchResponse<BatchResultEntry>, SqsError> {
+    check_batch(request.entries.iter().map(|entry| entry.id.as_str()))?;
+    queue_store.check_queue_exists(get_queue_name_from_url(&request.queue_url))?;
+
+    let mut response = BatchResponse {
+        successful: vec![],
+        failed: vec![],
+    };
+    for entry in request.entries {
+        match queue_store.change_message_visibility(
+            &request.queue_url,
+            &entry.receipt_handle,
+            entry.visibility_timeout,
+        ) {
+            Ok(_) => response.successful.push(BatchResultEntry { id: entry.id }),
+            Err(error) => response.failed.push(to_error_entry(entry.id, error)),
+        }
+    }
+
+    Ok(response)
+}
+
+/// Batches need between 1 and 10 entries, each with a different ID
+fn check_batch<'a>(ids: impl ExactSizeIterator<Item = &'a str>) -> Result<(), SqsError> {
+    match ids.len() {
+        0 => return Err(SqsError::empty_batch_request()),
+        entries if entries > MAX_BATCH_ENTRIES => {
+            return Err(SqsError::too_many_entries_in_batch_request(entries));
+        }
+        _ => (),
+    }
+
+    let mut seen_ids = HashSet::new();
+    for id in ids {
+        if !seen_ids.insert(id) {
+            return Err(SqsError::batch_entry_ids_not_distinct());
+        }
+    }
+
+    Ok(())
+}
+
+fn to_error_entry(id: String, error: SqsError) -> BatchResultErrorEntry {
+    BatchResultErrorEntry {
+        id,
+        sender_fault: true,
+        code: error.get_code().to_string(),
+        message: error.get_message().to_string(),
+    }
+}
@@ -0,0 +1,75 @@
+use crate::{
+    data::{
+        store::QueueStore, CreateQueueRequest, GetQueueAttributesRequest,
+        GetQueueAttributesResponse, GetQueueUrlRequest, ListQueueTagsResponse, ListQueuesRequest,
+        ListQueuesResponse, QueueUrlRequest, QueueUrlResponse,
+    },
+    response::SqsError,
+};
+
+use tracing::trace;
+
+/// The most queues SQS lists when no max results are given
+const MAX_LIST_QUEUES: usize = 1000;
+
+pub fn create_queue(
+    request: CreateQueueRequest,
+    host: &str,
+    queue_store: &QueueStore,
+) -> Result<QueueUrlResponse, SqsError> {
+    trace!("CreateQueue request received: {:#?}", request);
+    let queue_name = queue_store.create_queue(request)?;
+
+    Ok(QueueUrlResponse {
+        queue_url: queue_store.get_queue_url(host, &queue_name),
+    })
+}
+
+pub fn get_queue_url(
+    request: GetQueueUrlRequest,
+    host: &str,
+    queue_store: &QueueStore,
+) -> Result<QueueUrlResponse, SqsError> {
+    queue_store.check_queue_exists(&request.queue_name)?;
+
+    Ok(QueueUrlResponse {
+        queue_url: queue_store.get_queue_url(host, &request.queue_name),
+    })
+}
+
+pub fn list_queues(
+    request: ListQueuesRequest,
+    host: &str,
+    queue_store: &QueueStore,
+) -> ListQueuesResponse {
+    let queue_names = queue_store.list_queues(
+        request.queue_name_prefix.as_deref(),
+        request.max_results.unwrap_or(MAX_LIST_QUEUES),
+    );
+
+    ListQueuesResponse {
+        queue_urls: queue_names
+            .iter()
+            .map(|queue_name| queue_store.get_queue_url(host, queue_name))
+            .collect(),
+    }
+}
+
+pub fn get_queue_attributes(
+    request: GetQueueAttributesRequest,
+    queue_store: &QueueStore,
+) -> Result<GetQueueAttributesResponse, SqsError> {
+    let attributes =
+        queue_store.get_queue_attributes(&request.queue_url, &request.attribute_names)?;
+
+    Ok(GetQueueAttributesResponse { attributes })
+}
+
+pub fn list_queue_tags(
+    request: QueueUrlRequest,
+    queue_store: &QueueStore,
+) -> Result<ListQueueTagsResponse, SqsError> {
+    let tags = queue_store.list_queue_tags(&request.queue_url)?;
+
+    Ok(ListQueueTagsResponse { tags })
+}
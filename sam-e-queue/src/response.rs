@@ -0,0 +1,284 @@
+use crate::data::Protocol;
+
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde_json::Value;
+use tracing::{debug, trace};
+use uuid::Uuid;
+
+const QUERY_NAMESPACE: &str = "http://queue.amazonaws.com/doc/2012-11-05/";
+
+/// An error as SQS returns it. The JSON protocol uses the error's shape name while the query
+/// protocol (and the `x-amzn-query-error` header SDKs read) use the older error code.
+#[derive(Debug, Clone)]
+pub struct SqsError {
+    code: &'static str,
+    query_code: &'static str,
+    message: String,
+}
+
+impl SqsError {
+    fn new(code: &'static str, query_code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            query_code,
+            message: message.into(),
+        }
+    }
+
+    pub fn get_code(&self) -> &str {
+        self.query_code
+    }
+
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn queue_does_not_exist() -> Self {
+        Self::new(
+            "QueueDoesNotExist",
+            "AWS.SimpleQueueService.NonExistentQueue",
+            "The specified queue does not exist.",
+        )
+    }
+
+    pub fn queue_name_exists(queue_name: &str) -> Self {
+        Self::new(
+            "QueueNameExists",
+            "QueueAlreadyExists",
+            format!(
+                "A queue already exists with the same name and a different value for attribute(s): {}",
+                queue_name
+            ),
+        )
+    }
+
+    pub fn invalid_action(action: &str) -> Self {
+        Self::new(
+            "InvalidAction",
+            "InvalidAction",
+            format!("The action {} is not valid for this endpoint.", action),
+        )
+    }
+
+    pub fn invalid_parameter_value(message: impl Into<String>) -> Self {
+        Self::new("InvalidParameterValue", "InvalidParameterValue", message)
+    }
+
+    pub fn invalid_attribute_name(attribute: &str) -> Self {
+        Self::new(
+            "InvalidAttributeName",
+            "InvalidAttributeName",
+            format!("Unknown Attribute {}.", attribute),
+        )
+    }
+
+    pub fn receipt_handle_is_invalid(receipt_handle: &str) -> Self {
+        Self::new(
+            "ReceiptHandleIsInvalid",
+            "ReceiptHandleIsInvalid",
+            format!(
+                "The input receipt handle \"{}\" is not valid.",
+                receipt_handle
+            ),
+        )
+    }
+
+    pub fn message_not_inflight() -> Self {
+        Self::new(
+            "MessageNotInflight",
+            "AWS.SimpleQueueService.MessageNotInflight",
+            "The message referred to isn't in flight.",
+        )
+    }
+
+    pub fn empty_batch_request() -> Self {
+        Self::new(
+            "EmptyBatchRequest",
+            "AWS.SimpleQueueService.EmptyBatchRequest",
+            "There should be at least one entry in the request.",
+        )
+    }
+
+    pub fn too_many_entries_in_batch_request(entries: usize) -> Self {
+        Self::new(
+            "TooManyEntriesInBatchRequest",
+            "AWS.SimpleQueueService.TooManyEntriesInBatchRequest",
+            format!(
+                "Maximum number of entries per request are 10. You have sent {}.",
+                entries
+            ),
+        )
+    }
+
+    pub fn batch_entry_ids_not_distinct() -> Self {
+        Self::new(
+            "BatchEntryIdsNotDistinct",
+            "AWS.SimpleQueueService.BatchEntryIdsNotDistinct",
+            "Two or more batch entries in the request have the same Id.",
+        )
+    }
+}
+
+/// The result of an action, rendered with the protocol the request was made with
+pub struct SqsResponse {
+    pub protocol: Protocol,
+    pub action: String,
+    pub result: Result<Value, SqsError>,
+}
+
+impl IntoResponse for SqsResponse {
+    fn into_response(self) -> Response {
+        let request_id = Uuid::new_v4().to_string();
+
+        match (self.protocol, self.result) {
+            (Protocol::Json, Ok(result)) => {
+                trace!("JSON response: {}", result);
+                (
+                    [(header::CONTENT_TYPE, "application/x-amz-json-1.0")],
+                    [("x-amzn-RequestId", request_id)],
+                    result.to_string(),
+                )
+                    .into_response()
+            }
+            (Protocol::Json, Err(error)) => {
+                debug!("{} failed: {}", self.action, error.message);
+                let body = serde_json::json!({
+                    "__type": format!("com.amazonaws.sqs#{}", error.code),
+                    "message": error.message,
+                });
+                (
+                    StatusCode::BAD_REQUEST,
+                    [(header::CONTENT_TYPE, "application/x-amz-json-1.0")],
+                    [
+                        ("x-amzn-RequestId", request_id),
+                        ("x-amzn-query-error", format!("{};Sender", error.query_code)),
+                    ],
+                    body.to_string(),
+                )
+                    .into_response()
+            }
+            (Protocol::Query, Ok(result)) => {
+                let mut xml = format!(r#"<{}Response xmlns="{}">"#, self.action, QUERY_NAMESPACE);
+                if result.as_object().is_some_and(|result| !result.is_empty()) {
+                    xml.push_str(&format!("<{}Result>", self.action));
+                    write_xml_fields(&mut xml, &self.action, &result);
+                    xml.push_str(&format!("</{}Result>", self.action));
+                }
+                xml.push_str(&format!(
+                    "<ResponseMetadata><RequestId>{}</RequestId></ResponseMetadata></{}Response>",
+                    request_id, self.action
+                ));
+                trace!("Query response: {}", xml);
+                ([(header::CONTENT_TYPE, "text/xml")], xml).into_response()
+            }
+            (Protocol::Query, Err(error)) => {
+                debug!("{} failed: {}", self.action, error.message);
+                let xml = format!(
+                    r#"<ErrorResponse xmlns="{}"><Error><Type>Sender</Type><Code>{}</Code><Message>{}</Message><Detail/></Error><RequestId>{}</RequestId></ErrorResponse>"#,
+                    QUERY_NAMESPACE,
+                    error.query_code,
+                    escape_xml(&error.message),
+                    request_id
+                );
+                (
+                    StatusCode::BAD_REQUEST,
+                    [(header::CONTENT_TYPE, "text/xml")],
+                    xml,
+                )
+                    .into_response()
+            }
+        }
+    }
+}
+
+/// Writes the fields of a JSON result as the query protocol's XML. Lists are flattened into
+/// repeated elements and maps become name and value pairs, as SQS returns them.
+fn write_xml_fields(xml: &mut String, action: &str, value: &Value) {
+    let Some(fields) = value.as_object() else {
+        return;
+    };
+
+    for (key, value) in fields {
+        match (key.as_str(), value) {
+            ("Attributes", Value::Object(attributes)) => {
+                for (name, value) in attributes {
+                    xml.push_str(&format!(
+                        "<Attribute><Name>{}</Name><Value>{}</Value></Attribute>",
+                        escape_xml(name),
+                        escape_xml(value.as_str().unwrap_or_default())
+                    ));
+                }
+            }
+            ("Tags", Value::Object(tags)) => {
+                for (key, value) in tags {
+                    xml.push_str(&format!(
+                        "<Tag><Key>{}</Key><Value>{}</Value></Tag>",
+                        escape_xml(key),
+                        escape_xml(value.as_str().unwrap_or_default())
+                    ));
+                }
+            }
+            ("MessageAttributes", Value::Object(attributes)) => {
+                for (name, value) in attributes {
+                    xml.push_str(&format!(
+                        "<MessageAttribute><Name>{}</Name><Value>",
+                        escape_xml(name)
+                    ));
+                    write_xml_fields(xml, action, value);
+                    xml.push_str("</Value></MessageAttribute>");
+                }
+            }
+            (key, Value::Array(items)) => {
+                let element = get_xml_element_name(action, key);
+                for item in items {
+                    xml.push_str(&format!("<{}>", element));
+                    match item {
+                        Value::Object(_) => write_xml_fields(xml, action, item),
+                        item => xml.push_str(&escape_xml(&get_xml_text(item))),
+                    }
+                    xml.push_str(&format!("</{}>", element));
+                }
+            }
+            (key, value) => {
+                xml.push_str(&format!(
+                    "<{}>{}</{}>",
+                    key,
+                    escape_xml(&get_xml_text(value)),
+                    key
+                ));
+            }
+        }
+    }
+}
+
+/// The name each item of a list is given in the query protocol
+fn get_xml_element_name(action: &str, key: &str) -> String {
+    match key {
+        "QueueUrls" => "QueueUrl".to_string(),
+        "Messages" => "Message".to_string(),
+        "Successful" => format!("{}ResultEntry", action),
+        "Failed" => "BatchResultErrorEntry".to_string(),
+        "StringListValues" => "StringListValue".to_string(),
+        "BinaryListValues" => "BinaryListValue".to_string(),
+        key => key.to_string(),
+    }
+}
+
+fn get_xml_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.to_string(),
+        Value::Null => String::new(),
+        value => value.to_string(),
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
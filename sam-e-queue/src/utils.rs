@@ -0,0 +1,74 @@
+use crate::data::MessageAttributeValue;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use md5::{Digest, Md5};
+use sha2::Sha256;
+use std::collections::HashMap;
+
+const STRING_TRANSPORT_TYPE: u8 = 1;
+const BINARY_TRANSPORT_TYPE: u8 = 2;
+
+pub fn md5_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Md5::digest(bytes))
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Works out the MD5 of message attributes as SQS does, so SDKs that check it accept the
+/// response. Attributes are sorted by name, then each name, data type and value is written with
+/// its length first, with a byte between the type and value for whether it's a string or binary.
+pub fn get_md5_of_message_attributes(
+    attributes: &HashMap<String, MessageAttributeValue>,
+) -> String {
+    let mut names = attributes.keys().collect::<Vec<_>>();
+    names.sort();
+
+    let mut buffer: Vec<u8> = vec![];
+    for name in names {
+        let attribute = &attributes[name];
+        push_with_length(&mut buffer, name.as_bytes());
+        push_with_length(&mut buffer, attribute.data_type.as_bytes());
+
+        if let Some(string_value) = &attribute.string_value {
+            buffer.push(STRING_TRANSPORT_TYPE);
+            push_with_length(&mut buffer, string_value.as_bytes());
+        } else if let Some(binary_value) = &attribute.binary_value {
+            buffer.push(BINARY_TRANSPORT_TYPE);
+            push_with_length(
+                &mut buffer,
+                &STANDARD.decode(binary_value).unwrap_or_default(),
+            );
+        }
+    }
+
+    md5_hex(&buffer)
+}
+
+/// Message attributes count towards the maximum message size, with their names and types
+pub fn get_message_attributes_size(attributes: &HashMap<String, MessageAttributeValue>) -> usize {
+    attributes
+        .iter()
+        .map(|(name, attribute)| {
+            let value_size = attribute
+                .string_value
+                .as_ref()
+                .map(|value| value.len())
+                .or_else(|| {
+                    attribute
+                        .binary_value
+                        .as_ref()
+                        .and_then(|value| STANDARD.decode(value).ok())
+                        .map(|value| value.len())
+                })
+                .unwrap_or_default();
+            name.len() + attribute.data_type.len() + value_size
+        })
+        .sum()
+}
+
+fn push_with_length(buffer: &mut Vec<u8>, bytes: &[u8]) {
+    buffer.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+    buffer.extend_from_slice(bytes);
+}
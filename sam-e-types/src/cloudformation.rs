@@ -1,3 +1,4 @@
+pub mod intrinsic;
 pub mod resource;
 pub mod template;
 // pub mod parameter;
@@ -0,0 +1,108 @@
+use serde_yaml::Value;
+
+/// Takes a value from the template that refers to another resource and returns the logical name of
+/// that resource. Handles plain strings (names or ARNs), the short form tags (i.e. `!Ref`,
+/// `!GetAtt`, `!Sub` and `!Join`) and their long form mappings (i.e. `Fn::GetAtt`). Pseudo
+/// parameters such as `${AWS::Region}` are ignored when looking through `!Sub` strings.
+pub fn get_referenced_resource(value: &Value) -> Option<String> {
+    match value {
+        Value::String(value) => resource_from_string(value),
+        Value::Tagged(tagged) => {
+            if tagged.tag == "Ref" {
+                tagged.value.as_str().map(|s| s.to_string())
+            } else if tagged.tag == "GetAtt" {
+                resource_from_get_att(&tagged.value)
+            } else if tagged.tag == "Sub" {
+                resource_from_sub(&tagged.value)
+            } else if tagged.tag == "Join" {
+                resource_from_join(&tagged.value)
+            } else {
+                None
+            }
+        }
+        Value::Mapping(mapping) => {
+            if let Some(reference) = mapping.get("Ref") {
+                reference.as_str().map(|s| s.to_string())
+            } else if let Some(get_att) = mapping.get("Fn::GetAtt") {
+                resource_from_get_att(get_att)
+            } else if let Some(sub) = mapping.get("Fn::Sub") {
+                resource_from_sub(sub)
+            } else if let Some(join) = mapping.get("Fn::Join") {
+                resource_from_join(join)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Returns the value as a string where it's a plain scalar (i.e. not a reference to another
+/// resource). Numbers and booleans are converted as CloudFormation would.
+pub fn get_scalar_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(value) => Some(value.to_string()),
+        Value::Number(value) => Some(value.to_string()),
+        Value::Bool(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+fn resource_from_string(value: &str) -> Option<String> {
+    if value.is_empty() {
+        return None;
+    }
+
+    // ARNs end with the resource name (i.e. arn:aws:lambda:eu-west-1:123456789012:function:Name)
+    if value.starts_with("arn:") {
+        return value.rsplit(':').next().map(|s| s.to_string());
+    }
+
+    Some(value.to_string())
+}
+
+fn resource_from_get_att(value: &Value) -> Option<String> {
+    match value {
+        Value::String(value) => value.split('.').next().map(|s| s.to_string()),
+        Value::Sequence(values) => values
+            .first()
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        _ => None,
+    }
+}
+
+fn resource_from_sub(value: &Value) -> Option<String> {
+    let sub_string = match value {
+        Value::String(value) => value.as_str(),
+        Value::Sequence(values) => values.first()?.as_str()?,
+        _ => return None,
+    };
+
+    let mut remaining = sub_string;
+    while let Some(start) = remaining.find("${") {
+        let after_start = &remaining[start + 2..];
+        let end = after_start.find('}')?;
+        let variable = &after_start[..end];
+
+        if !variable.starts_with("AWS::") && !variable.starts_with('!') {
+            return variable.split('.').next().map(|s| s.to_string());
+        }
+
+        remaining = &after_start[end + 1..];
+    }
+
+    None
+}
+
+/// Joins are a delimiter followed by the values to join. Only references within the values are of
+/// interest, with the first one found returned.
+fn resource_from_join(value: &Value) -> Option<String> {
+    value
+        .as_sequence()?
+        .get(1)?
+        .as_sequence()?
+        .iter()
+        .filter(|v| !v.is_string())
+        .find_map(get_referenced_resource)
+}
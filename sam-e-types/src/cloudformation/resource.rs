@@ -1,20 +1,32 @@
+pub mod api_key;
 pub mod apigw;
+pub mod apigw_v2;
 pub mod base_path_mapping;
 pub mod bucket;
 pub mod db_instance;
 pub mod event_bus;
 pub mod event_rule;
 pub mod function;
+pub mod http_api;
+pub mod lambda_url;
 pub mod queue;
+pub mod usage_plan;
 
+pub use api_key::ApiKey;
 pub use apigw::ApiGateway;
+pub use apigw_v2::{
+    ApiGatewayV2Api, ApiGatewayV2Integration, ApiGatewayV2Route, ApiGatewayV2Stage,
+};
 pub use base_path_mapping::BasePathMapping;
 pub use bucket::Bucket;
 pub use db_instance::DbInstance;
 pub use event_bus::EventBus;
 pub use event_rule::EventRule;
 pub use function::Function;
+pub use http_api::HttpApi;
+pub use lambda_url::{FunctionUrlConfig, LambdaUrl};
 pub use queue::Queue;
+pub use usage_plan::{UsagePlan, UsagePlanKey};
 
 use serde::{Deserialize, Serialize};
 
@@ -24,8 +36,26 @@ pub enum ResourceType {
     Function,
     #[serde(rename = "AWS::Serverless::Api")]
     ApiGateway,
+    #[serde(rename = "AWS::Serverless::HttpApi")]
+    HttpApi,
+    #[serde(rename = "AWS::ApiGatewayV2::Api")]
+    ApiGatewayV2Api,
+    #[serde(rename = "AWS::ApiGatewayV2::Route")]
+    ApiGatewayV2Route,
+    #[serde(rename = "AWS::ApiGatewayV2::Integration")]
+    ApiGatewayV2Integration,
+    #[serde(rename = "AWS::ApiGatewayV2::Stage")]
+    ApiGatewayV2Stage,
     #[serde(rename = "AWS::ApiGateway::BasePathMapping")]
     BasePathMapping,
+    #[serde(rename = "AWS::ApiGateway::ApiKey")]
+    ApiKey,
+    #[serde(rename = "AWS::ApiGateway::UsagePlan")]
+    UsagePlan,
+    #[serde(rename = "AWS::ApiGateway::UsagePlanKey")]
+    UsagePlanKey,
+    #[serde(rename = "AWS::Lambda::Url")]
+    LambdaUrl,
     #[serde(rename = "AWS::RDS::DBInstance")]
     DbInstance,
     #[serde(rename = "AWS::SQS::Queue")]
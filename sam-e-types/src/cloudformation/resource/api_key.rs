@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct ApiKey {
+    pub name: Option<Value>,
+    pub description: Option<Value>,
+    pub enabled: Option<Value>,
+    pub value: Option<Value>,
+    pub stage_keys: Option<Vec<StageKey>>,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct StageKey {
+    pub rest_api_id: Option<Value>,
+    pub stage_name: Option<Value>,
+}
@@ -1,17 +1,29 @@
 use serde::Deserialize;
 // use crate::cloudformation::template::CloudFormationValue as Value;
 use serde_yaml::Value;
+use std::collections::HashMap;
 
 #[derive(Deserialize, Debug, PartialEq, Eq)]
 #[serde(rename_all = "PascalCase")]
 pub struct ApiGateway {
-    name: Value,
+    name: Option<Value>,
     description: Option<Value>,
     stage_name: Option<Value>,
+    auth: Option<ApiAuth>,
+    /// Either a single origin or a mapping of the allowed origin, methods, headers etc. Values are
+    /// wrapped in single quotes as they're passed straight through as header values.
+    cors: Option<Value>,
+    /// An OpenAPI document defining the routes of the API, with the functions they are integrated
+    /// with given by `x-amazon-apigateway-integration`
+    definition_body: Option<Value>,
+    /// The location of the OpenAPI document when it's kept outside the template
+    definition_uri: Option<Value>,
+    /// JSON schemas, by name, that request bodies can be validated against
+    models: Option<HashMap<String, Value>>,
 }
 
 impl ApiGateway {
-    pub fn get_name(&self) -> &Value {
+    pub fn get_name(&self) -> &Option<Value> {
         &self.name
     }
 
@@ -22,4 +34,146 @@ impl ApiGateway {
     pub fn get_stage_name(&self) -> &Option<Value> {
         &self.stage_name
     }
+
+    pub fn get_auth(&self) -> &Option<ApiAuth> {
+        &self.auth
+    }
+
+    pub fn get_cors(&self) -> &Option<Value> {
+        &self.cors
+    }
+
+    pub fn get_definition_body(&self) -> &Option<Value> {
+        &self.definition_body
+    }
+
+    pub fn get_definition_uri(&self) -> &Option<Value> {
+        &self.definition_uri
+    }
+
+    pub fn get_models(&self) -> &Option<HashMap<String, Value>> {
+        &self.models
+    }
+}
+
+/// The `Auth` property shared by `AWS::Serverless::Api` and `AWS::Serverless::HttpApi`
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct ApiAuth {
+    default_authorizer: Option<Value>,
+    authorizers: Option<HashMap<String, ApiAuthorizer>>,
+    api_key_required: Option<Value>,
+}
+
+impl ApiAuth {
+    pub fn get_default_authorizer(&self) -> &Option<Value> {
+        &self.default_authorizer
+    }
+
+    pub fn get_authorizers(&self) -> &Option<HashMap<String, ApiAuthorizer>> {
+        &self.authorizers
+    }
+
+    pub fn get_api_key_required(&self) -> &Option<Value> {
+        &self.api_key_required
+    }
+}
+
+/// A single authorizer within the `Auth` property. REST and HTTP APIs use slightly different keys
+/// for the same settings so every key is optional here and resolved when building the config.
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct ApiAuthorizer {
+    function_arn: Option<Value>,
+    function_payload_type: Option<Value>,
+    authorizer_payload_format_version: Option<Value>,
+    enable_simple_responses: Option<Value>,
+    identity: Option<AuthorizerIdentity>,
+    identity_source: Option<Value>,
+    user_pool_arn: Option<Value>,
+    jwt_configuration: Option<JwtConfiguration>,
+}
+
+impl ApiAuthorizer {
+    pub fn get_function_arn(&self) -> &Option<Value> {
+        &self.function_arn
+    }
+
+    pub fn get_function_payload_type(&self) -> &Option<Value> {
+        &self.function_payload_type
+    }
+
+    pub fn get_authorizer_payload_format_version(&self) -> &Option<Value> {
+        &self.authorizer_payload_format_version
+    }
+
+    pub fn get_enable_simple_responses(&self) -> &Option<Value> {
+        &self.enable_simple_responses
+    }
+
+    pub fn get_identity(&self) -> &Option<AuthorizerIdentity> {
+        &self.identity
+    }
+
+    pub fn get_identity_source(&self) -> &Option<Value> {
+        &self.identity_source
+    }
+
+    pub fn get_user_pool_arn(&self) -> &Option<Value> {
+        &self.user_pool_arn
+    }
+
+    pub fn get_jwt_configuration(&self) -> &Option<JwtConfiguration> {
+        &self.jwt_configuration
+    }
+}
+
+/// Note: unlike the rest of the template, the keys of the JWT configuration are lowercase
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub struct JwtConfiguration {
+    issuer: Option<Value>,
+    audience: Option<Vec<Value>>,
+}
+
+impl JwtConfiguration {
+    pub fn get_issuer(&self) -> &Option<Value> {
+        &self.issuer
+    }
+
+    pub fn get_audience(&self) -> &Option<Vec<Value>> {
+        &self.audience
+    }
+}
+
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct AuthorizerIdentity {
+    header: Option<Value>,
+    headers: Option<Vec<Value>>,
+    query_strings: Option<Vec<Value>>,
+    validation_expression: Option<Value>,
+    reauthorize_every: Option<Value>,
+}
+
+impl AuthorizerIdentity {
+    pub fn get_header(&self) -> &Option<Value> {
+        &self.header
+    }
+
+    pub fn get_headers(&self) -> &Option<Vec<Value>> {
+        &self.headers
+    }
+
+    pub fn get_query_strings(&self) -> &Option<Vec<Value>> {
+        &self.query_strings
+    }
+
+    pub fn get_validation_expression(&self) -> &Option<Value> {
+        &self.validation_expression
+    }
+
+    pub fn get_reauthorize_every(&self) -> &Option<Value> {
+        &self.reauthorize_every
+    }
 }
@@ -0,0 +1,99 @@
+use serde::Deserialize;
+use serde_yaml::Value;
+
+/// An `AWS::ApiGatewayV2::Api`. Only WebSocket APIs are emulated this way - HTTP APIs are expected
+/// to use `AWS::Serverless::HttpApi`.
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct ApiGatewayV2Api {
+    name: Option<Value>,
+    description: Option<Value>,
+    protocol_type: Option<Value>,
+    route_selection_expression: Option<Value>,
+}
+
+impl ApiGatewayV2Api {
+    pub fn get_name(&self) -> &Option<Value> {
+        &self.name
+    }
+
+    pub fn get_description(&self) -> &Option<Value> {
+        &self.description
+    }
+
+    pub fn get_protocol_type(&self) -> &Option<Value> {
+        &self.protocol_type
+    }
+
+    pub fn get_route_selection_expression(&self) -> &Option<Value> {
+        &self.route_selection_expression
+    }
+}
+
+/// A route on an `AWS::ApiGatewayV2::Api`. The target is the integration the route sends requests
+/// to, in the form `integrations/<integration id>`.
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct ApiGatewayV2Route {
+    api_id: Value,
+    route_key: Value,
+    target: Option<Value>,
+    route_response_selection_expression: Option<Value>,
+}
+
+impl ApiGatewayV2Route {
+    pub fn get_api_id(&self) -> &Value {
+        &self.api_id
+    }
+
+    pub fn get_route_key(&self) -> &Value {
+        &self.route_key
+    }
+
+    pub fn get_target(&self) -> &Option<Value> {
+        &self.target
+    }
+
+    pub fn get_route_response_selection_expression(&self) -> &Option<Value> {
+        &self.route_response_selection_expression
+    }
+}
+
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct ApiGatewayV2Integration {
+    api_id: Value,
+    integration_type: Option<Value>,
+    integration_uri: Option<Value>,
+}
+
+impl ApiGatewayV2Integration {
+    pub fn get_api_id(&self) -> &Value {
+        &self.api_id
+    }
+
+    pub fn get_integration_type(&self) -> &Option<Value> {
+        &self.integration_type
+    }
+
+    pub fn get_integration_uri(&self) -> &Option<Value> {
+        &self.integration_uri
+    }
+}
+
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct ApiGatewayV2Stage {
+    api_id: Value,
+    stage_name: Value,
+}
+
+impl ApiGatewayV2Stage {
+    pub fn get_api_id(&self) -> &Value {
+        &self.api_id
+    }
+
+    pub fn get_stage_name(&self) -> &Value {
+        &self.stage_name
+    }
+}
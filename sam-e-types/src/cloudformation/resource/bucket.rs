@@ -1,12 +1,15 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 // use crate::cloudformation::template::CloudFormationValue as Value;
 use serde_yaml::Value;
 
 #[derive(Deserialize, Debug, PartialEq, Eq)]
 #[serde(rename_all = "PascalCase")]
 pub struct Bucket {
+    /// Buckets without a name are named after their resource
+    #[serde(default)]
     bucket_name: Value,
     notification_configuration: Option<NotificationConfiguration>,
+    cors_configuration: Option<CorsConfiguration>,
 }
 
 impl Bucket {
@@ -17,6 +20,54 @@ impl Bucket {
     pub fn get_notification_configuration(&self) -> &Option<NotificationConfiguration> {
         &self.notification_configuration
     }
+
+    pub fn get_cors_configuration(&self) -> &Option<CorsConfiguration> {
+        &self.cors_configuration
+    }
+}
+
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct CorsConfiguration {
+    cors_rules: Vec<CorsRule>,
+}
+
+impl CorsConfiguration {
+    pub fn get_cors_rules(&self) -> &Vec<CorsRule> {
+        &self.cors_rules
+    }
+}
+
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct CorsRule {
+    allowed_headers: Option<Vec<Value>>,
+    allowed_methods: Vec<Value>,
+    allowed_origins: Vec<Value>,
+    exposed_headers: Option<Vec<Value>>,
+    max_age: Option<Value>,
+}
+
+impl CorsRule {
+    pub fn get_allowed_headers(&self) -> &Option<Vec<Value>> {
+        &self.allowed_headers
+    }
+
+    pub fn get_allowed_methods(&self) -> &Vec<Value> {
+        &self.allowed_methods
+    }
+
+    pub fn get_allowed_origins(&self) -> &Vec<Value> {
+        &self.allowed_origins
+    }
+
+    pub fn get_exposed_headers(&self) -> &Option<Vec<Value>> {
+        &self.exposed_headers
+    }
+
+    pub fn get_max_age(&self) -> &Option<Value> {
+        &self.max_age
+    }
 }
 
 #[derive(Deserialize, Debug, PartialEq, Eq)]
@@ -24,6 +75,7 @@ impl Bucket {
 pub struct QueueConfigurations {
     event: Value,
     queue: Value,
+    filter: Option<NotificationFilter>,
 }
 
 impl QueueConfigurations {
@@ -34,6 +86,10 @@ impl QueueConfigurations {
     pub fn get_queue(&self) -> &Value {
         &self.queue
     }
+
+    pub fn get_filter(&self) -> &Option<NotificationFilter> {
+        &self.filter
+    }
 }
 
 #[derive(Deserialize, Debug, PartialEq, Eq)]
@@ -41,6 +97,7 @@ impl QueueConfigurations {
 pub struct LambdaConfigurations {
     event: Value,
     function: Value,
+    filter: Option<NotificationFilter>,
 }
 
 impl LambdaConfigurations {
@@ -51,13 +108,62 @@ impl LambdaConfigurations {
     pub fn get_function(&self) -> &Value {
         &self.function
     }
+
+    pub fn get_filter(&self) -> &Option<NotificationFilter> {
+        &self.filter
+    }
+}
+
+/// Limits the notifications to objects with keys matching the rules. Also used by SAM S3 events.
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct NotificationFilter {
+    #[serde(rename = "S3Key")]
+    s3_key: Option<S3KeyFilter>,
+}
+
+impl NotificationFilter {
+    pub fn get_s3_key(&self) -> &Option<S3KeyFilter> {
+        &self.s3_key
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct S3KeyFilter {
+    rules: Option<Vec<FilterRule>>,
+}
+
+impl S3KeyFilter {
+    pub fn get_rules(&self) -> &Option<Vec<FilterRule>> {
+        &self.rules
+    }
+}
+
+/// A `prefix` or `suffix` the object's key has to have
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct FilterRule {
+    name: Value,
+    value: Value,
+}
+
+impl FilterRule {
+    pub fn get_name(&self) -> &Value {
+        &self.name
+    }
+
+    pub fn get_value(&self) -> &Value {
+        &self.value
+    }
 }
 
 #[derive(Deserialize, Debug, PartialEq, Eq)]
 #[serde(rename_all = "PascalCase")]
 pub struct NotificationConfiguration {
     queue_configurations: Option<Vec<QueueConfigurations>>,
-    lambda_configurations: Option<Vec<Value>>,
+    lambda_configurations: Option<Vec<LambdaConfigurations>>,
+    event_bridge_configuration: Option<EventBridgeConfiguration>,
 }
 
 impl NotificationConfiguration {
@@ -65,7 +171,24 @@ impl NotificationConfiguration {
         &self.queue_configurations
     }
 
-    pub fn get_lambda_configurations(&self) -> &Option<Vec<Value>> {
+    pub fn get_lambda_configurations(&self) -> &Option<Vec<LambdaConfigurations>> {
         &self.lambda_configurations
     }
+
+    pub fn get_event_bridge_configuration(&self) -> &Option<EventBridgeConfiguration> {
+        &self.event_bridge_configuration
+    }
+}
+
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct EventBridgeConfiguration {
+    #[serde(default)]
+    event_bridge_enabled: Value,
+}
+
+impl EventBridgeConfiguration {
+    pub fn get_event_bridge_enabled(&self) -> &Value {
+        &self.event_bridge_enabled
+    }
 }
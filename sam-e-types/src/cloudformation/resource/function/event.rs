@@ -1,3 +1,5 @@
+use crate::cloudformation::resource::bucket::NotificationFilter;
+
 use serde::{Deserialize, Serialize};
 // use crate::cloudformation::template::CloudFormationValue as Value;
 use serde_yaml::Value;
@@ -6,8 +8,12 @@ use serde_yaml::Value;
 pub enum EventType {
     #[serde(rename = "Api")]
     Api,
+    #[serde(rename = "HttpApi")]
+    HttpApi,
     #[serde(rename = "SQS")]
     Sqs,
+    #[serde(rename = "S3")]
+    S3,
     #[serde(untagged)]
     Other(serde_yaml::Value),
 }
@@ -27,6 +33,11 @@ pub struct ApiEvent {
     method: Value,
     rest_api_id: Option<Value>,
     stage_name: Option<Value>,
+    auth: Option<ApiEventAuth>,
+    request_model: Option<ApiEventRequestModel>,
+    /// Either parameter names (i.e. `method.request.header.Authorization`) or mappings of the name
+    /// to its settings (`Required` and `Caching`)
+    request_parameters: Option<Vec<Value>>,
 }
 
 impl ApiEvent {
@@ -45,6 +56,97 @@ impl ApiEvent {
     pub fn get_stage_name(&self) -> &Option<Value> {
         &self.stage_name
     }
+
+    pub fn get_auth(&self) -> &Option<ApiEventAuth> {
+        &self.auth
+    }
+
+    pub fn get_request_model(&self) -> &Option<ApiEventRequestModel> {
+        &self.request_model
+    }
+
+    pub fn get_request_parameters(&self) -> &Option<Vec<Value>> {
+        &self.request_parameters
+    }
+}
+
+/// The model (from the API's `Models`) a REST API event's request body is validated against
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct ApiEventRequestModel {
+    model: Value,
+    required: Option<Value>,
+    validate_body: Option<Value>,
+    validate_parameters: Option<Value>,
+}
+
+impl ApiEventRequestModel {
+    pub fn get_model(&self) -> &Value {
+        &self.model
+    }
+
+    pub fn get_required(&self) -> &Option<Value> {
+        &self.required
+    }
+
+    pub fn get_validate_body(&self) -> &Option<Value> {
+        &self.validate_body
+    }
+
+    pub fn get_validate_parameters(&self) -> &Option<Value> {
+        &self.validate_parameters
+    }
+}
+
+/// An event from an `AWS::Serverless::HttpApi`. Unlike REST API events, both the path and method
+/// are optional and default to the catch-all `$default` route.
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct HttpApiEvent {
+    path: Option<Value>,
+    method: Option<Value>,
+    api_id: Option<Value>,
+    auth: Option<ApiEventAuth>,
+}
+
+impl HttpApiEvent {
+    pub fn get_path(&self) -> &Option<Value> {
+        &self.path
+    }
+
+    pub fn get_method(&self) -> &Option<Value> {
+        &self.method
+    }
+
+    pub fn get_api_id(&self) -> &Option<Value> {
+        &self.api_id
+    }
+
+    pub fn get_auth(&self) -> &Option<ApiEventAuth> {
+        &self.auth
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct ApiEventAuth {
+    authorizer: Option<Value>,
+    authorization_scopes: Option<Vec<Value>>,
+    api_key_required: Option<Value>,
+}
+
+impl ApiEventAuth {
+    pub fn get_authorizer(&self) -> &Option<Value> {
+        &self.authorizer
+    }
+
+    pub fn get_authorization_scopes(&self) -> &Option<Vec<Value>> {
+        &self.authorization_scopes
+    }
+
+    pub fn get_api_key_required(&self) -> &Option<Value> {
+        &self.api_key_required
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
@@ -52,6 +154,10 @@ impl ApiEvent {
 pub struct SqsEvent {
     queue: Value,
     batch_size: Option<Value>,
+    maximum_batching_window_in_seconds: Option<Value>,
+    scaling_config: Option<SqsScalingConfig>,
+    function_response_types: Option<Vec<Value>>,
+    filter_criteria: Option<SqsFilterCriteria>,
 }
 
 impl SqsEvent {
@@ -62,4 +168,79 @@ impl SqsEvent {
     pub fn get_batch_size(&self) -> &Option<Value> {
         &self.batch_size
     }
+
+    pub fn get_maximum_batching_window_in_seconds(&self) -> &Option<Value> {
+        &self.maximum_batching_window_in_seconds
+    }
+
+    pub fn get_scaling_config(&self) -> &Option<SqsScalingConfig> {
+        &self.scaling_config
+    }
+
+    pub fn get_function_response_types(&self) -> &Option<Vec<Value>> {
+        &self.function_response_types
+    }
+
+    pub fn get_filter_criteria(&self) -> &Option<SqsFilterCriteria> {
+        &self.filter_criteria
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct SqsScalingConfig {
+    maximum_concurrency: Option<Value>,
+}
+
+impl SqsScalingConfig {
+    pub fn get_maximum_concurrency(&self) -> &Option<Value> {
+        &self.maximum_concurrency
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct SqsFilterCriteria {
+    filters: Option<Vec<SqsFilter>>,
+}
+
+impl SqsFilterCriteria {
+    pub fn get_filters(&self) -> &Option<Vec<SqsFilter>> {
+        &self.filters
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct SqsFilter {
+    /// The filter pattern, usually as a JSON string
+    pattern: Value,
+}
+
+impl SqsFilter {
+    pub fn get_pattern(&self) -> &Value {
+        &self.pattern
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct S3Event {
+    bucket: Value,
+    events: Value,
+    filter: Option<NotificationFilter>,
+}
+
+impl S3Event {
+    pub fn get_bucket(&self) -> &Value {
+        &self.bucket
+    }
+
+    pub fn get_events(&self) -> &Value {
+        &self.events
+    }
+
+    pub fn get_filter(&self) -> &Option<NotificationFilter> {
+        &self.filter
+    }
 }
@@ -0,0 +1,48 @@
+use super::apigw::ApiAuth;
+
+use serde::Deserialize;
+use serde_yaml::Value;
+
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct HttpApi {
+    name: Option<Value>,
+    description: Option<Value>,
+    stage_name: Option<Value>,
+    auth: Option<ApiAuth>,
+    /// Either `true` to allow everything or a mapping of the allowed origins, methods, headers etc.
+    cors_configuration: Option<Value>,
+    /// The routes of the API as an OpenAPI document, the same as for `AWS::Serverless::Api`
+    definition_body: Option<Value>,
+    definition_uri: Option<Value>,
+}
+
+impl HttpApi {
+    pub fn get_name(&self) -> &Option<Value> {
+        &self.name
+    }
+
+    pub fn get_description(&self) -> &Option<Value> {
+        &self.description
+    }
+
+    pub fn get_stage_name(&self) -> &Option<Value> {
+        &self.stage_name
+    }
+
+    pub fn get_auth(&self) -> &Option<ApiAuth> {
+        &self.auth
+    }
+
+    pub fn get_cors_configuration(&self) -> &Option<Value> {
+        &self.cors_configuration
+    }
+
+    pub fn get_definition_body(&self) -> &Option<Value> {
+        &self.definition_body
+    }
+
+    pub fn get_definition_uri(&self) -> &Option<Value> {
+        &self.definition_uri
+    }
+}
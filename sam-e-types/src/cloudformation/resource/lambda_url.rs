@@ -0,0 +1,59 @@
+use serde::Deserialize;
+use serde_yaml::Value;
+
+/// The `FunctionUrlConfig` property of an `AWS::Serverless::Function`
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct FunctionUrlConfig {
+    auth_type: Value,
+    cors: Option<Value>,
+    invoke_mode: Option<Value>,
+}
+
+impl FunctionUrlConfig {
+    pub fn get_auth_type(&self) -> &Value {
+        &self.auth_type
+    }
+
+    pub fn get_cors(&self) -> &Option<Value> {
+        &self.cors
+    }
+
+    pub fn get_invoke_mode(&self) -> &Option<Value> {
+        &self.invoke_mode
+    }
+}
+
+/// An `AWS::Lambda::Url`, the standalone version of a function's `FunctionUrlConfig`. The target
+/// is the function the URL invokes.
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct LambdaUrl {
+    target_function_arn: Value,
+    auth_type: Value,
+    cors: Option<Value>,
+    invoke_mode: Option<Value>,
+    qualifier: Option<Value>,
+}
+
+impl LambdaUrl {
+    pub fn get_target_function_arn(&self) -> &Value {
+        &self.target_function_arn
+    }
+
+    pub fn get_auth_type(&self) -> &Value {
+        &self.auth_type
+    }
+
+    pub fn get_cors(&self) -> &Option<Value> {
+        &self.cors
+    }
+
+    pub fn get_invoke_mode(&self) -> &Option<Value> {
+        &self.invoke_mode
+    }
+
+    pub fn get_qualifier(&self) -> &Option<Value> {
+        &self.qualifier
+    }
+}
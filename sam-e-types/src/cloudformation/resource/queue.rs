@@ -1,3 +1,5 @@
+use super::event_bus::Tag;
+
 use serde::Deserialize;
 // use crate::cloudformation::template::CloudFormationValue as Value;
 use serde_yaml::Value;
@@ -8,11 +10,53 @@ pub struct Queue {
     queue_name: Option<Value>,
     redrive_policy: Option<Value>,
     visibility_timeout: Option<Value>,
+    delay_seconds: Option<Value>,
+    message_retention_period: Option<Value>,
+    receive_message_wait_time_seconds: Option<Value>,
+    maximum_message_size: Option<Value>,
     fifo_queue: Option<Value>,
+    content_based_deduplication: Option<Value>,
+    tags: Option<Vec<Tag>>,
 }
 
 impl Queue {
     pub fn get_queue_name(&self) -> &Option<Value> {
         &self.queue_name
     }
+
+    pub fn get_redrive_policy(&self) -> &Option<Value> {
+        &self.redrive_policy
+    }
+
+    pub fn get_visibility_timeout(&self) -> &Option<Value> {
+        &self.visibility_timeout
+    }
+
+    pub fn get_delay_seconds(&self) -> &Option<Value> {
+        &self.delay_seconds
+    }
+
+    pub fn get_message_retention_period(&self) -> &Option<Value> {
+        &self.message_retention_period
+    }
+
+    pub fn get_receive_message_wait_time_seconds(&self) -> &Option<Value> {
+        &self.receive_message_wait_time_seconds
+    }
+
+    pub fn get_maximum_message_size(&self) -> &Option<Value> {
+        &self.maximum_message_size
+    }
+
+    pub fn get_fifo_queue(&self) -> &Option<Value> {
+        &self.fifo_queue
+    }
+
+    pub fn get_content_based_deduplication(&self) -> &Option<Value> {
+        &self.content_based_deduplication
+    }
+
+    pub fn get_tags(&self) -> &Option<Vec<Tag>> {
+        &self.tags
+    }
 }
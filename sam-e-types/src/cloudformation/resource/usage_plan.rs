@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct UsagePlan {
+    pub usage_plan_name: Option<Value>,
+    pub description: Option<Value>,
+    pub api_stages: Option<Vec<ApiStage>>,
+    pub quota: Option<QuotaSettings>,
+    pub throttle: Option<ThrottleSettings>,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct ApiStage {
+    pub api_id: Option<Value>,
+    pub stage: Option<Value>,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct QuotaSettings {
+    pub limit: Option<Value>,
+    pub offset: Option<Value>,
+    pub period: Option<Value>,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct ThrottleSettings {
+    pub burst_limit: Option<Value>,
+    pub rate_limit: Option<Value>,
+}
+
+/// Links an API key to a usage plan
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct UsagePlanKey {
+    pub key_id: Value,
+    pub key_type: Option<Value>,
+    pub usage_plan_id: Value,
+}
@@ -29,6 +29,14 @@ pub struct Template {
     outputs: Option<serde_yaml::Value>,
 }
 
+impl Template {
+    /// The Globals section, holding properties shared by every resource of a given type (i.e.
+    /// `Api` or `HttpApi`)
+    pub fn get_globals(&self) -> &Option<serde_yaml::Value> {
+        &self.globals
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
 pub enum CloudFormationValue {
     Ref(String),
@@ -8,7 +8,7 @@ pub use lambda::Lambda;
 pub use runtime::Runtime;
 
 use frontend::Frontend;
-use infrastructure::triggers::Triggers;
+use infrastructure::{s3::NotificationFilter, triggers::Triggers};
 use lambda::event::EventProperties;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -78,6 +78,36 @@ impl Config {
                                 }
                             }
                         }
+                        EventProperties::S3(s3_properties) => {
+                            for infrastructure in self.infrastructure.iter_mut() {
+                                let Infrastructure::S3(bucket_infra) = infrastructure else {
+                                    continue;
+                                };
+                                let bucket_props = &mut bucket_infra.properties;
+                                if &bucket_props.name != s3_properties.get_bucket() {
+                                    continue;
+                                }
+
+                                let triggers = bucket_props
+                                    .triggers
+                                    .get_or_insert_with(|| Triggers::new(None, None));
+                                // The bucket's notification configuration may already name it
+                                if !triggers
+                                    .get_lambdas()
+                                    .iter()
+                                    .flatten()
+                                    .any(|name| name == lambda.get_name())
+                                {
+                                    triggers.add_lambda(lambda.get_name().to_string());
+                                }
+                                bucket_props.notification_filters.push(NotificationFilter {
+                                    destination: lambda.get_name().to_string(),
+                                    events: s3_properties.get_events().clone(),
+                                    prefix: s3_properties.get_prefix().cloned(),
+                                    suffix: s3_properties.get_suffix().cloned(),
+                                });
+                            }
+                        }
                         _ => {}
                     }
                 }
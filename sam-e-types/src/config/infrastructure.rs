@@ -1,21 +1,31 @@
+pub mod api;
+pub mod api_key;
 pub mod event_bus;
+pub mod function_url;
 pub mod mysql;
 pub mod postgres;
 pub mod s3;
 pub mod sqs;
 pub mod triggers;
+pub mod usage_plan;
+pub mod websocket_api;
 pub mod event_rule;
 
+pub use api::{ApiInfrastructure, ApiBuilder};
+pub use api_key::{ApiKeyInfrastructure, ApiKeyBuilder};
 pub use event_bus::{EventBusInfrastructure, EventBusBuilder};
 pub use event_rule::{EventRuleInfrastructure, EventRuleBuilder};
+pub use function_url::{FunctionUrlInfrastructure, FunctionUrlBuilder};
 pub use mysql::{MysqlInfrastructure, MysqlBuilder};
 pub use postgres::{PostgresInfrastructure, PostgresBuilder};
 pub use s3::{S3Infrastructure, S3Builder};
 pub use sqs::{QueueInfrastructure, QueueBuilder};
+pub use usage_plan::{UsagePlanInfrastructure, UsagePlanBuilder};
+pub use websocket_api::{WebSocketApiInfrastructure, WebSocketApiBuilder};
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize, Serialize, Clone, Eq, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 #[serde(tag = "infrastructure_type")]
 pub enum Infrastructure {
     #[serde(rename = "SQS")]
@@ -30,6 +40,16 @@ pub enum Infrastructure {
     EventBus(ResourceContainer<EventBusInfrastructure>),
     #[serde(rename = "EventRule")]
     EventRule(ResourceContainer<EventRuleInfrastructure>),
+    #[serde(rename = "Api")]
+    Api(ResourceContainer<ApiInfrastructure>),
+    #[serde(rename = "ApiKey")]
+    ApiKey(ResourceContainer<ApiKeyInfrastructure>),
+    #[serde(rename = "UsagePlan")]
+    UsagePlan(ResourceContainer<UsagePlanInfrastructure>),
+    #[serde(rename = "WebSocketApi")]
+    WebSocketApi(ResourceContainer<WebSocketApiInfrastructure>),
+    #[serde(rename = "FunctionUrl")]
+    FunctionUrl(ResourceContainer<FunctionUrlInfrastructure>),
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
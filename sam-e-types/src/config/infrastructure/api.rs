@@ -0,0 +1,525 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The authorizer name SAM uses to switch off the default authorizer for a single route
+pub const NO_AUTHORIZER: &str = "NONE";
+
+/// The logical name SAM gives the API created for events that don't reference one
+pub const IMPLICIT_REST_API: &str = "ServerlessRestApi";
+
+/// The logical name SAM gives the HTTP API created for events that don't reference one
+pub const IMPLICIT_HTTP_API: &str = "ServerlessHttpApi";
+
+/// The port APIs are served on unless given their own
+pub const DEFAULT_API_PORT: u16 = 3000;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiType {
+    Rest,
+    Http,
+}
+
+/// An API Gateway (`AWS::Serverless::Api` or `AWS::Serverless::HttpApi`) and the settings that
+/// apply to every route within it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct ApiInfrastructure {
+    pub name: String,
+    pub template_name: String,
+    pub api_type: ApiType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stage_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_authorizer: Option<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub authorizers: HashMap<String, Authorizer>,
+    #[serde(default)]
+    pub api_key_required: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cors: Option<Cors>,
+    /// The port the API is served on. APIs without one share the default port.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    /// The host header that selects this API where more than one API shares a port
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    /// JSON schemas, by name, that request bodies on REST API routes are validated against
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub models: HashMap<String, serde_yaml::Value>,
+}
+
+impl ApiInfrastructure {
+    pub fn get_authorizer(&self, name: &str) -> Option<&Authorizer> {
+        self.authorizers.get(name)
+    }
+
+    pub fn get_model(&self, name: &str) -> Option<&serde_yaml::Value> {
+        self.models.get(name)
+    }
+
+    pub fn get_port(&self) -> u16 {
+        self.port.unwrap_or(DEFAULT_API_PORT)
+    }
+
+    pub fn get_host(&self) -> String {
+        self.host
+            .clone()
+            .unwrap_or_else(|| default_api_host(&self.name))
+    }
+
+    /// The stage shown in the request context. HTTP APIs are served from the $default stage
+    /// unless the template names one.
+    pub fn get_stage_name(&self) -> &str {
+        match (&self.stage_name, self.api_type) {
+            (Some(stage_name), _) => stage_name,
+            (None, ApiType::Rest) => "Prod",
+            (None, ApiType::Http) => "$default",
+        }
+    }
+}
+
+/// Each API can be reached at <api name>.localhost by default, as *.localhost resolves to the
+/// local machine
+fn default_api_host(name: &str) -> String {
+    format!("{}.localhost", name.to_lowercase())
+}
+
+pub struct ApiBuilder {
+    name: Option<String>,
+    template_name: Option<String>,
+    api_type: Option<ApiType>,
+    stage_name: Option<String>,
+    default_authorizer: Option<String>,
+    authorizers: HashMap<String, Authorizer>,
+    api_key_required: bool,
+    cors: Option<Cors>,
+    port: Option<u16>,
+    host: Option<String>,
+    models: HashMap<String, serde_yaml::Value>,
+}
+
+impl Default for ApiBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ApiBuilder {
+    pub fn new() -> Self {
+        Self {
+            name: None,
+            template_name: None,
+            api_type: None,
+            stage_name: None,
+            default_authorizer: None,
+            authorizers: HashMap::new(),
+            api_key_required: false,
+            cors: None,
+            port: None,
+            host: None,
+            models: HashMap::new(),
+        }
+    }
+
+    pub fn name(mut self, name: String) -> Self {
+        self.name = Some(name);
+        self
+    }
+
+    pub fn template_name(mut self, template_name: String) -> Self {
+        self.template_name = Some(template_name);
+        self
+    }
+
+    pub fn api_type(mut self, api_type: ApiType) -> Self {
+        self.api_type = Some(api_type);
+        self
+    }
+
+    pub fn stage_name(mut self, stage_name: String) -> Self {
+        self.stage_name = Some(stage_name);
+        self
+    }
+
+    pub fn default_authorizer(mut self, default_authorizer: String) -> Self {
+        self.default_authorizer = Some(default_authorizer);
+        self
+    }
+
+    pub fn authorizer(mut self, name: String, authorizer: Authorizer) -> Self {
+        self.authorizers.insert(name, authorizer);
+        self
+    }
+
+    pub fn api_key_required(mut self, api_key_required: bool) -> Self {
+        self.api_key_required = api_key_required;
+        self
+    }
+
+    pub fn cors(mut self, cors: Cors) -> Self {
+        self.cors = Some(cors);
+        self
+    }
+
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    pub fn host(mut self, host: String) -> Self {
+        self.host = Some(host);
+        self
+    }
+
+    pub fn model(mut self, name: String, schema: serde_yaml::Value) -> Self {
+        self.models.insert(name, schema);
+        self
+    }
+
+    pub fn build(self) -> Result<ApiInfrastructure> {
+        let name = self.name.ok_or_else(|| anyhow!("Name is required"))?;
+        let template_name = self
+            .template_name
+            .ok_or_else(|| anyhow!("Template name is required"))?;
+        let api_type = self
+            .api_type
+            .ok_or_else(|| anyhow!("API type is required"))?;
+
+        if let Some(default_authorizer) = &self.default_authorizer {
+            if default_authorizer != NO_AUTHORIZER
+                && !self.authorizers.contains_key(default_authorizer)
+            {
+                return Err(anyhow!(
+                    "Default authorizer {} not found in authorizers for API {}",
+                    default_authorizer,
+                    name
+                ));
+            }
+        }
+
+        let host = self.host.unwrap_or_else(|| default_api_host(&name));
+
+        Ok(ApiInfrastructure {
+            name,
+            template_name,
+            api_type,
+            stage_name: self.stage_name,
+            default_authorizer: self.default_authorizer,
+            authorizers: self.authorizers,
+            api_key_required: self.api_key_required,
+            cors: self.cors,
+            port: self.port,
+            host: Some(host),
+            models: self.models,
+        })
+    }
+}
+
+/// The CORS settings of an API. REST APIs only answer the preflight (OPTIONS) request with these,
+/// leaving the function to add the headers to its own responses, whereas HTTP APIs add the headers
+/// to every response.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Cors {
+    pub allow_origins: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow_methods: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow_headers: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub expose_headers: Vec<String>,
+    #[serde(default)]
+    pub allow_credentials: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_age: Option<u64>,
+}
+
+impl Cors {
+    pub fn is_origin_allowed(&self, origin: &str) -> bool {
+        self.allow_origins
+            .iter()
+            .any(|allowed| allowed == "*" || allowed.eq_ignore_ascii_case(origin))
+    }
+
+    pub fn is_method_allowed(&self, method: &str) -> bool {
+        self.allow_methods
+            .iter()
+            .any(|allowed| allowed == "*" || allowed.eq_ignore_ascii_case(method))
+    }
+
+    pub fn is_header_allowed(&self, header: &str) -> bool {
+        self.allow_headers
+            .iter()
+            .any(|allowed| allowed == "*" || allowed.eq_ignore_ascii_case(header))
+    }
+}
+
+pub struct CorsBuilder {
+    allow_origins: Vec<String>,
+    allow_methods: Vec<String>,
+    allow_headers: Vec<String>,
+    expose_headers: Vec<String>,
+    allow_credentials: bool,
+    max_age: Option<u64>,
+}
+
+impl Default for CorsBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CorsBuilder {
+    pub fn new() -> Self {
+        Self {
+            allow_origins: vec![],
+            allow_methods: vec![],
+            allow_headers: vec![],
+            expose_headers: vec![],
+            allow_credentials: false,
+            max_age: None,
+        }
+    }
+
+    pub fn allow_origin(mut self, allow_origin: String) -> Self {
+        self.allow_origins.push(allow_origin);
+        self
+    }
+
+    pub fn allow_method(mut self, allow_method: String) -> Self {
+        self.allow_methods.push(allow_method);
+        self
+    }
+
+    pub fn allow_header(mut self, allow_header: String) -> Self {
+        self.allow_headers.push(allow_header);
+        self
+    }
+
+    pub fn expose_header(mut self, expose_header: String) -> Self {
+        self.expose_headers.push(expose_header);
+        self
+    }
+
+    pub fn allow_credentials(mut self, allow_credentials: bool) -> Self {
+        self.allow_credentials = allow_credentials;
+        self
+    }
+
+    pub fn max_age(mut self, max_age: u64) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    pub fn build(self) -> Result<Cors> {
+        if self.allow_origins.is_empty() {
+            return Err(anyhow!("At least one allowed origin is required"));
+        }
+
+        Ok(Cors {
+            allow_origins: self.allow_origins,
+            allow_methods: self.allow_methods,
+            allow_headers: self.allow_headers,
+            expose_headers: self.expose_headers,
+            allow_credentials: self.allow_credentials,
+            max_age: self.max_age,
+        })
+    }
+}
+
+/// An authorizer that protects routes on an API
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "Type")]
+pub enum Authorizer {
+    Lambda(LambdaAuthorizer),
+    /// A JWT authorizer on an HTTP API
+    Jwt(JwtAuthorizer),
+    /// A Cognito user pool authorizer on a REST API
+    Cognito(JwtAuthorizer),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LambdaAuthorizerType {
+    /// Receives just the token from a single header (REST APIs only)
+    Token,
+    /// Receives the full request with the identity sources
+    Request,
+}
+
+/// Where an authorizer reads the caller's identity from
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum IdentitySource {
+    Header(String),
+    QueryString(String),
+}
+
+/// A Lambda (custom) authorizer. The function is invoked through the invoker before the target
+/// function and its response decides whether the request is allowed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct LambdaAuthorizer {
+    pub function_name: String,
+    pub authorizer_type: LambdaAuthorizerType,
+    pub identity_sources: Vec<IdentitySource>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validation_expression: Option<String>,
+    pub result_ttl_seconds: u64,
+    /// HTTP APIs only - either "1.0" or "2.0"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload_format_version: Option<String>,
+    #[serde(default)]
+    pub enable_simple_responses: bool,
+}
+
+pub struct LambdaAuthorizerBuilder {
+    function_name: Option<String>,
+    authorizer_type: LambdaAuthorizerType,
+    identity_sources: Vec<IdentitySource>,
+    validation_expression: Option<String>,
+    result_ttl_seconds: u64,
+    payload_format_version: Option<String>,
+    enable_simple_responses: bool,
+}
+
+impl Default for LambdaAuthorizerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LambdaAuthorizerBuilder {
+    pub fn new() -> Self {
+        Self {
+            function_name: None,
+            authorizer_type: LambdaAuthorizerType::Token,
+            identity_sources: vec![],
+            validation_expression: None,
+            // API Gateway caches authorizer results for 5 minutes unless told otherwise
+            result_ttl_seconds: 300,
+            payload_format_version: None,
+            enable_simple_responses: false,
+        }
+    }
+
+    pub fn function_name(mut self, function_name: String) -> Self {
+        self.function_name = Some(function_name);
+        self
+    }
+
+    pub fn authorizer_type(mut self, authorizer_type: LambdaAuthorizerType) -> Self {
+        self.authorizer_type = authorizer_type;
+        self
+    }
+
+    pub fn identity_source(mut self, identity_source: IdentitySource) -> Self {
+        self.identity_sources.push(identity_source);
+        self
+    }
+
+    pub fn validation_expression(mut self, validation_expression: String) -> Self {
+        self.validation_expression = Some(validation_expression);
+        self
+    }
+
+    pub fn result_ttl_seconds(mut self, result_ttl_seconds: u64) -> Self {
+        self.result_ttl_seconds = result_ttl_seconds;
+        self
+    }
+
+    pub fn payload_format_version(mut self, payload_format_version: String) -> Self {
+        self.payload_format_version = Some(payload_format_version);
+        self
+    }
+
+    pub fn enable_simple_responses(mut self, enable_simple_responses: bool) -> Self {
+        self.enable_simple_responses = enable_simple_responses;
+        self
+    }
+
+    pub fn build(self) -> Result<LambdaAuthorizer> {
+        let function_name = self
+            .function_name
+            .ok_or_else(|| anyhow!("Function name is required"))?;
+
+        // TOKEN authorizers default to reading the Authorization header, where REQUEST authorizers
+        // without identity sources are sent every request
+        let identity_sources = if self.identity_sources.is_empty()
+            && self.authorizer_type == LambdaAuthorizerType::Token
+        {
+            vec![IdentitySource::Header("Authorization".to_string())]
+        } else {
+            self.identity_sources
+        };
+
+        Ok(LambdaAuthorizer {
+            function_name,
+            authorizer_type: self.authorizer_type,
+            identity_sources,
+            validation_expression: self.validation_expression,
+            result_ttl_seconds: self.result_ttl_seconds,
+            payload_format_version: self.payload_format_version,
+            enable_simple_responses: self.enable_simple_responses,
+        })
+    }
+}
+
+/// An authorizer that validates a JWT from a header. Locally, tokens are validated against the
+/// keys of the local token issuer rather than the real issuer (i.e. a Cognito user pool).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct JwtAuthorizer {
+    pub identity_header: String,
+    /// Only checked where the issuer can be resolved from the template
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issuer: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub audience: Vec<String>,
+}
+
+pub struct JwtAuthorizerBuilder {
+    identity_header: Option<String>,
+    issuer: Option<String>,
+    audience: Vec<String>,
+}
+
+impl Default for JwtAuthorizerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JwtAuthorizerBuilder {
+    pub fn new() -> Self {
+        Self {
+            identity_header: None,
+            issuer: None,
+            audience: vec![],
+        }
+    }
+
+    pub fn identity_header(mut self, identity_header: String) -> Self {
+        self.identity_header = Some(identity_header);
+        self
+    }
+
+    pub fn issuer(mut self, issuer: String) -> Self {
+        self.issuer = Some(issuer);
+        self
+    }
+
+    pub fn audience(mut self, audience: String) -> Self {
+        self.audience.push(audience);
+        self
+    }
+
+    pub fn build(self) -> JwtAuthorizer {
+        JwtAuthorizer {
+            identity_header: self
+                .identity_header
+                .unwrap_or_else(|| "Authorization".to_string()),
+            issuer: self.issuer,
+            audience: self.audience,
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// An API key for API Gateway. The value can be set in the config to match the key used by
+/// clients, otherwise one is generated when the environment is built.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct ApiKeyInfrastructure {
+    pub name: String,
+    pub template_name: String,
+    pub value: String,
+    pub enabled: bool,
+}
+
+pub struct ApiKeyBuilder {
+    name: Option<String>,
+    template_name: Option<String>,
+    value: Option<String>,
+    enabled: bool,
+}
+
+impl Default for ApiKeyBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ApiKeyBuilder {
+    pub fn new() -> Self {
+        Self {
+            name: None,
+            template_name: None,
+            value: None,
+            enabled: true,
+        }
+    }
+
+    pub fn name(mut self, name: String) -> Self {
+        self.name = Some(name);
+        self
+    }
+
+    pub fn template_name(mut self, template_name: String) -> Self {
+        self.template_name = Some(template_name);
+        self
+    }
+
+    pub fn value(mut self, value: String) -> Self {
+        self.value = Some(value);
+        self
+    }
+
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    pub fn build(self) -> Result<ApiKeyInfrastructure> {
+        let name = self.name.ok_or_else(|| anyhow!("Name is required"))?;
+        let template_name = self
+            .template_name
+            .ok_or_else(|| anyhow!("Template name is required"))?;
+
+        // API Gateway generates 40 character alphanumeric keys
+        let value = self.value.unwrap_or_else(|| {
+            format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())[..40].to_string()
+        });
+
+        Ok(ApiKeyInfrastructure {
+            name,
+            template_name,
+            value,
+            enabled: self.enabled,
+        })
+    }
+}
@@ -0,0 +1,114 @@
+use crate::config::infrastructure::api::Cors;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+/// The port the first function URL is served on, with any others taking the ports after it
+pub const DEFAULT_FUNCTION_URL_PORT: u16 = 3010;
+
+/// A function URL, either from the `FunctionUrlConfig` of a function or an `AWS::Lambda::Url`.
+/// Each one is served on its own port as every function URL has its own domain.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct FunctionUrlInfrastructure {
+    pub function_name: String,
+    pub template_name: String,
+    pub auth_type: FunctionUrlAuthType,
+    pub invoke_mode: InvokeMode,
+    pub cors: Option<Cors>,
+    pub port: u16,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FunctionUrlAuthType {
+    #[default]
+    #[serde(rename = "NONE")]
+    None,
+    #[serde(rename = "AWS_IAM")]
+    AwsIam,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InvokeMode {
+    #[default]
+    #[serde(rename = "BUFFERED")]
+    Buffered,
+    #[serde(rename = "RESPONSE_STREAM")]
+    ResponseStream,
+}
+
+pub struct FunctionUrlBuilder {
+    function_name: Option<String>,
+    template_name: Option<String>,
+    auth_type: Option<FunctionUrlAuthType>,
+    invoke_mode: Option<InvokeMode>,
+    cors: Option<Cors>,
+    port: Option<u16>,
+}
+
+impl Default for FunctionUrlBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FunctionUrlBuilder {
+    pub fn new() -> Self {
+        Self {
+            function_name: None,
+            template_name: None,
+            auth_type: None,
+            invoke_mode: None,
+            cors: None,
+            port: None,
+        }
+    }
+
+    pub fn function_name(mut self, function_name: String) -> Self {
+        self.function_name = Some(function_name);
+        self
+    }
+
+    pub fn template_name(mut self, template_name: String) -> Self {
+        self.template_name = Some(template_name);
+        self
+    }
+
+    pub fn auth_type(mut self, auth_type: FunctionUrlAuthType) -> Self {
+        self.auth_type = Some(auth_type);
+        self
+    }
+
+    pub fn invoke_mode(mut self, invoke_mode: InvokeMode) -> Self {
+        self.invoke_mode = Some(invoke_mode);
+        self
+    }
+
+    pub fn cors(mut self, cors: Cors) -> Self {
+        self.cors = Some(cors);
+        self
+    }
+
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    pub fn build(self) -> Result<FunctionUrlInfrastructure> {
+        let function_name = self
+            .function_name
+            .ok_or_else(|| anyhow!("Function name is required"))?;
+        let template_name = self
+            .template_name
+            .ok_or_else(|| anyhow!("Template name is required"))?;
+
+        Ok(FunctionUrlInfrastructure {
+            function_name,
+            template_name,
+            auth_type: self.auth_type.unwrap_or_default(),
+            invoke_mode: self.invoke_mode.unwrap_or_default(),
+            cors: self.cors,
+            port: self.port.unwrap_or(DEFAULT_FUNCTION_URL_PORT),
+        })
+    }
+}
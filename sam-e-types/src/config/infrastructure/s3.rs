@@ -10,12 +10,96 @@ pub struct S3Infrastructure {
     pub template_name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub triggers: Option<Triggers>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notification_filters: Vec<NotificationFilter>,
+    /// Whether notifications are also sent to the default event bus
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub event_bridge_enabled: bool,
+    /// Used by the local S3 store to answer cross-origin requests
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cors_rules: Vec<CorsRule>,
+}
+
+impl S3Infrastructure {
+    /// Checks whether the destination is sent notifications of the event for the object. A
+    /// destination can be configured more than once, and is sent every notification when it has no
+    /// filters at all.
+    pub fn is_notified(&self, destination: &str, event_name: &str, key: &str) -> bool {
+        let mut filters = self
+            .notification_filters
+            .iter()
+            .filter(|filter| filter.destination == destination)
+            .peekable();
+        if filters.peek().is_none() {
+            return true;
+        }
+
+        filters.any(|filter| filter.matches(event_name, key))
+    }
+}
+
+/// The events and object keys a destination is sent notifications for
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct NotificationFilter {
+    /// The name of the queue or function
+    pub destination: String,
+    /// Event types such as `s3:ObjectCreated:*` or `s3:ObjectRemoved:Delete`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suffix: Option<String>,
+}
+
+impl NotificationFilter {
+    /// Event names are matched with or without the `s3:` prefix, with a trailing `*` matching any
+    /// event of that type
+    pub fn matches(&self, event_name: &str, key: &str) -> bool {
+        let event_name = event_name.trim_start_matches("s3:");
+        let matches_event = self.events.is_empty()
+            || self.events.iter().any(|event| {
+                let event = event.trim_start_matches("s3:");
+                match event.strip_suffix('*') {
+                    Some(event_type) => event_name.starts_with(event_type),
+                    None => event_name == event,
+                }
+            });
+
+        matches_event
+            && self
+                .prefix
+                .as_ref()
+                .is_none_or(|prefix| key.starts_with(prefix))
+            && self
+                .suffix
+                .as_ref()
+                .is_none_or(|suffix| key.ends_with(suffix))
+    }
+}
+
+/// A rule for which origins can make cross-origin requests to the bucket, and how
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct CorsRule {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_headers: Vec<String>,
+    pub allowed_methods: Vec<String>,
+    pub allowed_origins: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exposed_headers: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_age: Option<u32>,
 }
 
 pub struct S3Builder {
     name: Option<String>,
     template_name: Option<String>,
     triggers: Option<Triggers>,
+    notification_filters: Vec<NotificationFilter>,
+    event_bridge_enabled: bool,
+    cors_rules: Vec<CorsRule>,
 }
 
 impl S3Builder {
@@ -24,6 +108,9 @@ impl S3Builder {
             name: None,
             template_name: None,
             triggers: None,
+            notification_filters: vec![],
+            event_bridge_enabled: false,
+            cors_rules: vec![],
         }
     }
 
@@ -42,6 +129,21 @@ impl S3Builder {
         self
     }
 
+    pub fn notification_filter(mut self, notification_filter: NotificationFilter) -> Self {
+        self.notification_filters.push(notification_filter);
+        self
+    }
+
+    pub fn event_bridge_enabled(mut self, event_bridge_enabled: bool) -> Self {
+        self.event_bridge_enabled = event_bridge_enabled;
+        self
+    }
+
+    pub fn cors_rule(mut self, cors_rule: CorsRule) -> Self {
+        self.cors_rules.push(cors_rule);
+        self
+    }
+
     pub fn build(self) -> Result<S3Infrastructure> {
         let name = self.name.ok_or_else(|| anyhow!("Name is required"))?;
         let template_name = self
@@ -52,6 +154,9 @@ impl S3Builder {
             name,
             template_name,
             triggers: self.triggers,
+            notification_filters: self.notification_filters,
+            event_bridge_enabled: self.event_bridge_enabled,
+            cors_rules: self.cors_rules,
         })
     }
 }
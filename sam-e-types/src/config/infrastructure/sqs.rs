@@ -2,6 +2,7 @@ use super::triggers::Triggers;
 
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "PascalCase")]
@@ -12,6 +13,61 @@ pub struct QueueInfrastructure {
     pub queue_url: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub triggers: Option<Triggers>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redrive_policy: Option<RedrivePolicy>,
+    #[serde(default)]
+    pub fifo_queue: bool,
+    #[serde(default)]
+    pub content_based_deduplication: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visibility_timeout: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delay_seconds: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_retention_period: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub receive_message_wait_time_seconds: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maximum_message_size: Option<u32>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub tags: HashMap<String, String>,
+}
+
+impl QueueInfrastructure {
+    /// The name the queue is created with locally. FIFO queue names have to end in .fifo
+    pub fn get_queue_name(&self) -> String {
+        if self.fifo_queue && !self.name.ends_with(".fifo") {
+            format!("{}.fifo", self.name)
+        } else {
+            self.name.to_string()
+        }
+    }
+
+    /// The attributes set in the template that the queue is created with, by their SQS names
+    pub fn get_attributes(&self) -> Vec<(&'static str, u32)> {
+        [
+            ("VisibilityTimeout", self.visibility_timeout),
+            ("DelaySeconds", self.delay_seconds),
+            ("MessageRetentionPeriod", self.message_retention_period),
+            (
+                "ReceiveMessageWaitTimeSeconds",
+                self.receive_message_wait_time_seconds,
+            ),
+            ("MaximumMessageSize", self.maximum_message_size),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| value.map(|value| (name, value)))
+        .collect()
+    }
+}
+
+/// Moves messages to a dead-letter queue once they have been received more than the max receive
+/// count without being deleted
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct RedrivePolicy {
+    pub dead_letter_queue: String,
+    pub max_receive_count: u32,
 }
 
 pub struct QueueBuilder {
@@ -19,6 +75,15 @@ pub struct QueueBuilder {
     template_name: Option<String>,
     queue_url: Option<String>,
     triggers: Option<Triggers>,
+    redrive_policy: Option<RedrivePolicy>,
+    fifo_queue: bool,
+    content_based_deduplication: bool,
+    visibility_timeout: Option<u32>,
+    delay_seconds: Option<u32>,
+    message_retention_period: Option<u32>,
+    receive_message_wait_time_seconds: Option<u32>,
+    maximum_message_size: Option<u32>,
+    tags: HashMap<String, String>,
 }
 
 impl QueueBuilder {
@@ -28,6 +93,15 @@ impl QueueBuilder {
             template_name: None,
             queue_url: None,
             triggers: None,
+            redrive_policy: None,
+            fifo_queue: false,
+            content_based_deduplication: false,
+            visibility_timeout: None,
+            delay_seconds: None,
+            message_retention_period: None,
+            receive_message_wait_time_seconds: None,
+            maximum_message_size: None,
+            tags: HashMap::new(),
         }
     }
 
@@ -51,6 +125,57 @@ impl QueueBuilder {
         self
     }
 
+    pub fn redrive_policy(mut self, dead_letter_queue: String, max_receive_count: u32) -> Self {
+        self.redrive_policy = Some(RedrivePolicy {
+            dead_letter_queue,
+            max_receive_count,
+        });
+        self
+    }
+
+    pub fn fifo_queue(mut self, fifo_queue: bool) -> Self {
+        self.fifo_queue = fifo_queue;
+        self
+    }
+
+    pub fn content_based_deduplication(mut self, content_based_deduplication: bool) -> Self {
+        self.content_based_deduplication = content_based_deduplication;
+        self
+    }
+
+    pub fn visibility_timeout(mut self, visibility_timeout: u32) -> Self {
+        self.visibility_timeout = Some(visibility_timeout);
+        self
+    }
+
+    pub fn delay_seconds(mut self, delay_seconds: u32) -> Self {
+        self.delay_seconds = Some(delay_seconds);
+        self
+    }
+
+    pub fn message_retention_period(mut self, message_retention_period: u32) -> Self {
+        self.message_retention_period = Some(message_retention_period);
+        self
+    }
+
+    pub fn receive_message_wait_time_seconds(
+        mut self,
+        receive_message_wait_time_seconds: u32,
+    ) -> Self {
+        self.receive_message_wait_time_seconds = Some(receive_message_wait_time_seconds);
+        self
+    }
+
+    pub fn maximum_message_size(mut self, maximum_message_size: u32) -> Self {
+        self.maximum_message_size = Some(maximum_message_size);
+        self
+    }
+
+    pub fn tag(mut self, key: String, value: String) -> Self {
+        self.tags.insert(key, value);
+        self
+    }
+
     pub fn build(self) -> Result<QueueInfrastructure> {
         let name = self.name.ok_or_else(|| anyhow!("Name is required"))?;
         let template_name = self
@@ -62,6 +187,15 @@ impl QueueBuilder {
             template_name,
             queue_url: self.queue_url,
             triggers: self.triggers,
+            redrive_policy: self.redrive_policy,
+            fifo_queue: self.fifo_queue,
+            content_based_deduplication: self.content_based_deduplication,
+            visibility_timeout: self.visibility_timeout,
+            delay_seconds: self.delay_seconds,
+            message_retention_period: self.message_retention_period,
+            receive_message_wait_time_seconds: self.receive_message_wait_time_seconds,
+            maximum_message_size: self.maximum_message_size,
+            tags: self.tags,
         })
     }
 }
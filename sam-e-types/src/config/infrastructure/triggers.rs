@@ -4,6 +4,9 @@ use tracing::{debug, error};
 
 use aws_sdk_sqs::{config::Region, Client};
 
+/// The message group FIFO queues are sent events in, so events reach them in order
+const FIFO_MESSAGE_GROUP: &str = "default";
+
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
 pub struct Triggers {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -49,7 +52,10 @@ impl Triggers {
         self.queues = Some(queues);
     }
 
-    pub async fn send(&self, event: String) -> Result<()> {
+    /// Sends the event to the queues, which have to be named as they're created locally. FIFO
+    /// queues are sent the event in a single message group, deduplicated by the event's ID as
+    /// EventBridge does.
+    pub async fn send(&self, event: String, event_id: &str, region: &str) -> Result<()> {
         if let Some(lambdas) = &self.lambdas {
             for lambda in lambdas {
                 debug!("Sending event {} to lambda {}", event, lambda);
@@ -58,12 +64,12 @@ impl Triggers {
 
         if let Some(queues) = &self.queues {
             debug!("Creating AWS SQS client");
-            let region = Region::new("eu-west-1");
+            let region = Region::new(region.to_string());
 
             // let config = aws_config::defaults(BehaviorVersion::v2023_11_09())
             let config = aws_config::from_env()
                 .region(region)
-                .endpoint_url("http://sqs-local:9324")
+                .endpoint_url("http://localhost:9324")
                 .load()
                 .await;
 
@@ -83,12 +89,16 @@ impl Triggers {
                     return Err(anyhow!("Queue URL not set for queue: {}", queue));
                 };
 
-                client
+                let mut send_message = client
                     .send_message()
                     .queue_url(queue_url)
-                    .message_body(&event)
-                    .send()
-                    .await?;
+                    .message_body(&event);
+                if queue.ends_with(".fifo") {
+                    send_message = send_message
+                        .message_group_id(FIFO_MESSAGE_GROUP)
+                        .message_deduplication_id(event_id);
+                }
+                send_message.send().await?;
 
                 debug!("Event sent to queue successfully");
             }
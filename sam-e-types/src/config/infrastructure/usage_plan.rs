@@ -0,0 +1,133 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+/// A usage plan for API Gateway. Limits how often the linked API keys can call the linked APIs.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct UsagePlanInfrastructure {
+    pub name: String,
+    pub template_name: String,
+    /// The logical names of the APIs the plan applies to
+    pub apis: Vec<String>,
+    /// The logical names of the API keys linked to the plan
+    pub api_keys: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub throttle: Option<Throttle>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quota: Option<Quota>,
+}
+
+impl UsagePlanInfrastructure {
+    pub fn applies_to(&self, api_name: &str, api_key_name: &str) -> bool {
+        self.apis.iter().any(|a| a == api_name) && self.api_keys.iter().any(|k| k == api_key_name)
+    }
+}
+
+/// Steady state rate (requests per second) and the burst allowed on top of it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Throttle {
+    pub burst_limit: u64,
+    /// Rates can be fractional, such as one request every two seconds
+    pub rate_limit: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaPeriod {
+    Day,
+    Week,
+    Month,
+}
+
+impl QuotaPeriod {
+    pub fn from_cloud_formation(period: &str) -> Option<Self> {
+        match period.to_uppercase().as_str() {
+            "DAY" => Some(QuotaPeriod::Day),
+            "WEEK" => Some(QuotaPeriod::Week),
+            "MONTH" => Some(QuotaPeriod::Month),
+            _ => None,
+        }
+    }
+}
+
+/// The maximum number of requests allowed in each period
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Quota {
+    pub limit: u64,
+    pub period: QuotaPeriod,
+}
+
+pub struct UsagePlanBuilder {
+    name: Option<String>,
+    template_name: Option<String>,
+    apis: Vec<String>,
+    api_keys: Vec<String>,
+    throttle: Option<Throttle>,
+    quota: Option<Quota>,
+}
+
+impl Default for UsagePlanBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UsagePlanBuilder {
+    pub fn new() -> Self {
+        Self {
+            name: None,
+            template_name: None,
+            apis: vec![],
+            api_keys: vec![],
+            throttle: None,
+            quota: None,
+        }
+    }
+
+    pub fn name(mut self, name: String) -> Self {
+        self.name = Some(name);
+        self
+    }
+
+    pub fn template_name(mut self, template_name: String) -> Self {
+        self.template_name = Some(template_name);
+        self
+    }
+
+    pub fn api(mut self, api: String) -> Self {
+        self.apis.push(api);
+        self
+    }
+
+    pub fn api_key(mut self, api_key: String) -> Self {
+        self.api_keys.push(api_key);
+        self
+    }
+
+    pub fn throttle(mut self, throttle: Throttle) -> Self {
+        self.throttle = Some(throttle);
+        self
+    }
+
+    pub fn quota(mut self, quota: Quota) -> Self {
+        self.quota = Some(quota);
+        self
+    }
+
+    pub fn build(self) -> Result<UsagePlanInfrastructure> {
+        let name = self.name.ok_or_else(|| anyhow!("Name is required"))?;
+        let template_name = self
+            .template_name
+            .ok_or_else(|| anyhow!("Template name is required"))?;
+
+        Ok(UsagePlanInfrastructure {
+            name,
+            template_name,
+            apis: self.apis,
+            api_keys: self.api_keys,
+            throttle: self.throttle,
+            quota: self.quota,
+        })
+    }
+}
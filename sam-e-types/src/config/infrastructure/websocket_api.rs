@@ -0,0 +1,120 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The port the first WebSocket API is served on, with any others taking the ports after it
+pub const DEFAULT_WEBSOCKET_PORT: u16 = 3004;
+
+/// The route used for messages that don't match any other route
+pub const DEFAULT_ROUTE: &str = "$default";
+
+/// A WebSocket API (`AWS::ApiGatewayV2::Api` with a `ProtocolType` of WEBSOCKET) and the
+/// functions its routes are integrated with
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct WebSocketApiInfrastructure {
+    pub name: String,
+    pub template_name: String,
+    pub stage_name: String,
+    /// Picks the route for each message, i.e. `$request.body.action`
+    pub route_selection_expression: String,
+    pub routes: HashMap<String, WebSocketRoute>,
+    pub port: u16,
+}
+
+impl WebSocketApiInfrastructure {
+    pub fn get_route(&self, route_key: &str) -> Option<&WebSocketRoute> {
+        self.routes.get(route_key)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct WebSocketRoute {
+    pub function_name: String,
+    /// Whether the function's response is sent back to the client (a route response is set)
+    #[serde(default)]
+    pub two_way: bool,
+}
+
+pub struct WebSocketApiBuilder {
+    name: Option<String>,
+    template_name: Option<String>,
+    stage_name: Option<String>,
+    route_selection_expression: Option<String>,
+    routes: HashMap<String, WebSocketRoute>,
+    port: Option<u16>,
+}
+
+impl Default for WebSocketApiBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WebSocketApiBuilder {
+    pub fn new() -> Self {
+        Self {
+            name: None,
+            template_name: None,
+            stage_name: None,
+            route_selection_expression: None,
+            routes: HashMap::new(),
+            port: None,
+        }
+    }
+
+    pub fn name(mut self, name: String) -> Self {
+        self.name = Some(name);
+        self
+    }
+
+    pub fn template_name(mut self, template_name: String) -> Self {
+        self.template_name = Some(template_name);
+        self
+    }
+
+    pub fn stage_name(mut self, stage_name: String) -> Self {
+        self.stage_name = Some(stage_name);
+        self
+    }
+
+    pub fn route_selection_expression(mut self, route_selection_expression: String) -> Self {
+        self.route_selection_expression = Some(route_selection_expression);
+        self
+    }
+
+    pub fn route(mut self, route_key: String, function_name: String, two_way: bool) -> Self {
+        self.routes.insert(
+            route_key,
+            WebSocketRoute {
+                function_name,
+                two_way,
+            },
+        );
+        self
+    }
+
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    pub fn build(self) -> Result<WebSocketApiInfrastructure> {
+        let name = self.name.ok_or_else(|| anyhow!("Name is required"))?;
+        let template_name = self
+            .template_name
+            .ok_or_else(|| anyhow!("Template name is required"))?;
+
+        Ok(WebSocketApiInfrastructure {
+            name,
+            template_name,
+            stage_name: self.stage_name.unwrap_or_else(|| "Prod".to_string()),
+            route_selection_expression: self
+                .route_selection_expression
+                .unwrap_or_else(|| "$request.body.action".to_string()),
+            routes: self.routes,
+            port: self.port.unwrap_or(DEFAULT_WEBSOCKET_PORT),
+        })
+    }
+}
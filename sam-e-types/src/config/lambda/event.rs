@@ -1,5 +1,6 @@
 use fancy_regex::Regex;
 use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
 
 /// Properties for an API event
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
@@ -9,6 +10,27 @@ pub struct EventApiProperties {
     base_path: Option<String>,
     method: String,
     route_regex: String,
+    /// The logical name of the API the route belongs to (if not the implicit API)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    api_id: Option<String>,
+    /// The authorizer set on the route, overriding the API's default authorizer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    authorizer: Option<String>,
+    /// Scopes the caller's token must have (at least one of) for JWT and Cognito authorizers
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    authorization_scopes: Vec<String>,
+    /// Overrides whether the API requires an API key for this route
+    #[serde(skip_serializing_if = "Option::is_none")]
+    api_key_required: Option<bool>,
+    /// The model (from the API's models) the request body must match
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_model: Option<String>,
+    /// Whether the request must have a body for the model to check, which is the default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_body_required: Option<bool>,
+    /// Parameters that must be in the request (i.e. `method.request.header.Authorization`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    required_parameters: Vec<String>,
 }
 
 impl EventApiProperties {
@@ -24,21 +46,257 @@ impl EventApiProperties {
         &self.method
     }
 
+    pub fn get_api_id(&self) -> Option<&String> {
+        self.api_id.as_ref()
+    }
+
+    pub fn set_api_id(&mut self, api_id: String) {
+        self.api_id = Some(api_id);
+    }
+
+    pub fn get_authorizer(&self) -> Option<&String> {
+        self.authorizer.as_ref()
+    }
+
+    pub fn set_authorizer(&mut self, authorizer: String) {
+        self.authorizer = Some(authorizer);
+    }
+
+    pub fn get_authorization_scopes(&self) -> &Vec<String> {
+        &self.authorization_scopes
+    }
+
+    pub fn set_authorization_scopes(&mut self, authorization_scopes: Vec<String>) {
+        self.authorization_scopes = authorization_scopes;
+    }
+
+    pub fn get_api_key_required(&self) -> Option<bool> {
+        self.api_key_required
+    }
+
+    pub fn set_api_key_required(&mut self, api_key_required: bool) {
+        self.api_key_required = Some(api_key_required);
+    }
+
+    pub fn get_request_model(&self) -> Option<&String> {
+        self.request_model.as_ref()
+    }
+
+    pub fn set_request_model(&mut self, request_model: String) {
+        self.request_model = Some(request_model);
+    }
+
+    pub fn is_request_body_required(&self) -> bool {
+        self.request_body_required.unwrap_or(true)
+    }
+
+    pub fn set_request_body_required(&mut self, request_body_required: bool) {
+        self.request_body_required = Some(request_body_required);
+    }
+
+    pub fn get_required_parameters(&self) -> &Vec<String> {
+        &self.required_parameters
+    }
+
+    pub fn set_required_parameters(&mut self, required_parameters: Vec<String>) {
+        self.required_parameters = required_parameters;
+    }
+
     pub fn get_route_regex(&self) -> Regex {
         Regex::new(&self.route_regex).expect("invalid regex")
     }
+
+    /// The path of the route including the base path (if one has been mapped)
+    pub fn get_full_path(&self) -> String {
+        match &self.base_path {
+            Some(base_path) => format!("/{}{}", base_path.trim_matches('/'), self.path),
+            None => self.path.to_owned(),
+        }
+    }
+
+    pub fn is_any_method(&self) -> bool {
+        self.method.eq_ignore_ascii_case("ANY")
+    }
+
+    /// Splits the full path into segments, classifying each one by how specific it is
+    pub fn get_route_segments(&self) -> Vec<RouteSegment> {
+        self.get_full_path()
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(RouteSegment::from_path_segment)
+            .collect()
+    }
+
+    /// The full path with parameter names removed (i.e. `/users/{id}` becomes `/users/{}`). Two
+    /// routes with the same template will match exactly the same requests.
+    pub fn get_route_template(&self) -> String {
+        let segments: Vec<String> = self
+            .get_full_path()
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| match RouteSegment::from_path_segment(segment) {
+                RouteSegment::Greedy => "{+}".to_string(),
+                RouteSegment::Parameter => "{}".to_string(),
+                RouteSegment::Exact => segment.to_string(),
+            })
+            .collect();
+
+        format!("/{}", segments.join("/"))
+    }
+
+    /// The precedence of the route when more than one route matches a request. Mirrors API
+    /// Gateway: paths are compared segment by segment (exact before parameterised before greedy)
+    /// and a specific method wins over `ANY` for the same path.
+    pub fn get_route_precedence(&self) -> (Vec<RouteSegment>, bool) {
+        (self.get_route_segments(), !self.is_any_method())
+    }
+}
+
+/// How specific a single segment of an API route is. Ordered from least to most specific so
+/// routes can be compared the same way API Gateway selects between overlapping routes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RouteSegment {
+    Greedy,
+    Parameter,
+    Exact,
+}
+
+impl RouteSegment {
+    pub fn from_path_segment(segment: &str) -> Self {
+        if segment.starts_with('{') && segment.ends_with("+}") {
+            RouteSegment::Greedy
+        } else if segment.starts_with('{') && segment.ends_with('}') {
+            RouteSegment::Parameter
+        } else {
+            RouteSegment::Exact
+        }
+    }
 }
 
+/// The number of messages sent to a function at once when the event doesn't set a batch size
+pub const DEFAULT_SQS_BATCH_SIZE: u16 = 10;
+
+/// The number of batches a queue sends to a function at once without a scaling config, matching
+/// the five pollers Lambda starts with for each queue
+pub const DEFAULT_SQS_MAXIMUM_CONCURRENCY: u16 = 5;
+
 /// Properties for an SQS event
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct EventSqsProperties {
     queue: String,
+    #[serde(default = "default_sqs_batch_size")]
+    batch_size: u16,
+    /// How long to wait for a full batch before invoking with the messages received so far
+    #[serde(default)]
+    maximum_batching_window: u16,
+    #[serde(default = "default_sqs_maximum_concurrency")]
+    maximum_concurrency: u16,
+    /// The function returns the messages it failed to process rather than failing the whole batch
+    #[serde(default)]
+    report_batch_item_failures: bool,
+    /// Messages are only sent to the function when they match one of these patterns. Messages that
+    /// don't match any are deleted without invoking the function
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    filter_patterns: Vec<Value>,
+}
+
+fn default_sqs_batch_size() -> u16 {
+    DEFAULT_SQS_BATCH_SIZE
+}
+
+fn default_sqs_maximum_concurrency() -> u16 {
+    DEFAULT_SQS_MAXIMUM_CONCURRENCY
 }
 
 impl EventSqsProperties {
     pub fn get_queue(&self) -> &String {
         &self.queue
     }
+
+    pub fn get_batch_size(&self) -> u16 {
+        self.batch_size
+    }
+
+    pub fn set_batch_size(&mut self, batch_size: u16) {
+        self.batch_size = batch_size;
+    }
+
+    /// The batching window in seconds
+    pub fn get_maximum_batching_window(&self) -> u16 {
+        self.maximum_batching_window
+    }
+
+    pub fn set_maximum_batching_window(&mut self, maximum_batching_window: u16) {
+        self.maximum_batching_window = maximum_batching_window;
+    }
+
+    pub fn get_maximum_concurrency(&self) -> u16 {
+        self.maximum_concurrency
+    }
+
+    pub fn set_maximum_concurrency(&mut self, maximum_concurrency: u16) {
+        self.maximum_concurrency = maximum_concurrency;
+    }
+
+    pub fn get_report_batch_item_failures(&self) -> bool {
+        self.report_batch_item_failures
+    }
+
+    pub fn set_report_batch_item_failures(&mut self, report_batch_item_failures: bool) {
+        self.report_batch_item_failures = report_batch_item_failures;
+    }
+
+    pub fn get_filter_patterns(&self) -> &Vec<Value> {
+        &self.filter_patterns
+    }
+
+    pub fn set_filter_patterns(&mut self, filter_patterns: Vec<Value>) {
+        self.filter_patterns = filter_patterns;
+    }
+}
+
+/// Properties for an S3 event
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct EventS3Properties {
+    /// The name of the bucket (rather than its logical name in the template)
+    bucket: String,
+    /// Event types such as `s3:ObjectCreated:*`, with no events meaning any event
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    events: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prefix: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    suffix: Option<String>,
+}
+
+impl EventS3Properties {
+    pub fn get_bucket(&self) -> &String {
+        &self.bucket
+    }
+
+    pub fn get_events(&self) -> &Vec<String> {
+        &self.events
+    }
+
+    pub fn set_events(&mut self, events: Vec<String>) {
+        self.events = events;
+    }
+
+    pub fn get_prefix(&self) -> Option<&String> {
+        self.prefix.as_ref()
+    }
+
+    pub fn set_prefix(&mut self, prefix: String) {
+        self.prefix = Some(prefix);
+    }
+
+    pub fn get_suffix(&self) -> Option<&String> {
+        self.suffix.as_ref()
+    }
+
+    pub fn set_suffix(&mut self, suffix: String) {
+        self.suffix = Some(suffix);
+    }
 }
 
 /// Properties for an event - abstracted to allow for different event types
@@ -48,6 +306,7 @@ impl EventSqsProperties {
 pub enum EventProperties {
     Api(EventApiProperties),
     Sqs(EventSqsProperties),
+    S3(EventS3Properties),
 }
 
 /// A Lambda function event as specified in the SAM template
@@ -74,6 +333,13 @@ impl Event {
             base_path,
             method,
             route_regex,
+            api_id: None,
+            authorizer: None,
+            authorization_scopes: vec![],
+            api_key_required: None,
+            request_model: None,
+            request_body_required: None,
+            required_parameters: vec![],
         };
 
         self.properties = Some(EventProperties::Api(api_props));
@@ -86,8 +352,22 @@ impl Event {
         }
     }
 
+    pub fn get_api_properties_mut(&mut self) -> Option<&mut EventApiProperties> {
+        match &mut self.properties {
+            Some(EventProperties::Api(api_properties)) => Some(api_properties),
+            _ => None,
+        }
+    }
+
     pub fn set_sqs_properties(&mut self, queue: String) {
-        let sqs_props = EventSqsProperties { queue };
+        let sqs_props = EventSqsProperties {
+            queue,
+            batch_size: DEFAULT_SQS_BATCH_SIZE,
+            maximum_batching_window: 0,
+            maximum_concurrency: DEFAULT_SQS_MAXIMUM_CONCURRENCY,
+            report_batch_item_failures: false,
+            filter_patterns: vec![],
+        };
         self.properties = Some(EventProperties::Sqs(sqs_props));
     }
 
@@ -98,6 +378,37 @@ impl Event {
         }
     }
 
+    pub fn get_sqs_properties_mut(&mut self) -> Option<&mut EventSqsProperties> {
+        match &mut self.properties {
+            Some(EventProperties::Sqs(sqs_properties)) => Some(sqs_properties),
+            _ => None,
+        }
+    }
+
+    pub fn set_s3_properties(&mut self, bucket: String) {
+        let s3_props = EventS3Properties {
+            bucket,
+            events: vec![],
+            prefix: None,
+            suffix: None,
+        };
+        self.properties = Some(EventProperties::S3(s3_props));
+    }
+
+    pub fn get_s3_properties(&self) -> Option<&EventS3Properties> {
+        match &self.properties {
+            Some(EventProperties::S3(s3_properties)) => Some(s3_properties),
+            _ => None,
+        }
+    }
+
+    pub fn get_s3_properties_mut(&mut self) -> Option<&mut EventS3Properties> {
+        match &mut self.properties {
+            Some(EventProperties::S3(s3_properties)) => Some(s3_properties),
+            _ => None,
+        }
+    }
+
     pub fn get_properties(&self) -> Option<&EventProperties> {
         self.properties.as_ref()
     }
@@ -139,3 +450,85 @@ fn replaced_regex_path(path: &str, base_path: &Option<String>) -> String {
         format!("^{}$", replaced_sam_path)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn api_properties(path: &str, base_path: Option<&str>, method: &str) -> EventApiProperties {
+        let mut event = Event::new(None);
+        event.set_api_properties(
+            path.to_string(),
+            base_path.map(str::to_string),
+            method.to_string(),
+        );
+        event.get_api_properties().cloned().unwrap()
+    }
+
+    #[test]
+    fn exact_segment_takes_precedence_over_parameter() {
+        let exact = api_properties("/users/me", None, "GET");
+        let parameter = api_properties("/users/{id}", None, "GET");
+
+        assert!(exact.get_route_precedence() > parameter.get_route_precedence());
+    }
+
+    #[test]
+    fn parameter_takes_precedence_over_greedy_parameter() {
+        let parameter = api_properties("/users/{id}", None, "GET");
+        let greedy = api_properties("/users/{proxy+}", None, "GET");
+
+        assert!(parameter.get_route_precedence() > greedy.get_route_precedence());
+    }
+
+    #[test]
+    fn specific_method_takes_precedence_over_any() {
+        let method = api_properties("/users/{id}", None, "GET");
+        let any = api_properties("/users/{id}", None, "ANY");
+
+        assert!(method.get_route_precedence() > any.get_route_precedence());
+    }
+
+    #[test]
+    fn path_takes_precedence_over_method() {
+        let exact_any = api_properties("/users/me", None, "ANY");
+        let parameter_method = api_properties("/users/{id}", None, "GET");
+
+        assert!(exact_any.get_route_precedence() > parameter_method.get_route_precedence());
+    }
+
+    #[test]
+    fn earlier_segments_take_precedence() {
+        let exact_first = api_properties("/users/me/{proxy+}", None, "GET");
+        let parameter_first = api_properties("/users/{id}/orders", None, "GET");
+
+        assert!(exact_first.get_route_precedence() > parameter_first.get_route_precedence());
+    }
+
+    #[test]
+    fn route_precedence_selects_most_specific_route() {
+        let routes = [
+            api_properties("/{proxy+}", None, "ANY"),
+            api_properties("/users/{proxy+}", None, "GET"),
+            api_properties("/users/{id}", None, "ANY"),
+            api_properties("/users/{id}", None, "GET"),
+            api_properties("/users/me", None, "ANY"),
+        ];
+
+        let selected = routes
+            .iter()
+            .max_by_key(|route| route.get_route_precedence())
+            .unwrap();
+
+        assert_eq!(selected.get_path(), "/users/me");
+    }
+
+    #[test]
+    fn route_template_ignores_parameter_names() {
+        let id = api_properties("/users/{id}/{proxy+}", Some("v1"), "GET");
+        let user_id = api_properties("/users/{userId}/{path+}", Some("v1"), "GET");
+
+        assert_eq!(id.get_route_template(), "/v1/users/{}/{+}");
+        assert_eq!(id.get_route_template(), user_id.get_route_template());
+    }
+}
@@ -3,6 +3,15 @@ pub mod template;
 use serde::{Deserialize, Serialize};
 use template::{Template, TemplateBuilder};
 
+/// The account ID used in ARNs and request contexts unless one is set in the runtime
+pub const DEFAULT_ACCOUNT_ID: &str = "123456789012";
+
+/// The region used in ARNs and request contexts unless one is set in the runtime
+pub const DEFAULT_REGION: &str = "eu-west-1";
+
+/// The API ID shown in API Gateway request contexts unless one is set in the runtime
+pub const DEFAULT_API_ID: &str = "1234567890";
+
 /// Configuration for the local runtime
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Runtime {
@@ -12,6 +21,24 @@ pub struct Runtime {
     use_s3_source: bool,
     credentials_location: String,
     docker_registry: Option<String>,
+    /// The account, region and API ID that events appear to come from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    account_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    region: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    api_id: Option<String>,
+    /// Serves the API source, function URLs and frontend over HTTPS. A self-signed certificate is
+    /// generated unless the certificate and key locations are set
+    #[serde(default)]
+    use_https: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    certificate_location: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    certificate_key_location: Option<String>,
+    /// Serves buckets from the S3 source on port 9000 rather than a MinIO container
+    #[serde(default)]
+    use_s3_store: bool,
 }
 
 impl Default for Runtime {
@@ -23,6 +50,13 @@ impl Default for Runtime {
             use_s3_source: false,
             credentials_location: String::from(""),
             docker_registry: None,
+            account_id: None,
+            region: None,
+            api_id: None,
+            use_https: false,
+            certificate_location: None,
+            certificate_key_location: None,
+            use_s3_store: false,
         }
     }
 }
@@ -75,6 +109,48 @@ impl Runtime {
             .as_ref()
             .ok_or_else(|| anyhow::anyhow!("Docker registry is not set"))
     }
+
+    /// The account ID as set in the runtime, without the default
+    pub fn get_account_id_setting(&self) -> Option<&String> {
+        self.account_id.as_ref()
+    }
+
+    pub fn get_region_setting(&self) -> Option<&String> {
+        self.region.as_ref()
+    }
+
+    pub fn get_api_id_setting(&self) -> Option<&String> {
+        self.api_id.as_ref()
+    }
+
+    pub fn get_account_id(&self) -> &str {
+        self.account_id.as_deref().unwrap_or(DEFAULT_ACCOUNT_ID)
+    }
+
+    pub fn get_region(&self) -> &str {
+        self.region.as_deref().unwrap_or(DEFAULT_REGION)
+    }
+
+    pub fn get_api_id(&self) -> &str {
+        self.api_id.as_deref().unwrap_or(DEFAULT_API_ID)
+    }
+
+    pub fn get_use_https(&self) -> bool {
+        self.use_https
+    }
+
+    /// The certificate provided by the user, in PEM format
+    pub fn get_certificate_location(&self) -> Option<&String> {
+        self.certificate_location.as_ref()
+    }
+
+    pub fn get_certificate_key_location(&self) -> Option<&String> {
+        self.certificate_key_location.as_ref()
+    }
+
+    pub fn get_use_s3_store(&self) -> bool {
+        self.use_s3_store
+    }
 }
 
 pub struct RuntimeBuilder {
@@ -84,6 +160,13 @@ pub struct RuntimeBuilder {
     use_s3_source: bool,
     credentials_location: Option<String>,
     docker_registry: Option<String>,
+    account_id: Option<String>,
+    region: Option<String>,
+    api_id: Option<String>,
+    use_https: bool,
+    certificate_location: Option<String>,
+    certificate_key_location: Option<String>,
+    use_s3_store: bool,
 }
 
 impl RuntimeBuilder {
@@ -95,6 +178,13 @@ impl RuntimeBuilder {
             use_s3_source: false,
             credentials_location: None,
             docker_registry: None,
+            account_id: None,
+            region: None,
+            api_id: None,
+            use_https: false,
+            certificate_location: None,
+            certificate_key_location: None,
+            use_s3_store: false,
         }
     }
 
@@ -132,6 +222,44 @@ impl RuntimeBuilder {
         self
     }
 
+    pub fn with_account_id(mut self, account_id: Option<String>) -> Self {
+        self.account_id = account_id;
+        self
+    }
+
+    pub fn with_region(mut self, region: Option<String>) -> Self {
+        self.region = region;
+        self
+    }
+
+    pub fn with_api_id(mut self, api_id: Option<String>) -> Self {
+        self.api_id = api_id;
+        self
+    }
+
+    pub fn with_use_https(mut self, use_https: bool) -> Self {
+        self.use_https = use_https;
+        self
+    }
+
+    pub fn with_certificate_location(mut self, certificate_location: Option<String>) -> Self {
+        self.certificate_location = certificate_location;
+        self
+    }
+
+    pub fn with_certificate_key_location(
+        mut self,
+        certificate_key_location: Option<String>,
+    ) -> Self {
+        self.certificate_key_location = certificate_key_location;
+        self
+    }
+
+    pub fn with_use_s3_store(mut self, use_s3_store: bool) -> Self {
+        self.use_s3_store = use_s3_store;
+        self
+    }
+
     pub fn build(self) -> Runtime {
         let Some(credentials_location) = self.credentials_location else {
             panic!("Credentials location must be set");
@@ -144,6 +272,13 @@ impl RuntimeBuilder {
             use_s3_source: self.use_s3_source,
             credentials_location,
             docker_registry: self.docker_registry,
+            account_id: self.account_id,
+            region: self.region,
+            api_id: self.api_id,
+            use_https: self.use_https,
+            certificate_location: self.certificate_location,
+            certificate_key_location: self.certificate_key_location,
+            use_s3_store: self.use_s3_store,
         }
     }
 }
@@ -1,7 +1,14 @@
 use anyhow::{anyhow, Result};
 use aws_lambda_events::{
-    apigw::ApiGatewayProxyResponse,
-    event::{apigw::ApiGatewayProxyRequest, sqs::SqsEvent},
+    apigw::{
+        ApiGatewayCustomAuthorizerRequest, ApiGatewayCustomAuthorizerRequestTypeRequest,
+        ApiGatewayProxyResponse, ApiGatewayV2CustomAuthorizerV2Request,
+        ApiGatewayWebsocketProxyRequest,
+    },
+    event::{
+        apigw::ApiGatewayProxyRequest, lambda_function_urls::LambdaFunctionUrlRequest, s3::S3Event,
+        sqs::SqsEvent,
+    },
 };
 use chrono::{DateTime, Local};
 use serde::{Deserialize, Serialize};
@@ -19,6 +26,19 @@ pub enum Status {
 pub enum EventRequest {
     Api(ApiGatewayProxyRequest),
     Sqs(SqsEvent),
+    Authorizer(AuthorizerRequest),
+    WebSocket(ApiGatewayWebsocketProxyRequest),
+    FunctionUrl(LambdaFunctionUrlRequest),
+    S3(S3Event),
+}
+
+/// The event sent to a Lambda authorizer. The shape depends on the type of authorizer and the
+/// payload format version of the API.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub enum AuthorizerRequest {
+    Token(ApiGatewayCustomAuthorizerRequest),
+    Request(Box<ApiGatewayCustomAuthorizerRequestTypeRequest>),
+    HttpV2(Box<ApiGatewayV2CustomAuthorizerV2Request>),
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -0,0 +1,633 @@
+use crate::data::ApiState;
+use sam_e_types::{
+    config::{
+        infrastructure::{
+            api::{
+                ApiType, Authorizer, IdentitySource, JwtAuthorizer, LambdaAuthorizer,
+                LambdaAuthorizerType, NO_AUTHORIZER,
+            },
+            ApiInfrastructure,
+        },
+        lambda::event::EventApiProperties,
+    },
+    invocation::{AuthorizerRequest, EventRequest, InvocationBuilder},
+};
+
+use anyhow::Result;
+use aws_lambda_events::{
+    apigw::{
+        ApiGatewayCustomAuthorizerRequest, ApiGatewayCustomAuthorizerRequestTypeRequest,
+        ApiGatewayCustomAuthorizerRequestTypeRequestContext,
+        ApiGatewayCustomAuthorizerRequestTypeRequestIdentity, ApiGatewayCustomAuthorizerResponse,
+        ApiGatewayProxyRequest, ApiGatewayV2CustomAuthorizerSimpleResponse,
+        ApiGatewayV2CustomAuthorizerV2Request, ApiGatewayV2httpRequestContext,
+        ApiGatewayV2httpRequestContextHttpDescription,
+    },
+    iam::IamPolicyEffect,
+};
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use fancy_regex::Regex;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+use tracing::{debug, error, info, trace, warn};
+use uuid::Uuid;
+
+/// Authorizer results cached by authorizer and identity, as API Gateway does between requests
+pub type AuthorizerCache = HashMap<String, CachedAuthorization>;
+
+#[derive(Debug, Clone)]
+pub struct CachedAuthorization {
+    result: AuthorizerResult,
+    expires_at: Instant,
+}
+
+/// The outcome of invoking an authorizer. Policies are kept whole (rather than just allow/deny) so
+/// a cached policy can be evaluated against other routes, like API Gateway does.
+#[derive(Debug, Clone)]
+enum AuthorizerResult {
+    Policy(ApiGatewayCustomAuthorizerResponse),
+    Simple(ApiGatewayV2CustomAuthorizerSimpleResponse),
+    Unauthorized,
+}
+
+/// Runs the authorizer for the matched route (if there is one) before the request is passed to
+/// the Lambda. Returns the response to send back if the request isn't authorized, otherwise adds
+/// the authorizer context to the request and returns `None`.
+pub async fn authorize(
+    api_state: &ApiState,
+    api_props: &EventApiProperties,
+    api_request: &mut ApiGatewayProxyRequest,
+) -> Result<Option<Response>> {
+    let Some(api) = api_state.get_api(api_props.get_api_id()) else {
+        trace!("Route is not attached to a configured API. Skipping authorization");
+        return Ok(None);
+    };
+
+    let authorizer_name = api_props
+        .get_authorizer()
+        .or(api.default_authorizer.as_ref());
+    let Some(authorizer_name) = authorizer_name else {
+        trace!("No authorizer set for route. Skipping authorization");
+        return Ok(None);
+    };
+
+    if authorizer_name == NO_AUTHORIZER {
+        trace!("Authorizer explicitly disabled for route");
+        return Ok(None);
+    }
+
+    let Some(authorizer) = api.get_authorizer(authorizer_name) else {
+        warn!(
+            "Authorizer {} is not emulated locally. Allowing request",
+            authorizer_name
+        );
+        return Ok(None);
+    };
+
+    match authorizer {
+        Authorizer::Lambda(lambda_authorizer) => {
+            authorize_with_lambda(
+                api_state,
+                api,
+                authorizer_name,
+                lambda_authorizer,
+                api_request,
+            )
+            .await
+        }
+        Authorizer::Jwt(jwt_authorizer) | Authorizer::Cognito(jwt_authorizer) => Ok(
+            authorize_with_jwt(api_state, api, authorizer, jwt_authorizer, api_props, api_request),
+        ),
+    }
+}
+
+/// Validates the bearer token against the local issuer. The claims are added to the request
+/// under `jwt.claims` (and `claims` for Cognito authorizers, as REST APIs do).
+fn authorize_with_jwt(
+    api_state: &ApiState,
+    api: &ApiInfrastructure,
+    authorizer: &Authorizer,
+    jwt_authorizer: &JwtAuthorizer,
+    api_props: &EventApiProperties,
+    api_request: &mut ApiGatewayProxyRequest,
+) -> Option<Response> {
+    let Some(header_value) = api_request
+        .headers
+        .get(jwt_authorizer.identity_header.as_str())
+        .and_then(|v| v.to_str().ok())
+    else {
+        info!("No token found in request. Returning unauthorized");
+        return Some(unauthorized());
+    };
+
+    let token = header_value
+        .strip_prefix("Bearer ")
+        .unwrap_or(header_value)
+        .trim();
+
+    let claims = match api_state.get_issuer().verify(token, jwt_authorizer) {
+        Ok(claims) => claims,
+        Err(e) => {
+            info!("Invalid token: {}. Returning unauthorized", e);
+            return Some(unauthorized());
+        }
+    };
+
+    let scopes: Vec<String> = match claims.get("scope").or(claims.get("scp")) {
+        Some(serde_json::Value::String(scope)) => {
+            scope.split(' ').map(|s| s.to_string()).collect()
+        }
+        Some(serde_json::Value::Array(scopes)) => scopes
+            .iter()
+            .filter_map(|s| s.as_str())
+            .map(|s| s.to_string())
+            .collect(),
+        _ => vec![],
+    };
+
+    let required_scopes = api_props.get_authorization_scopes();
+    if !required_scopes.is_empty() && !required_scopes.iter().any(|s| scopes.contains(s)) {
+        // REST APIs treat the token as unauthorized, where HTTP APIs forbid the request
+        info!("Token does not have any of the scopes required by the route");
+        return Some(match api.api_type {
+            ApiType::Rest => unauthorized(),
+            ApiType::Http => forbidden(api.api_type, false),
+        });
+    }
+
+    // API Gateway passes all claims through as strings
+    let string_claims: serde_json::Map<String, serde_json::Value> = claims
+        .into_iter()
+        .map(|(key, value)| {
+            let value = match value {
+                serde_json::Value::String(value) => value,
+                other => other.to_string(),
+            };
+            (key, serde_json::Value::String(value))
+        })
+        .collect();
+
+    let authorizer_fields = &mut api_request.request_context.authorizer.fields;
+    authorizer_fields.insert(
+        "jwt".to_string(),
+        serde_json::json!({
+            "claims": string_claims,
+            "scopes": if scopes.is_empty() { None } else { Some(scopes) },
+        }),
+    );
+    if let Authorizer::Cognito(_) = authorizer {
+        authorizer_fields.insert(
+            "claims".to_string(),
+            serde_json::Value::Object(string_claims),
+        );
+    }
+
+    None
+}
+
+async fn authorize_with_lambda(
+    api_state: &ApiState,
+    api: &ApiInfrastructure,
+    authorizer_name: &str,
+    authorizer: &LambdaAuthorizer,
+    api_request: &mut ApiGatewayProxyRequest,
+) -> Result<Option<Response>> {
+    debug!(
+        "Authorizing request with Lambda authorizer: {}",
+        authorizer_name
+    );
+
+    let mut identity_values = vec![];
+    for identity_source in authorizer.identity_sources.iter() {
+        let value = match identity_source {
+            IdentitySource::Header(header) => api_request
+                .headers
+                .get(header.as_str())
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_string()),
+            IdentitySource::QueryString(query_string) => api_request
+                .query_string_parameters
+                .first(query_string)
+                .map(|v| v.to_string()),
+        };
+
+        match value {
+            Some(value) if !value.is_empty() => identity_values.push(value),
+            _ => {
+                info!(
+                    "Identity source {:?} missing from request. Returning unauthorized",
+                    identity_source
+                );
+                return Ok(Some(unauthorized()));
+            }
+        }
+    }
+
+    if let Some(validation_expression) = &authorizer.validation_expression {
+        let token = identity_values.first().map(|v| v.as_str()).unwrap_or("");
+        let full_expression = format!("^(?:{})$", validation_expression);
+        let is_valid = Regex::new(&full_expression)
+            .map(|regex| regex.is_match(token).unwrap_or(false))
+            .unwrap_or_else(|e| {
+                warn!("Invalid validation expression for authorizer: {}", e);
+                true
+            });
+
+        if !is_valid {
+            info!("Token failed the authorizer validation expression. Returning unauthorized");
+            return Ok(Some(unauthorized()));
+        }
+    }
+
+    let method_arn = get_method_arn(api_state.get_region(), api_request);
+    let cache_key = format!(
+        "{}:{}:{}",
+        api.name,
+        authorizer_name,
+        identity_values.join(",")
+    );
+
+    // Results can only be cached against an identity, so REQUEST authorizers without identity
+    // sources are invoked every time
+    let is_cached = authorizer.result_ttl_seconds > 0 && !identity_values.is_empty();
+    let cached_result = if is_cached {
+        api_state
+            .get_authorizer_cache()
+            .read()
+            .get(&cache_key)
+            .filter(|cached| cached.expires_at > Instant::now())
+            .map(|cached| cached.result.clone())
+    } else {
+        None
+    };
+
+    let result = match cached_result {
+        Some(result) => {
+            debug!("Using cached result for authorizer: {}", authorizer_name);
+            result
+        }
+        None => {
+            let result = invoke_authorizer(
+                api_state,
+                api,
+                authorizer,
+                api_request,
+                &method_arn,
+                identity_values,
+            )
+            .await?;
+
+            let Some(result) = result else {
+                return Ok(Some(
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(serde_json::json!({ "message": null })),
+                    )
+                        .into_response(),
+                ));
+            };
+
+            if is_cached {
+                api_state.get_authorizer_cache().write().insert(
+                    cache_key,
+                    CachedAuthorization {
+                        result: result.clone(),
+                        expires_at: Instant::now()
+                            + Duration::from_secs(authorizer.result_ttl_seconds),
+                    },
+                );
+            }
+
+            result
+        }
+    };
+
+    match result {
+        AuthorizerResult::Unauthorized => Ok(Some(unauthorized())),
+        AuthorizerResult::Simple(response) => {
+            if !response.is_authorized {
+                info!("Authorizer denied the request");
+                return Ok(Some(forbidden(api.api_type, false)));
+            }
+
+            add_authorizer_context(api, api_request, None, response.context);
+            Ok(None)
+        }
+        AuthorizerResult::Policy(response) => {
+            let mut allowed = false;
+            for statement in response.policy_document.statement.iter() {
+                let action_matches = statement
+                    .action
+                    .iter()
+                    .any(|action| wildcard_match(action, "execute-api:Invoke"));
+                let resource_matches = statement
+                    .resource
+                    .iter()
+                    .any(|resource| wildcard_match(resource, &method_arn));
+
+                if !action_matches || !resource_matches {
+                    continue;
+                }
+
+                match statement.effect {
+                    IamPolicyEffect::Deny => {
+                        info!("Authorizer policy explicitly denies: {}", method_arn);
+                        return Ok(Some(forbidden(api.api_type, true)));
+                    }
+                    IamPolicyEffect::Allow => allowed = true,
+                }
+            }
+
+            if !allowed {
+                info!("Authorizer policy does not allow: {}", method_arn);
+                return Ok(Some(forbidden(api.api_type, false)));
+            }
+
+            add_authorizer_context(api, api_request, response.principal_id, response.context);
+            Ok(None)
+        }
+    }
+}
+
+/// Invokes the authorizer Lambda through the invoker and parses the response. Returns `None`
+/// where the authorizer failed in a way API Gateway would report as a server error.
+async fn invoke_authorizer(
+    api_state: &ApiState,
+    api: &ApiInfrastructure,
+    authorizer: &LambdaAuthorizer,
+    api_request: &ApiGatewayProxyRequest,
+    method_arn: &str,
+    identity_values: Vec<String>,
+) -> Result<Option<AuthorizerResult>> {
+    let is_v2_payload = api.api_type == ApiType::Http
+        && authorizer.payload_format_version.as_deref() == Some("2.0");
+
+    let authorizer_request = if is_v2_payload {
+        AuthorizerRequest::HttpV2(Box::new(create_v2_request(
+            api_request,
+            method_arn,
+            identity_values,
+        )))
+    } else {
+        match authorizer.authorizer_type {
+            LambdaAuthorizerType::Token => {
+                AuthorizerRequest::Token(ApiGatewayCustomAuthorizerRequest {
+                    type_: Some("TOKEN".to_string()),
+                    authorization_token: identity_values.into_iter().next(),
+                    method_arn: Some(method_arn.to_string()),
+                })
+            }
+            LambdaAuthorizerType::Request => {
+                AuthorizerRequest::Request(Box::new(create_request_type_request(
+                    api_request,
+                    method_arn,
+                )))
+            }
+        }
+    };
+
+    let invocation = InvocationBuilder::new()
+        .with_request(EventRequest::Authorizer(authorizer_request))
+        .with_request_id(Uuid::new_v4())
+        .with_lambda_name(authorizer.function_name.to_string())
+        .build()?;
+
+    debug!("Invoking authorizer: {}", authorizer.function_name);
+    let response = api_state
+        .get_client()
+        .post("http://0.0.0.0:3030/invoke")
+        .json(&serde_json::json!(invocation))
+        .send()
+        .await?;
+
+    let status = response.status();
+    let response_data = response.json::<serde_json::Value>().await?;
+    trace!("Authorizer response: {:#?}", response_data);
+
+    if !status.is_success() {
+        // Authorizers signal an invalid token by failing with the message "Unauthorized"
+        let error_message = response_data
+            .get("errorMessage")
+            .and_then(|m| m.as_str())
+            .unwrap_or_default();
+        if error_message == "Unauthorized" {
+            return Ok(Some(AuthorizerResult::Unauthorized));
+        }
+
+        error!("Authorizer failed: {}", response_data);
+        return Ok(None);
+    }
+
+    let result = if is_v2_payload && authorizer.enable_simple_responses {
+        serde_json::from_value::<ApiGatewayV2CustomAuthorizerSimpleResponse>(response_data)
+            .map(AuthorizerResult::Simple)
+    } else {
+        serde_json::from_value::<ApiGatewayCustomAuthorizerResponse>(response_data)
+            .map(AuthorizerResult::Policy)
+    };
+
+    match result {
+        Ok(result) => Ok(Some(result)),
+        Err(e) => {
+            error!("Unable to parse the authorizer response: {}", e);
+            Ok(None)
+        }
+    }
+}
+
+fn create_request_type_request(
+    api_request: &ApiGatewayProxyRequest,
+    method_arn: &str,
+) -> ApiGatewayCustomAuthorizerRequestTypeRequest {
+    let request_context = &api_request.request_context;
+
+    ApiGatewayCustomAuthorizerRequestTypeRequest {
+        type_: Some("REQUEST".to_string()),
+        method_arn: Some(method_arn.to_string()),
+        resource: api_request.resource.clone(),
+        path: api_request.path.clone(),
+        http_method: Some(api_request.http_method.clone()),
+        headers: api_request.headers.clone(),
+        multi_value_headers: api_request.multi_value_headers.clone(),
+        query_string_parameters: api_request.query_string_parameters.clone(),
+        multi_value_query_string_parameters: api_request
+            .multi_value_query_string_parameters
+            .clone(),
+        path_parameters: api_request.path_parameters.clone(),
+        stage_variables: api_request.stage_variables.clone(),
+        request_context: ApiGatewayCustomAuthorizerRequestTypeRequestContext {
+            path: request_context.path.clone(),
+            account_id: request_context.account_id.clone(),
+            resource_id: request_context.resource_id.clone(),
+            stage: request_context.stage.clone(),
+            request_id: request_context.request_id.clone(),
+            identity: Some(ApiGatewayCustomAuthorizerRequestTypeRequestIdentity {
+                source_ip: request_context.identity.source_ip.clone(),
+                ..Default::default()
+            }),
+            resource_path: request_context.resource_path.clone(),
+            http_method: Some(api_request.http_method.clone()),
+            apiid: request_context.apiid.clone(),
+        },
+    }
+}
+
+fn create_v2_request(
+    api_request: &ApiGatewayProxyRequest,
+    method_arn: &str,
+    identity_values: Vec<String>,
+) -> ApiGatewayV2CustomAuthorizerV2Request {
+    let request_context = &api_request.request_context;
+    let route_key = format!(
+        "{} {}",
+        api_request.http_method,
+        api_request.resource.clone().unwrap_or_default()
+    );
+    let raw_query_string = api_request
+        .query_string_parameters
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<String>>()
+        .join("&");
+
+    ApiGatewayV2CustomAuthorizerV2Request {
+        version: Some("2.0".to_string()),
+        type_: Some("REQUEST".to_string()),
+        route_arn: Some(method_arn.to_string()),
+        identity_source: Some(identity_values),
+        route_key: Some(route_key.clone()),
+        raw_path: api_request.path.clone(),
+        raw_query_string: Some(raw_query_string),
+        cookies: vec![],
+        headers: api_request.headers.clone(),
+        query_string_parameters: api_request
+            .query_string_parameters
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+        request_context: ApiGatewayV2httpRequestContext {
+            route_key: Some(route_key),
+            account_id: request_context.account_id.clone(),
+            stage: request_context.stage.clone(),
+            request_id: request_context.request_id.clone(),
+            apiid: request_context.apiid.clone(),
+            domain_name: request_context.domain_name.clone(),
+            domain_prefix: request_context.domain_prefix.clone(),
+            time: request_context.request_time.clone(),
+            time_epoch: request_context.request_time_epoch,
+            http: ApiGatewayV2httpRequestContextHttpDescription {
+                method: api_request.http_method.clone(),
+                path: api_request.path.clone(),
+                protocol: request_context.protocol.clone(),
+                source_ip: request_context.identity.source_ip.clone(),
+                user_agent: request_context.identity.user_agent.clone(),
+            },
+            ..Default::default()
+        },
+        path_parameters: api_request.path_parameters.clone(),
+        stage_variables: api_request.stage_variables.clone(),
+    }
+}
+
+/// Adds the authorizer output to the request context so the Lambda can read it. REST APIs merge
+/// the context into the authorizer object whereas HTTP APIs nest it under `lambda`.
+fn add_authorizer_context(
+    api: &ApiInfrastructure,
+    api_request: &mut ApiGatewayProxyRequest,
+    principal_id: Option<String>,
+    context: serde_json::Value,
+) {
+    let authorizer_fields = &mut api_request.request_context.authorizer.fields;
+
+    if let Some(principal_id) = principal_id {
+        authorizer_fields.insert(
+            "principalId".to_string(),
+            serde_json::Value::String(principal_id),
+        );
+    }
+
+    match api.api_type {
+        ApiType::Rest => {
+            if let serde_json::Value::Object(context) = context {
+                authorizer_fields.extend(context);
+            }
+        }
+        ApiType::Http => {
+            if !context.is_null() {
+                authorizer_fields.insert("lambda".to_string(), context);
+            }
+        }
+    }
+}
+
+/// The ARN API Gateway passes to authorizers, i.e.
+/// `arn:aws:execute-api:{region}:{account}:{api_id}/{stage}/{method}/{path}`
+fn get_method_arn(region: &str, api_request: &ApiGatewayProxyRequest) -> String {
+    let request_context = &api_request.request_context;
+    format!(
+        "arn:aws:execute-api:{}:{}:{}/{}/{}{}",
+        region,
+        request_context.account_id.clone().unwrap_or_default(),
+        request_context.apiid.clone().unwrap_or_default(),
+        request_context.stage.clone().unwrap_or_default(),
+        api_request.http_method,
+        api_request.path.clone().unwrap_or_default()
+    )
+}
+
+/// Matches a value against an IAM style pattern where `*` matches any number of characters and
+/// `?` matches a single character
+fn wildcard_match(pattern: &str, value: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let value: Vec<char> = value.chars().collect();
+
+    let (mut p, mut v) = (0, 0);
+    let mut last_star: Option<(usize, usize)> = None;
+
+    while v < value.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == value[v]) {
+            p += 1;
+            v += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            last_star = Some((p, v));
+            p += 1;
+        } else if let Some((star_p, star_v)) = last_star {
+            p = star_p + 1;
+            v = star_v + 1;
+            last_star = Some((star_p, star_v + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+fn unauthorized() -> Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        Json(serde_json::json!({ "message": "Unauthorized" })),
+    )
+        .into_response()
+}
+
+/// The forbidden response differs between REST and HTTP APIs (down to the casing of the key)
+fn forbidden(api_type: ApiType, explicit_deny: bool) -> Response {
+    let body = match (api_type, explicit_deny) {
+        (ApiType::Rest, true) => serde_json::json!({
+            "Message": "User is not authorized to access this resource with an explicit deny"
+        }),
+        (ApiType::Rest, false) => serde_json::json!({
+            "Message": "User is not authorized to access this resource"
+        }),
+        (ApiType::Http, _) => serde_json::json!({ "message": "Forbidden" }),
+    };
+
+    (StatusCode::FORBIDDEN, Json(body)).into_response()
+}
@@ -0,0 +1,217 @@
+use crate::{data::ApiState, utils::find_lambda_with_base_path};
+use sam_e_types::config::infrastructure::api::{ApiType, Cors};
+
+use axum::{
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use tracing::{debug, trace};
+
+/// Answers a CORS preflight (OPTIONS) request using the CORS settings of the API the requested
+/// route belongs to. Returns `None` when the request isn't a preflight or the API doesn't have
+/// CORS set, leaving the request to be handled like any other.
+pub fn preflight(api_state: &ApiState, headers: &HeaderMap, path: &str) -> Option<Response> {
+    let origin = header_str(headers, header::ORIGIN)?;
+    let request_method = header_str(headers, header::ACCESS_CONTROL_REQUEST_METHOD)?;
+
+    let api_lambdas = api_state.get_api_lambdas(header_str(headers, header::HOST));
+    let (_, matched_event) =
+        find_lambda_with_base_path(api_lambdas.iter().collect(), path, request_method).ok()?;
+    let api_props = matched_event.get_api_properties()?;
+    let api = api_state.get_api(api_props.get_api_id())?;
+    let cors = api.cors.as_ref()?;
+
+    debug!(
+        "Answering CORS preflight request for {} {}",
+        request_method, path
+    );
+    trace!("CORS settings: {:?}", cors);
+
+    let response = match api.api_type {
+        ApiType::Rest => rest_preflight(cors),
+        ApiType::Http => http_preflight(cors, headers, origin, request_method),
+    };
+
+    Some(response)
+}
+
+/// Answers a CORS preflight request to a function URL, which is handled the same as one to an
+/// HTTP API. Returns `None` when the request isn't a preflight.
+pub fn function_url_preflight(cors: &Cors, headers: &HeaderMap) -> Option<Response> {
+    let origin = header_str(headers, header::ORIGIN)?;
+    let request_method = header_str(headers, header::ACCESS_CONTROL_REQUEST_METHOD)?;
+
+    debug!(
+        "Answering CORS preflight request to function URL for {}",
+        request_method
+    );
+    trace!("CORS settings: {:?}", cors);
+
+    Some(http_preflight(cors, headers, origin, request_method))
+}
+
+/// REST APIs answer the preflight with a mock integration that returns the configured headers as
+/// they are, whatever the request
+fn rest_preflight(cors: &Cors) -> Response {
+    let mut response_headers = HeaderMap::new();
+    if let Some(allow_origin) = cors.allow_origins.first() {
+        insert(
+            &mut response_headers,
+            header::ACCESS_CONTROL_ALLOW_ORIGIN,
+            allow_origin,
+        );
+    }
+    if !cors.allow_methods.is_empty() {
+        insert(
+            &mut response_headers,
+            header::ACCESS_CONTROL_ALLOW_METHODS,
+            &cors.allow_methods.join(","),
+        );
+    }
+    if !cors.allow_headers.is_empty() {
+        insert(
+            &mut response_headers,
+            header::ACCESS_CONTROL_ALLOW_HEADERS,
+            &cors.allow_headers.join(","),
+        );
+    }
+    insert_preflight_settings(cors, &mut response_headers);
+
+    (StatusCode::OK, response_headers).into_response()
+}
+
+/// HTTP APIs (and function URLs) only return the headers when the origin, method and headers are
+/// all allowed
+fn http_preflight(
+    cors: &Cors,
+    headers: &HeaderMap,
+    origin: &str,
+    request_method: &str,
+) -> Response {
+    let request_headers: Vec<&str> = header_str(headers, header::ACCESS_CONTROL_REQUEST_HEADERS)
+        .map(|h| {
+            h.split(',')
+                .map(|h| h.trim())
+                .filter(|h| !h.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if !cors.is_origin_allowed(origin)
+        || !cors.is_method_allowed(request_method)
+        || !request_headers.iter().all(|h| cors.is_header_allowed(h))
+    {
+        debug!("CORS preflight request not allowed by the CORS settings");
+        return StatusCode::NO_CONTENT.into_response();
+    }
+
+    let mut response_headers = HeaderMap::new();
+    insert(
+        &mut response_headers,
+        header::ACCESS_CONTROL_ALLOW_ORIGIN,
+        &allowed_origin(cors, origin),
+    );
+    insert(
+        &mut response_headers,
+        header::ACCESS_CONTROL_ALLOW_METHODS,
+        &echo_wildcard(&cors.allow_methods, &[request_method]),
+    );
+    if !request_headers.is_empty() {
+        insert(
+            &mut response_headers,
+            header::ACCESS_CONTROL_ALLOW_HEADERS,
+            &echo_wildcard(&cors.allow_headers, &request_headers),
+        );
+    }
+    insert_preflight_settings(cors, &mut response_headers);
+
+    (StatusCode::NO_CONTENT, response_headers).into_response()
+}
+
+fn insert_preflight_settings(cors: &Cors, response_headers: &mut HeaderMap) {
+    if let Some(max_age) = cors.max_age {
+        insert(
+            response_headers,
+            header::ACCESS_CONTROL_MAX_AGE,
+            &max_age.to_string(),
+        );
+    }
+    if cors.allow_credentials {
+        insert(
+            response_headers,
+            header::ACCESS_CONTROL_ALLOW_CREDENTIALS,
+            "true",
+        );
+    }
+}
+
+/// Adds the CORS headers to a response from an HTTP API (or function URL). REST APIs leave this to
+/// the function so nothing is added to their responses.
+pub fn add_cors_headers(
+    api_type: ApiType,
+    cors: &Cors,
+    request_headers: &HeaderMap,
+    response: &mut Response,
+) {
+    if api_type != ApiType::Http {
+        return;
+    }
+
+    let Some(origin) = header_str(request_headers, header::ORIGIN) else {
+        return;
+    };
+    if !cors.is_origin_allowed(origin) {
+        debug!("Origin {} not allowed by the API's CORS settings", origin);
+        return;
+    }
+
+    let response_headers = response.headers_mut();
+    insert(
+        response_headers,
+        header::ACCESS_CONTROL_ALLOW_ORIGIN,
+        &allowed_origin(cors, origin),
+    );
+    if cors.allow_credentials {
+        insert(
+            response_headers,
+            header::ACCESS_CONTROL_ALLOW_CREDENTIALS,
+            "true",
+        );
+    }
+    if !cors.expose_headers.is_empty() {
+        insert(
+            response_headers,
+            header::ACCESS_CONTROL_EXPOSE_HEADERS,
+            &cors.expose_headers.join(","),
+        );
+    }
+}
+
+/// A wildcard origin can't be used alongside credentials so the request's origin is returned
+/// instead
+fn allowed_origin(cors: &Cors, origin: &str) -> String {
+    if cors.allow_origins.iter().any(|o| o == "*") && !cors.allow_credentials {
+        "*".to_string()
+    } else {
+        origin.to_string()
+    }
+}
+
+/// Returns what was requested when everything is allowed, otherwise the allowed values
+fn echo_wildcard(allowed: &[String], requested: &[&str]) -> String {
+    if allowed.iter().any(|a| a == "*") {
+        requested.join(",")
+    } else {
+        allowed.join(",")
+    }
+}
+
+fn header_str(headers: &HeaderMap, name: header::HeaderName) -> Option<&str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+fn insert(headers: &mut HeaderMap, name: header::HeaderName, value: &str) {
+    if let Ok(value) = HeaderValue::from_str(value) {
+        headers.insert(name, value);
+    }
+}
@@ -1,29 +1,218 @@
+use crate::{
+    auth::AuthorizerCache, issuer::LocalIssuer, usage_plan::UsageStore, websocket::Connections,
+};
+
+use parking_lot::RwLock;
 use reqwest::Client;
-use sam_e_types::config::{lambda::Lambda, Config};
-use std::str::FromStr;
+use sam_e_types::config::{
+    infrastructure::{
+        api::{DEFAULT_API_PORT, IMPLICIT_REST_API},
+        ApiInfrastructure, ApiKeyInfrastructure, FunctionUrlInfrastructure, Infrastructure,
+        UsagePlanInfrastructure, WebSocketApiInfrastructure,
+    },
+    lambda::{
+        event::{Event, EventApiProperties},
+        Lambda,
+    },
+    Config,
+};
+use std::{collections::HashMap, str::FromStr, sync::Arc};
 
 #[derive(Debug, Clone)]
 pub struct ApiState {
     pub lambdas: Vec<Lambda>,
+    pub apis: HashMap<String, ApiInfrastructure>,
+    pub api_keys: Vec<ApiKeyInfrastructure>,
+    pub usage_plans: Vec<UsagePlanInfrastructure>,
+    pub websocket_apis: Vec<WebSocketApiInfrastructure>,
+    pub function_urls: Vec<FunctionUrlInfrastructure>,
     pub client: Client,
+    pub authorizer_cache: Arc<RwLock<AuthorizerCache>>,
+    pub issuer: Arc<LocalIssuer>,
+    pub usage: Arc<RwLock<UsageStore>>,
+    pub connections: Arc<RwLock<Connections>>,
+    /// The port of the listener this state is used by
+    pub port: u16,
+    /// The account, region and API ID shown in request contexts and ARNs
+    pub account_id: String,
+    pub region: String,
+    pub api_id: String,
+    /// Whether the listeners serve HTTPS
+    pub use_https: bool,
 }
 
 impl ApiState {
-    pub fn from_config(config: &Config) -> Self {
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
         let client = Client::new();
-        Self {
+        let apis = config
+            .get_infrastructure()
+            .iter()
+            .filter_map(|i| match i {
+                Infrastructure::Api(api) => {
+                    Some((api.properties.name.to_string(), api.properties.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+
+        let mut api_keys = vec![];
+        let mut usage_plans = vec![];
+        let mut websocket_apis = vec![];
+        let mut function_urls = vec![];
+        for infrastructure in config.get_infrastructure() {
+            match infrastructure {
+                Infrastructure::WebSocketApi(websocket_api) => {
+                    websocket_apis.push(websocket_api.properties.clone())
+                }
+                Infrastructure::FunctionUrl(function_url) => {
+                    function_urls.push(function_url.properties.clone())
+                }
+                Infrastructure::ApiKey(api_key) => api_keys.push(api_key.properties.clone()),
+                Infrastructure::UsagePlan(usage_plan) => {
+                    usage_plans.push(usage_plan.properties.clone())
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self {
             lambdas: config.get_lambdas().to_owned(),
+            apis,
+            api_keys,
+            usage_plans,
+            websocket_apis,
+            function_urls,
             client,
+            authorizer_cache: Arc::new(RwLock::new(HashMap::new())),
+            issuer: Arc::new(LocalIssuer::new()?),
+            usage: Arc::new(RwLock::new(HashMap::new())),
+            connections: Arc::new(RwLock::new(HashMap::new())),
+            port: DEFAULT_API_PORT,
+            account_id: config.get_runtime().get_account_id().to_string(),
+            region: config.get_runtime().get_region().to_string(),
+            api_id: config.get_runtime().get_api_id().to_string(),
+            use_https: config.get_runtime().get_use_https(),
+        })
+    }
+
+    /// A copy of the state for the listener on another port. Everything other than the port is
+    /// shared between listeners.
+    pub fn with_port(&self, port: u16) -> Self {
+        Self {
+            port,
+            ..self.clone()
+        }
+    }
+
+    /// Every port an API is served on, starting with the default port
+    pub fn get_ports(&self) -> Vec<u16> {
+        let mut ports: Vec<u16> = self
+            .apis
+            .values()
+            .map(|api| api.get_port())
+            .filter(|port| *port != DEFAULT_API_PORT)
+            .collect();
+        ports.sort();
+        ports.dedup();
+        ports.insert(0, DEFAULT_API_PORT);
+        ports
+    }
+
+    /// Gets the API a route belongs to. Routes on the implicit API (i.e. without a RestApiId or
+    /// ApiId) won't have one.
+    pub fn get_api(&self, api_id: Option<&String>) -> Option<&ApiInfrastructure> {
+        api_id.and_then(|api_id| self.apis.get(api_id))
+    }
+
+    /// Gets the WebSocket API served on the port of this state
+    pub fn get_websocket_api(&self) -> Option<&WebSocketApiInfrastructure> {
+        self.websocket_apis.iter().find(|api| api.port == self.port)
+    }
+
+    /// Gets the function URL served on the port of this state
+    pub fn get_function_url(&self) -> Option<&FunctionUrlInfrastructure> {
+        self.function_urls.iter().find(|url| url.port == self.port)
+    }
+
+    pub fn get_connections(&self) -> &Arc<RwLock<Connections>> {
+        &self.connections
+    }
+
+    pub fn get_account_id(&self) -> &str {
+        &self.account_id
+    }
+
+    pub fn get_region(&self) -> &str {
+        &self.region
+    }
+
+    pub fn get_api_id(&self) -> &str {
+        &self.api_id
+    }
+
+    /// The scheme requests are sent to the listeners with
+    pub fn get_scheme(&self) -> &'static str {
+        match self.use_https {
+            true => "https",
+            false => "http",
         }
     }
 
-    pub fn get_api_lambdas(&self) -> Vec<&Lambda> {
+    pub fn get_issuer(&self) -> &LocalIssuer {
+        &self.issuer
+    }
+
+    pub fn get_usage(&self) -> &Arc<RwLock<UsageStore>> {
+        &self.usage
+    }
+
+    pub fn get_authorizer_cache(&self) -> &Arc<RwLock<AuthorizerCache>> {
+        &self.authorizer_cache
+    }
+
+    /// Gets the Lambdas with routes on the APIs served to a request, keeping only those routes.
+    /// The port the request came in on selects the APIs served there and, where more than one
+    /// API shares the port, a matching host header narrows this down to a single API. Routes on
+    /// APIs missing from the config are served on the default port.
+    pub fn get_api_lambdas(&self, host: Option<&str>) -> Vec<Lambda> {
+        let host = host.map(|host| host.split(':').next().unwrap_or(host));
+        let host_api = host.and_then(|host| {
+            self.apis
+                .values()
+                .filter(|api| api.get_port() == self.port)
+                .find(|api| api.get_host().eq_ignore_ascii_case(host))
+        });
+
+        let serves_route = |api_props: &EventApiProperties| {
+            let api_id = api_props
+                .get_api_id()
+                .map(|api_id| api_id.as_str())
+                .unwrap_or(IMPLICIT_REST_API);
+            match host_api {
+                Some(host_api) => host_api.name == api_id,
+                None => match self.apis.get(api_id) {
+                    Some(api) => api.get_port() == self.port,
+                    None => self.port == DEFAULT_API_PORT,
+                },
+            }
+        };
+
         self.lambdas
             .iter()
-            .filter(|l| {
-                l.get_events()
-                    .into_iter()
-                    .any(|e| e.get_api_properties().is_some())
+            .filter_map(|lambda| {
+                let events: Vec<Event> = lambda
+                    .get_events()
+                    .iter()
+                    .filter(|e| e.get_api_properties().is_some_and(serves_route))
+                    .cloned()
+                    .collect();
+                if events.is_empty() {
+                    return None;
+                }
+
+                let mut lambda = lambda.clone();
+                lambda.set_events(events);
+                Some(lambda)
             })
             .collect()
     }
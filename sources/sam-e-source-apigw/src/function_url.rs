@@ -0,0 +1,262 @@
+use crate::{
+    cors,
+    data::ApiState,
+    response::AppError,
+    utils::{add_forwarded_headers, get_source_ip, RequestOrigin},
+};
+use sam_e_types::{
+    config::infrastructure::{
+        api::ApiType,
+        function_url::{FunctionUrlAuthType, InvokeMode},
+    },
+    invocation::{EventRequest, InvocationBuilder},
+};
+
+use aws_lambda_events::lambda_function_urls::{
+    LambdaFunctionUrlRequest, LambdaFunctionUrlRequestContext,
+    LambdaFunctionUrlRequestContextAuthorizerDescription,
+    LambdaFunctionUrlRequestContextAuthorizerIamDescription,
+    LambdaFunctionUrlRequestContextHttpDescription,
+};
+use axum::{
+    body::{Body, Bytes},
+    extract::{ConnectInfo, Query, State},
+    http::{header, HeaderMap, Method, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::{collections::HashMap, net::SocketAddr};
+use tracing::{debug, trace, warn};
+use uuid::Uuid;
+
+/// Handles a request to a function URL, sending it to the function as a function URL event
+/// (payload format 2.0) through the invoker. Every path on the URL goes to the function.
+pub async fn handler(
+    method: Method,
+    headers: HeaderMap,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    uri: Uri,
+    Query(params): Query<Vec<(String, String)>>,
+    State(api_state): State<ApiState>,
+    body: Bytes,
+) -> Result<Response, AppError> {
+    let Some(function_url) = api_state.get_function_url() else {
+        warn!("No function URL found for port: {}", api_state.port);
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+    debug!(
+        "Request received for function URL of: {}",
+        function_url.function_name
+    );
+
+    if method == Method::OPTIONS {
+        if let Some(preflight) = function_url
+            .cors
+            .as_ref()
+            .and_then(|url_cors| cors::function_url_preflight(url_cors, &headers))
+        {
+            return Ok(preflight);
+        }
+    }
+
+    let authorizer = match function_url.auth_type {
+        FunctionUrlAuthType::None => None,
+        FunctionUrlAuthType::AwsIam => {
+            let Some(iam) = iam_identity(api_state.get_account_id(), &headers, &params) else {
+                debug!("Request to AWS_IAM function URL is not signed");
+                return Ok((
+                    StatusCode::FORBIDDEN,
+                    Json(serde_json::json!({ "Message": "Forbidden" })),
+                )
+                    .into_response());
+            };
+            Some(LambdaFunctionUrlRequestContextAuthorizerDescription { iam: Some(iam) })
+        }
+    };
+
+    let request_headers = headers.clone();
+    let url_id = function_url.function_name.to_lowercase();
+    let origin = RequestOrigin {
+        peer,
+        account_id: api_state.get_account_id(),
+        api_id: &url_id,
+        stage: "$default",
+        scheme: api_state.get_scheme(),
+        request_id: Uuid::new_v4(),
+    };
+    let function_url_request =
+        create_function_url_request(&origin, method, headers, &uri, params, body, authorizer);
+
+    let new_invocation = InvocationBuilder::new()
+        .with_request(EventRequest::FunctionUrl(function_url_request))
+        .with_lambda_name(function_url.function_name.to_string())
+        .build()?;
+
+    if function_url.invoke_mode == InvokeMode::ResponseStream {
+        debug!("Function URL streams its response, this is returned as the function sends it");
+    }
+
+    debug!("Now adding invocation to store");
+    let response = api_state
+        .get_client()
+        .post("http://0.0.0.0:3030/invoke")
+        .json(&serde_json::json!(new_invocation))
+        .send()
+        .await?;
+
+    debug!("Response from invoker");
+    trace!("Response generated: {:#?}", response);
+
+    // The function's response is passed on as it is, other than the headers for the connection to
+    // the invoker. The body is streamed so responses from RESPONSE_STREAM functions reach the
+    // caller as the function sends them.
+    let status_code =
+        StatusCode::from_u16(response.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+    let mut response_headers = HeaderMap::new();
+    for (key, value) in response.headers().iter() {
+        if [
+            header::CONTENT_LENGTH,
+            header::TRANSFER_ENCODING,
+            header::CONNECTION,
+            header::DATE,
+        ]
+        .contains(key)
+        {
+            continue;
+        }
+        response_headers.append(key.clone(), value.clone());
+    }
+    let response_body = Body::from_stream(response.bytes_stream());
+
+    let mut response = (status_code, response_headers, response_body).into_response();
+    if let Some(url_cors) = &function_url.cors {
+        cors::add_cors_headers(ApiType::Http, url_cors, &request_headers, &mut response);
+    }
+
+    Ok(response)
+}
+
+/// Creates the function URL event. Like HTTP APIs, cookies are taken out of the headers and
+/// repeated query string parameters are joined with commas.
+fn create_function_url_request(
+    origin: &RequestOrigin,
+    method: Method,
+    headers: HeaderMap,
+    uri: &Uri,
+    params: Vec<(String, String)>,
+    body: Bytes,
+    authorizer: Option<LambdaFunctionUrlRequestContextAuthorizerDescription>,
+) -> LambdaFunctionUrlRequest {
+    debug!("Creating function URL request");
+    let mut header_map = headers;
+    let source_ip = get_source_ip(&header_map, &origin.peer);
+    add_forwarded_headers(&mut header_map, &origin.peer, origin.scheme);
+    let cookies = header_map.remove(header::COOKIE).map(|cookie| {
+        cookie
+            .to_str()
+            .unwrap_or_default()
+            .split(';')
+            .map(|c| c.trim().to_string())
+            .filter(|c| !c.is_empty())
+            .collect::<Vec<_>>()
+    });
+
+    let mut query_string_parameters: HashMap<String, String> = HashMap::new();
+    for (key, value) in params {
+        query_string_parameters
+            .entry(key)
+            .and_modify(|existing| {
+                existing.push(',');
+                existing.push_str(&value);
+            })
+            .or_insert(value);
+    }
+
+    let (body, is_base64_encoded) = if body.is_empty() {
+        (None, false)
+    } else {
+        match String::from_utf8(body.to_vec()) {
+            Ok(text) => (Some(text), false),
+            Err(_) => (Some(STANDARD.encode(&body)), true),
+        }
+    };
+
+    let domain_name = header_map
+        .get(header::HOST)
+        .and_then(|h| h.to_str().ok())
+        .unwrap_or("localhost")
+        .to_string();
+    let user_agent = header_map
+        .get(header::USER_AGENT)
+        .and_then(|h| h.to_str().ok())
+        .map(|h| h.to_string());
+    let now = chrono::Utc::now();
+
+    let function_url_request = LambdaFunctionUrlRequest {
+        version: Some("2.0".to_string()),
+        raw_path: Some(uri.path().to_string()),
+        raw_query_string: Some(uri.query().unwrap_or_default().to_string()),
+        cookies,
+        headers: header_map,
+        query_string_parameters,
+        request_context: LambdaFunctionUrlRequestContext {
+            account_id: Some(origin.account_id.to_string()),
+            request_id: Some(origin.request_id.to_string()),
+            authorizer,
+            apiid: Some(origin.api_id.to_string()),
+            domain_name: Some(domain_name),
+            domain_prefix: Some(origin.api_id.to_string()),
+            time: Some(now.format("%d/%b/%Y:%H:%M:%S %z").to_string()),
+            time_epoch: now.timestamp_millis(),
+            http: LambdaFunctionUrlRequestContextHttpDescription {
+                method: Some(method.to_string()),
+                path: Some(uri.path().to_string()),
+                protocol: Some("HTTP/1.1".to_string()),
+                source_ip: Some(source_ip),
+                user_agent,
+            },
+        },
+        body,
+        is_base64_encoded,
+    };
+
+    trace!("Function URL request: {:#?}", function_url_request);
+
+    function_url_request
+}
+
+/// Function URLs using AWS_IAM auth need requests signed with SigV4, either in the authorization
+/// header or the query string. The signature isn't checked locally, only that there is one, with
+/// the caller taken from the access key it was signed with.
+fn iam_identity(
+    account_id: &str,
+    headers: &HeaderMap,
+    params: &[(String, String)],
+) -> Option<LambdaFunctionUrlRequestContextAuthorizerIamDescription> {
+    let credential = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("AWS4-HMAC-SHA256 "))
+        .and_then(|h| {
+            h.split(',')
+                .find_map(|part| part.trim().strip_prefix("Credential="))
+        })
+        .or_else(|| {
+            params
+                .iter()
+                .find(|(key, _)| key == "X-Amz-Credential")
+                .map(|(_, value)| value.as_str())
+        })?;
+
+    let access_key = credential.split('/').next().unwrap_or(credential);
+    debug!("Request signed with access key: {}", access_key);
+
+    Some(LambdaFunctionUrlRequestContextAuthorizerIamDescription {
+        access_key: Some(access_key.to_string()),
+        account_id: Some(account_id.to_string()),
+        caller_id: Some(access_key.to_string()),
+        user_arn: Some(format!("arn:aws:iam::{}:user/{}", account_id, access_key)),
+        user_id: Some(access_key.to_string()),
+    })
+}
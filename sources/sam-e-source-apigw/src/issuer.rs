@@ -0,0 +1,222 @@
+use crate::{data::ApiState, response::AppError};
+use sam_e_types::config::infrastructure::api::{Authorizer, JwtAuthorizer};
+
+use anyhow::{anyhow, Result};
+use axum::{extract::State, response::IntoResponse, Json};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use ring::{
+    rand::SystemRandom,
+    signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING},
+};
+use serde::Deserialize;
+use std::{collections::HashMap, fmt};
+use tracing::{debug, info, trace};
+use uuid::Uuid;
+
+/// The issuer used for tokens where the authorizer doesn't specify one
+pub const LOCAL_ISSUER_URL: &str = "http://localhost:3003";
+/// The issuer is always served over plain HTTP, so this port also serves the @connections API
+pub const LOCAL_ISSUER_PORT: u16 = 3003;
+
+/// A local stand in for a token issuer (i.e. a Cognito user pool). A new signing key is generated
+/// each time the environment starts so tokens need minting again after a restart.
+pub struct LocalIssuer {
+    key_id: String,
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    jwks: serde_json::Value,
+}
+
+impl fmt::Debug for LocalIssuer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalIssuer")
+            .field("key_id", &self.key_id)
+            .finish()
+    }
+}
+
+impl LocalIssuer {
+    pub fn new() -> Result<Self> {
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng)
+            .map_err(|_| anyhow!("Failed to generate signing key"))?;
+        let key_pair =
+            EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref(), &rng)
+                .map_err(|e| anyhow!("Failed to load signing key: {}", e))?;
+
+        // The public key is uncompressed (0x04 followed by the x and y coordinates)
+        let public_key = key_pair.public_key().as_ref();
+        let x = URL_SAFE_NO_PAD.encode(&public_key[1..33]);
+        let y = URL_SAFE_NO_PAD.encode(&public_key[33..65]);
+
+        let key_id = Uuid::new_v4().to_string();
+        let jwks = serde_json::json!({
+            "keys": [{
+                "kty": "EC",
+                "crv": "P-256",
+                "alg": "ES256",
+                "use": "sig",
+                "kid": key_id,
+                "x": x,
+                "y": y,
+            }]
+        });
+
+        Ok(Self {
+            key_id,
+            encoding_key: EncodingKey::from_ec_der(pkcs8.as_ref()),
+            decoding_key: DecodingKey::from_ec_components(&x, &y)?,
+            jwks,
+        })
+    }
+
+    pub fn get_jwks(&self) -> &serde_json::Value {
+        &self.jwks
+    }
+
+    pub fn mint(&self, claims: &serde_json::Value) -> Result<String> {
+        let mut header = Header::new(Algorithm::ES256);
+        header.kid = Some(self.key_id.to_string());
+
+        Ok(jsonwebtoken::encode(&header, claims, &self.encoding_key)?)
+    }
+
+    /// Validates the token's signature and expiry, along with the issuer and audience where the
+    /// authorizer specifies them. Returns the claims of a valid token.
+    pub fn verify(
+        &self,
+        token: &str,
+        authorizer: &JwtAuthorizer,
+    ) -> Result<HashMap<String, serde_json::Value>> {
+        let header = jsonwebtoken::decode_header(token)?;
+        if header.kid.as_deref() != Some(self.key_id.as_str()) {
+            return Err(anyhow!("Token was not signed by the local issuer"));
+        }
+
+        let mut validation = Validation::new(Algorithm::ES256);
+        // Access tokens (i.e. from Cognito) use client_id rather than aud so this is checked below
+        validation.validate_aud = false;
+        if let Some(issuer) = &authorizer.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+
+        let claims = jsonwebtoken::decode::<HashMap<String, serde_json::Value>>(
+            token,
+            &self.decoding_key,
+            &validation,
+        )?
+        .claims;
+
+        if !authorizer.audience.is_empty() {
+            let mut token_audiences = vec![];
+            match claims.get("aud") {
+                Some(serde_json::Value::String(aud)) => token_audiences.push(aud.to_string()),
+                Some(serde_json::Value::Array(auds)) => token_audiences.extend(
+                    auds.iter()
+                        .filter_map(|aud| aud.as_str())
+                        .map(|aud| aud.to_string()),
+                ),
+                _ => {}
+            }
+            if let Some(client_id) = claims.get("client_id").and_then(|c| c.as_str()) {
+                token_audiences.push(client_id.to_string());
+            }
+
+            if !token_audiences
+                .iter()
+                .any(|aud| authorizer.audience.contains(aud))
+            {
+                return Err(anyhow!("Token audience does not match the authorizer"));
+            }
+        }
+
+        Ok(claims)
+    }
+}
+
+/// Request to mint a new token. Everything is optional - by default a token is minted that
+/// satisfies the first JWT or Cognito authorizer found.
+#[derive(Debug, Deserialize)]
+pub struct TokenRequest {
+    /// The name of the authorizer the token is for. Used to fill in the issuer and audience.
+    authorizer: Option<String>,
+    #[serde(default)]
+    claims: serde_json::Map<String, serde_json::Value>,
+    #[serde(default)]
+    scopes: Vec<String>,
+    expires_in: Option<i64>,
+}
+
+pub async fn jwks_handler(State(api_state): State<ApiState>) -> impl IntoResponse {
+    debug!("JWKS requested from local issuer");
+    Json(api_state.get_issuer().get_jwks().clone())
+}
+
+pub async fn token_handler(
+    State(api_state): State<ApiState>,
+    Json(token_request): Json<TokenRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Minting a new token from local issuer");
+    trace!("Token request: {:?}", token_request);
+
+    let authorizer = find_jwt_authorizer(&api_state, token_request.authorizer.as_deref())?;
+    let expires_in = token_request.expires_in.unwrap_or(3600);
+    let now = chrono::Utc::now().timestamp();
+
+    let mut claims = serde_json::Map::new();
+    claims.insert("sub".to_string(), serde_json::json!("local-user"));
+    claims.insert(
+        "iss".to_string(),
+        serde_json::json!(authorizer
+            .and_then(|a| a.issuer.clone())
+            .unwrap_or_else(|| LOCAL_ISSUER_URL.to_string())),
+    );
+    if let Some(audience) = authorizer.and_then(|a| a.audience.first()) {
+        claims.insert("aud".to_string(), serde_json::json!(audience));
+    }
+    if !token_request.scopes.is_empty() {
+        claims.insert(
+            "scope".to_string(),
+            serde_json::json!(token_request.scopes.join(" ")),
+        );
+    }
+    claims.insert("iat".to_string(), serde_json::json!(now));
+    claims.insert("exp".to_string(), serde_json::json!(now + expires_in));
+    claims.insert("jti".to_string(), serde_json::json!(Uuid::new_v4()));
+
+    // Anything requested explicitly takes priority over the defaults
+    claims.extend(token_request.claims);
+
+    let token = api_state
+        .get_issuer()
+        .mint(&serde_json::Value::Object(claims))?;
+
+    Ok(Json(serde_json::json!({
+        "access_token": token,
+        "token_type": "Bearer",
+        "expires_in": expires_in,
+    })))
+}
+
+fn find_jwt_authorizer<'a>(
+    api_state: &'a ApiState,
+    authorizer_name: Option<&str>,
+) -> Result<Option<&'a JwtAuthorizer>> {
+    let mut jwt_authorizers = api_state.apis.values().flat_map(|api| {
+        api.authorizers
+            .iter()
+            .filter_map(|(name, authorizer)| match authorizer {
+                Authorizer::Jwt(jwt) | Authorizer::Cognito(jwt) => Some((name, jwt)),
+                _ => None,
+            })
+    });
+
+    match authorizer_name {
+        Some(authorizer_name) => jwt_authorizers
+            .find(|(name, _)| name.as_str() == authorizer_name)
+            .map(|(_, jwt)| Some(jwt))
+            .ok_or_else(|| anyhow!("No JWT authorizer found named {}", authorizer_name)),
+        None => Ok(jwt_authorizers.next().map(|(_, jwt)| jwt)),
+    }
+}
@@ -1,16 +1,32 @@
+mod auth;
+mod cors;
 mod data;
+mod function_url;
+mod issuer;
 mod middleware;
 mod request;
 mod response;
+mod usage_plan;
 pub mod utils;
+mod validation;
+mod websocket;
 
-use axum::{routing::get, Router};
+use axum::{
+    routing::{any, get, post},
+    Router,
+};
+use axum_server::tls_rustls::RustlsConfig;
 
-use tracing::{debug, info};
+use std::net::SocketAddr;
+use tracing::{debug, error, info};
 use tracing_subscriber::EnvFilter;
 
 use sam_e_types::config::Config;
 
+/// Where the certificate and key are mounted in the container when HTTPS is enabled
+const CERTIFICATE_LOCATION: &str = "/certs/cert.pem";
+const CERTIFICATE_KEY_LOCATION: &str = "/certs/key.pem";
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     tracing_subscriber::fmt()
@@ -33,33 +49,140 @@ async fn main() -> anyhow::Result<()> {
         return Ok(());
     }
 
+    let tls_config = if config.get_runtime().get_use_https() {
+        debug!("Loading the certificate to serve HTTPS");
+        rustls::crypto::ring::default_provider()
+            .install_default()
+            .map_err(|_| anyhow::anyhow!("Failed to set up TLS"))?;
+        let tls_config =
+            RustlsConfig::from_pem_file(CERTIFICATE_LOCATION, CERTIFICATE_KEY_LOCATION)
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to load the certificate for HTTPS: {}", e))?;
+        Some(tls_config)
+    } else {
+        None
+    };
+
     debug!("Creating the API state");
-    let api_state = data::ApiState::from_config(&config);
+    let api_state = data::ApiState::from_config(&config)
+        .map_err(|e| anyhow::anyhow!("Failed to create the local token issuer: {}", e))?;
+
+    // The issuer's listener stays on plain HTTP, so it also serves the @connections API for
+    // functions when the WebSocket APIs use HTTPS
+    debug!("Setting up the local token issuer");
+    let issuer_app = Router::new()
+        .route("/.well-known/jwks.json", get(issuer::jwks_handler))
+        .route("/token", post(issuer::token_handler))
+        .merge(management_router())
+        .layer(middleware::cors_layer())
+        .with_state(api_state.clone());
+
+    spawn_serve("Local token issuer", issuer::LOCAL_ISSUER_PORT, issuer_app, None);
 
+    // Each port gets its own listener, with APIs sharing a port told apart by the host header.
+    // CORS is handled per API by the request handler (using the settings from the template) so
+    // there is no CORS layer here.
     debug!("Setting up the API routes");
-    let app = Router::new()
+    let ports = api_state.get_ports();
+    for port in ports.iter().skip(1) {
+        let app = api_router(api_state.with_port(*port));
+        spawn_serve("API", *port, app, tls_config.clone());
+    }
+
+    for websocket_api in api_state.websocket_apis.iter() {
+        debug!("Setting up the WebSocket API: {}", websocket_api.name);
+        let app = websocket_router(api_state.with_port(websocket_api.port));
+        spawn_serve("WebSocket API", websocket_api.port, app, tls_config.clone());
+    }
+
+    for function_url in api_state.function_urls.iter() {
+        debug!("Setting up the function URL for: {}", function_url.function_name);
+        let app = function_url_router(api_state.with_port(function_url.port));
+        spawn_serve("Function URL", function_url.port, app, tls_config.clone());
+    }
+
+    let app = api_router(api_state);
+    serve("API", 3000, app, tls_config).await
+}
+
+/// Serves the app in the background, logging why if its listener fails
+fn spawn_serve(name: &'static str, port: u16, app: Router, tls_config: Option<RustlsConfig>) {
+    tokio::spawn(async move {
+        if let Err(e) = serve(name, port, app, tls_config).await {
+            error!("{} on port {} failed: {}", name, port, e);
+        }
+    });
+}
+
+/// Serves the app on the given port, over HTTPS when a certificate has been loaded. HTTPS
+/// connections can negotiate HTTP/2 as well as HTTP/1.1.
+async fn serve(
+    name: &str,
+    port: u16,
+    app: Router,
+    tls_config: Option<RustlsConfig>,
+) -> anyhow::Result<()> {
+    let make_service = app.into_make_service_with_connect_info::<SocketAddr>();
+    match tls_config {
+        Some(tls_config) => {
+            let addr = SocketAddr::from(([0, 0, 0, 0], port));
+            info!("{} listening on: {} (HTTPS)", name, addr);
+            axum_server::bind_rustls(addr, tls_config)
+                .serve(make_service)
+                .await?;
+        }
+        None => {
+            let listener = tokio::net::TcpListener::bind(("0.0.0.0", port))
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to listen on port {}: {}", port, e))?;
+            info!("{} listening on: {}", name, listener.local_addr()?);
+            axum::serve(listener, make_service).await?;
+        }
+    }
+
+    Ok(())
+}
+
+fn api_router(api_state: data::ApiState) -> Router {
+    Router::new()
+        .route("/", any(request::handler))
+        .route("/*path", any(request::handler))
+        .layer(axum::middleware::from_fn(middleware::host_from_authority))
+        .with_state(api_state)
+}
+
+/// Connections can be opened on any path (i.e. the stage), alongside the @connections API used by
+/// functions to send messages to clients
+fn websocket_router(api_state: data::ApiState) -> Router {
+    Router::new()
+        .merge(management_router())
+        .fallback(websocket::handler)
+        .layer(axum::middleware::from_fn(middleware::host_from_authority))
+        .with_state(api_state)
+}
+
+/// The @connections API, with or without the stage in the path
+fn management_router() -> Router<data::ApiState> {
+    Router::new()
         .route(
-            "/",
-            get(request::handler)
-                .post(request::handler)
-                .patch(request::handler)
-                .put(request::handler)
-                .delete(request::handler),
+            "/@connections/:connection_id",
+            post(websocket::post_to_connection)
+                .get(websocket::get_connection)
+                .delete(websocket::delete_connection),
         )
         .route(
-            "/*path",
-            get(request::handler)
-                .post(request::handler)
-                .patch(request::handler)
-                .put(request::handler)
-                .delete(request::handler),
+            "/:stage/@connections/:connection_id",
+            post(websocket::post_to_connection)
+                .get(websocket::get_connection)
+                .delete(websocket::delete_connection),
         )
-        .layer(middleware::cors_layer())
-        .with_state(api_state);
-
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
-    info!("Listening on: {}", listener.local_addr().unwrap());
-    axum::serve(listener, app).await.unwrap();
+}
 
-    Ok(())
+/// Every path and method on a function URL goes to its function
+fn function_url_router(api_state: data::ApiState) -> Router {
+    Router::new()
+        .route("/", any(function_url::handler))
+        .route("/*path", any(function_url::handler))
+        .layer(axum::middleware::from_fn(middleware::host_from_authority))
+        .with_state(api_state)
 }
@@ -1,9 +1,32 @@
-use axum::http::{header, Method};
+use axum::{
+    extract::Request,
+    http::{header, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
 use tower_http::cors::{Any, CorsLayer};
 
+/// Allows any request through, regardless of origin, method or headers. Only used for the local
+/// endpoints that don't exist in AWS - API routes use the CORS settings of their API instead.
 pub fn cors_layer() -> CorsLayer {
     CorsLayer::new()
         .allow_origin(Any)
-        .allow_methods([Method::GET, Method::POST, Method::PATCH, Method::DELETE])
-        .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE])
+        .allow_methods(Any)
+        .allow_headers(Any)
+}
+
+/// HTTP/2 requests carry the host in the URI's authority rather than a host header. This adds the
+/// header back so APIs can still be picked by host and the request contexts have a domain name.
+pub async fn host_from_authority(mut request: Request, next: Next) -> Response {
+    if !request.headers().contains_key(header::HOST) {
+        if let Some(host) = request
+            .uri()
+            .authority()
+            .and_then(|authority| HeaderValue::from_str(authority.as_str()).ok())
+        {
+            request.headers_mut().insert(header::HOST, host);
+        }
+    }
+
+    next.run(request).await
 }
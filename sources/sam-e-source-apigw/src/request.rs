@@ -1,29 +1,38 @@
 use crate::{
+    auth, cors,
     data::{ApiState, ContentType},
     response::AppError,
-    utils::{create_api_request, find_lambda_with_base_path},
+    usage_plan,
+    utils::{create_api_request, find_lambda_with_base_path, RequestOrigin},
+    validation,
+};
+use sam_e_types::{
+    config::lambda::{event::EventApiProperties, Lambda},
+    invocation::{EventRequest, InvocationBuilder},
 };
-use sam_e_types::invocation::{EventRequest, InvocationBuilder};
 
 use axum::{
-    extract::{Json, Path, Query, State},
-    http::{HeaderMap, Method},
-    response::{Html, IntoResponse},
+    body::Bytes,
+    extract::{ConnectInfo, Json, Path, Query, State},
+    http::{header, HeaderMap, Method},
+    response::{Html, IntoResponse, Response},
 };
-use std::{collections::HashMap, str::FromStr};
+use std::{net::SocketAddr, str::FromStr};
 use tracing::{debug, trace, warn};
 use uuid::Uuid;
 
 pub async fn handler(
     method: Method,
     headers: HeaderMap,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
     path: Option<Path<String>>,
-    Query(params): Query<HashMap<String, String>>,
+    Query(params): Query<Vec<(String, String)>>,
     State(api_state): State<ApiState>,
-    body: Option<Json<serde_json::Value>>,
-) -> Result<impl IntoResponse, AppError> {
+    body: Bytes,
+) -> Result<Response, AppError> {
     debug!("Request received: {:#?}", method);
-    let api_lambdas = api_state.get_api_lambdas();
+    let host = headers.get(header::HOST).and_then(|h| h.to_str().ok());
+    let api_lambdas = api_state.get_api_lambdas(host);
     if api_lambdas.is_empty() {
         warn!("No API lambdas detected from SAM template.");
         return Ok("No API lambdas detected from SAM template.".into_response());
@@ -38,23 +47,101 @@ pub async fn handler(
         "/".to_string()
     };
 
-    let (matched_lambda, matched_event) =
-        find_lambda_with_base_path(api_lambdas, &prepended_path, &method.to_string())?;
+    if method == Method::OPTIONS {
+        if let Some(preflight) = cors::preflight(&api_state, &headers, &prepended_path) {
+            return Ok(preflight);
+        }
+    }
+
+    let (matched_lambda, matched_event) = find_lambda_with_base_path(
+        api_lambdas.iter().collect(),
+        &prepended_path,
+        &method.to_string(),
+    )?;
     let matched_api_props = matched_event.get_api_properties().unwrap().to_owned();
     trace!("Event lambda found: {:?}", &matched_lambda);
 
-    debug!("Creating invocation using matched lambda and request data");
-    let request_id = Uuid::new_v4();
-    let api_data = create_api_request(
-        body,
+    let request_headers = headers.clone();
+    let route_request = RouteRequest {
+        method,
         headers,
+        peer,
         params,
-        method,
-        &prepended_path,
+        body,
+        path: prepended_path,
+    };
+    let mut response =
+        handle_route(&api_state, matched_lambda, &matched_api_props, route_request).await?;
+
+    if let Some(api) = api_state.get_api(matched_api_props.get_api_id()) {
+        if let Some(api_cors) = &api.cors {
+            cors::add_cors_headers(api.api_type, api_cors, &request_headers, &mut response);
+        }
+    }
+
+    Ok(response)
+}
+
+/// The parts of the request passed on to the matched route
+struct RouteRequest {
+    method: Method,
+    headers: HeaderMap,
+    peer: SocketAddr,
+    params: Vec<(String, String)>,
+    body: Bytes,
+    path: String,
+}
+
+/// Authorizes the request against the matched route before invoking its Lambda through the
+/// invoker and returning the Lambda's response
+async fn handle_route(
+    api_state: &ApiState,
+    matched_lambda: Lambda,
+    matched_api_props: &EventApiProperties,
+    route_request: RouteRequest,
+) -> Result<Response, AppError> {
+    debug!("Creating invocation using matched lambda and request data");
+    let request_id = Uuid::new_v4();
+    let stage = api_state
+        .get_api(matched_api_props.get_api_id())
+        .map(|api| api.get_stage_name())
+        .unwrap_or("Prod");
+    let origin = RequestOrigin {
+        peer: route_request.peer,
+        account_id: api_state.get_account_id(),
+        api_id: api_state.get_api_id(),
+        stage,
+        scheme: api_state.get_scheme(),
+        request_id,
+    };
+    let mut api_data = create_api_request(
+        route_request.body,
+        route_request.headers,
+        route_request.params,
+        route_request.method,
+        &route_request.path,
         &matched_api_props.get_base_path(),
-        &request_id,
+        &origin,
     );
 
+    if let Some(rejection) = auth::authorize(api_state, matched_api_props, &mut api_data).await? {
+        debug!("Request rejected by authorizer");
+        return Ok(rejection);
+    }
+
+    if let Some(rejection) =
+        usage_plan::check_api_key(api_state, matched_api_props, &mut api_data)
+    {
+        debug!("Request rejected by API key or usage plan");
+        return Ok(rejection);
+    }
+
+    if let Some(rejection) = validation::validate_request(api_state, matched_api_props, &api_data)
+    {
+        debug!("Request rejected by request validation");
+        return Ok(rejection);
+    }
+
     let new_invocation = InvocationBuilder::new()
         .with_request(EventRequest::Api(api_data))
         .with_request_id(request_id)
@@ -87,6 +174,24 @@ pub async fn handler(
         return Ok("Failed to parse content type string into enum".into_response());
     };
 
+    // Headers set by the Lambda (i.e. Access-Control-Allow-Origin) are passed on to the caller.
+    // The content type is set below from the type of response.
+    let mut lambda_headers = HeaderMap::new();
+    for (key, value) in response.headers().iter() {
+        if [
+            header::CONTENT_TYPE,
+            header::CONTENT_LENGTH,
+            header::TRANSFER_ENCODING,
+            header::CONNECTION,
+            header::DATE,
+        ]
+        .contains(key)
+        {
+            continue;
+        }
+        lambda_headers.append(key.clone(), value.clone());
+    }
+
     let status_code_reqwest = response.status().as_u16();
     let status_code = axum::http::StatusCode::from_u16(status_code_reqwest).unwrap_or(axum::http::StatusCode::INTERNAL_SERVER_ERROR);
     match response_type {
@@ -95,21 +200,21 @@ pub async fn handler(
             let response_data = response.json::<serde_json::Value>().await?;
             debug!("Response data parsed successfully. Now returning...");
             trace!("Response data: {:#?}", response_data);
-            Ok((status_code, Json(response_data)).into_response())
+            Ok((status_code, lambda_headers, Json(response_data)).into_response())
         }
         ContentType::Text => {
             debug!("Parsing response data as text");
             let response_data = response.text().await?;
             debug!("Response data parsed successfully. Now returning...");
             trace!("Response data: {:#?}", response_data);
-            Ok((status_code, response_data).into_response())
+            Ok((status_code, lambda_headers, response_data).into_response())
         }
         ContentType::Html => {
             debug!("Parsing response data as HTML");
             let response_data = response.text().await?;
             debug!("Response data parsed successfully. Now returning...");
             trace!("Response data: {:#?}", response_data);
-            Ok((status_code, Html(response_data)).into_response())
+            Ok((status_code, lambda_headers, Html(response_data)).into_response())
         }
     }
 }
@@ -0,0 +1,170 @@
+use crate::data::ApiState;
+use sam_e_types::config::{
+    infrastructure::{
+        api::IMPLICIT_REST_API,
+        usage_plan::{QuotaPeriod, UsagePlanInfrastructure},
+    },
+    lambda::event::EventApiProperties,
+};
+
+use aws_lambda_events::apigw::ApiGatewayProxyRequest;
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+use tracing::{debug, info, trace};
+
+/// Usage of each API key within each usage plan, keyed by plan and key name
+pub type UsageStore = HashMap<String, KeyUsage>;
+
+/// Tracks requests made with an API key. Throttling uses a token bucket (refilled at the rate
+/// limit up to the burst limit) and the quota is counted over a fixed window.
+#[derive(Debug, Clone)]
+pub struct KeyUsage {
+    tokens: f64,
+    last_refill: Instant,
+    quota_used: u64,
+    quota_window_start: Instant,
+}
+
+/// Checks the API key on requests to routes that require one, then applies the throttle and
+/// quota of the key's usage plan. Returns the response to send back if the request is rejected,
+/// otherwise adds the key to the request identity and returns `None`.
+pub fn check_api_key(
+    api_state: &ApiState,
+    api_props: &EventApiProperties,
+    api_request: &mut ApiGatewayProxyRequest,
+) -> Option<Response> {
+    let api = api_state.get_api(api_props.get_api_id());
+    let api_key_required = api_props
+        .get_api_key_required()
+        .unwrap_or_else(|| api.map(|a| a.api_key_required).unwrap_or(false));
+
+    if !api_key_required {
+        trace!("API key not required for route");
+        return None;
+    }
+
+    let Some(key_value) = api_request
+        .headers
+        .get("x-api-key")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string())
+    else {
+        info!("API key required but not found in request. Returning forbidden");
+        return Some(forbidden());
+    };
+
+    let Some(api_key) = api_state
+        .api_keys
+        .iter()
+        .find(|k| k.enabled && k.value == key_value)
+    else {
+        info!("API key not recognised or disabled. Returning forbidden");
+        return Some(forbidden());
+    };
+
+    let api_name = api_props
+        .get_api_id()
+        .map(|a| a.as_str())
+        .unwrap_or(IMPLICIT_REST_API);
+
+    // API keys are only valid for APIs they are linked to through a usage plan
+    let Some(usage_plan) = api_state
+        .usage_plans
+        .iter()
+        .find(|p| p.applies_to(api_name, &api_key.name))
+    else {
+        info!(
+            "API key {} is not in a usage plan for API {}. Returning forbidden",
+            api_key.name, api_name
+        );
+        return Some(forbidden());
+    };
+
+    if let Some(rejection) = record_usage(api_state, usage_plan, &api_key.name) {
+        return Some(rejection);
+    }
+
+    debug!("API key {} accepted", api_key.name);
+    let identity = &mut api_request.request_context.identity;
+    identity.api_key = Some(key_value);
+    identity.api_key_id = Some(api_key.name.to_string());
+
+    None
+}
+
+fn record_usage(
+    api_state: &ApiState,
+    usage_plan: &UsagePlanInfrastructure,
+    api_key_name: &str,
+) -> Option<Response> {
+    let now = Instant::now();
+    let mut usage_store = api_state.get_usage().write();
+    let usage = usage_store
+        .entry(format!("{}:{}", usage_plan.name, api_key_name))
+        .or_insert_with(|| KeyUsage {
+            tokens: usage_plan
+                .throttle
+                .as_ref()
+                .map(|t| t.burst_limit as f64)
+                .unwrap_or_default(),
+            last_refill: now,
+            quota_used: 0,
+            quota_window_start: now,
+        });
+
+    if let Some(throttle) = &usage_plan.throttle {
+        let elapsed = now.duration_since(usage.last_refill).as_secs_f64();
+        usage.tokens =
+            (usage.tokens + elapsed * throttle.rate_limit).min(throttle.burst_limit as f64);
+        usage.last_refill = now;
+
+        if usage.tokens < 1.0 {
+            info!("Usage plan {} throttled the request", usage_plan.name);
+            return Some(too_many_requests("Too Many Requests"));
+        }
+        usage.tokens -= 1.0;
+    }
+
+    if let Some(quota) = &usage_plan.quota {
+        let window = match quota.period {
+            QuotaPeriod::Day => Duration::from_secs(60 * 60 * 24),
+            QuotaPeriod::Week => Duration::from_secs(60 * 60 * 24 * 7),
+            QuotaPeriod::Month => Duration::from_secs(60 * 60 * 24 * 30),
+        };
+        if now.duration_since(usage.quota_window_start) >= window {
+            usage.quota_used = 0;
+            usage.quota_window_start = now;
+        }
+
+        if usage.quota_used >= quota.limit {
+            info!("Usage plan {} quota exceeded", usage_plan.name);
+            return Some(too_many_requests("Limit Exceeded"));
+        }
+        usage.quota_used += 1;
+    }
+
+    None
+}
+
+fn forbidden() -> Response {
+    (
+        StatusCode::FORBIDDEN,
+        Json(serde_json::json!({ "message": "Forbidden" })),
+    )
+        .into_response()
+}
+
+fn too_many_requests(message: &str) -> Response {
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        Json(serde_json::json!({ "message": message })),
+    )
+        .into_response()
+}
@@ -1,18 +1,30 @@
 use anyhow::{anyhow, Result};
-use aws_lambda_events::apigw::{
-    ApiGatewayProxyRequest, ApiGatewayProxyRequestContext, ApiGatewayRequestAuthorizer, ApiGatewayRequestIdentity
+use aws_lambda_events::{
+    apigw::{
+        ApiGatewayProxyRequest, ApiGatewayProxyRequestContext, ApiGatewayRequestAuthorizer,
+        ApiGatewayRequestIdentity,
+    },
+    query_map::QueryMap,
 };
 use axum::{
-    extract::Json,
-    http::{HeaderMap, HeaderName, HeaderValue, Method},
+    body::Bytes,
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method},
 };
-use sam_e_types::config::lambda::{event::Event, event::EventProperties, Lambda};
-use std::collections::HashMap;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use sam_e_types::config::lambda::{
+    event::{Event, EventApiProperties, EventProperties},
+    Lambda,
+};
+use std::{collections::HashMap, net::SocketAddr};
 use tracing::{debug, trace, warn};
 use uuid::Uuid;
 
+const X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
+
 /// Finds the relevant Lambda that matches the base path and method been used in the invocation.
 /// This will then be passed to the invoker ready to be processed by the Lambda Runtime API.
+/// Where more than one route matches, the most specific is chosen using API Gateway's precedence
+/// rules so the order of lambdas in the config doesn't matter.
 pub fn find_lambda_with_base_path(
     lambdas: Vec<&Lambda>,
     base_path: &str,
@@ -22,6 +34,8 @@ pub fn find_lambda_with_base_path(
         "Checking lambdas for match to api request: {} {}",
         base_path, method
     );
+    let mut matches: Vec<(&Lambda, &Event, &EventApiProperties)> = vec![];
+
     for lambda in lambdas {
         for event in lambda.get_events() {
             let Some(event_props) = event.get_properties() else {
@@ -42,8 +56,13 @@ pub fn find_lambda_with_base_path(
                         .contains(&api_props.get_method().to_uppercase().as_str());
 
                     if route_filter && method_filter {
-                        debug!("Match found for lambda: {}", lambda.get_name());
-                        return Ok((lambda.to_owned().clone(), event.to_owned()));
+                        debug!(
+                            "Route {} {} matches for lambda: {}",
+                            api_props.get_method(),
+                            api_props.get_full_path(),
+                            lambda.get_name()
+                        );
+                        matches.push((lambda, event, api_props));
                     } else {
                         trace!("No match found for lambda: {}", lambda.get_name());
                     }
@@ -53,7 +72,20 @@ pub fn find_lambda_with_base_path(
         }
     }
 
-    Err(anyhow!("No matching lambda found"))
+    let Some((lambda, event, api_props)) = matches
+        .into_iter()
+        .max_by_key(|(_, _, api_props)| api_props.get_route_precedence())
+    else {
+        return Err(anyhow!("No matching lambda found"));
+    };
+
+    debug!(
+        "Match found for lambda: {} using route: {} {}",
+        lambda.get_name(),
+        api_props.get_method(),
+        api_props.get_full_path()
+    );
+    Ok((lambda.to_owned(), event.to_owned()))
 }
 
 fn remove_base_path(path: &str, base_path: &Option<&String>) -> String {
@@ -69,40 +101,73 @@ fn remove_base_path(path: &str, base_path: &Option<&String>) -> String {
     }
 }
 
+/// Where a request came from and the API it was sent to, as shown in the request context
+pub struct RequestOrigin<'a> {
+    pub peer: SocketAddr,
+    pub account_id: &'a str,
+    pub api_id: &'a str,
+    pub stage: &'a str,
+    /// The scheme of the listener the request came in on
+    pub scheme: &'a str,
+    pub request_id: Uuid,
+}
+
 pub fn create_api_request(
-    body: Option<Json<serde_json::Value>>,
+    body: Bytes,
     headers: HeaderMap,
-    params: HashMap<String, String>,
+    params: Vec<(String, String)>,
     method: Method,
     path: &str,
     base_path: &Option<&String>,
-    request_id: &Uuid,
+    origin: &RequestOrigin,
 ) -> ApiGatewayProxyRequest {
     debug!("Creating API Gateway request");
     let resource_path = remove_base_path(path, base_path);
     debug!("Resource path: {:?}", resource_path);
 
     let mut header_map = headers;
-    header_map.insert(
-        HeaderName::from_static("x-forwarded-proto"),
-        HeaderValue::from_static("http"),
-    );
+    let source_ip = get_source_ip(&header_map, &origin.peer);
+    add_forwarded_headers(&mut header_map, &origin.peer, origin.scheme);
 
-    let request_context = create_api_request_context(path, request_id, &method, &header_map);
+    // Repeated parameters are kept in the multi-value parameters, with the single value
+    // parameters taking the last one
+    let mut query_params: HashMap<String, Vec<String>> = HashMap::new();
+    for (key, value) in params {
+        query_params.entry(key).or_default().push(value);
+    }
+    let query_map = QueryMap::from(query_params);
+
+    // The body is passed on as it was sent, with anything that isn't text base64 encoded
+    let (body, is_base64_encoded) = if body.is_empty() {
+        (None, false)
+    } else {
+        match String::from_utf8(body.to_vec()) {
+            Ok(text) => (Some(text), false),
+            Err(_) => (Some(STANDARD.encode(&body)), true),
+        }
+    };
+
+    let request_context = create_api_request_context(
+        path,
+        origin,
+        &source_ip,
+        &method,
+        &header_map,
+    );
     let api_request = ApiGatewayProxyRequest {
-        body: body.map(|b| b.0.to_string()),
+        body,
+        multi_value_headers: header_map.clone(),
         headers: header_map,
         http_method: method,
-        is_base64_encoded: false,
-        multi_value_headers: Default::default(),
-        multi_value_query_string_parameters: Default::default(),
+        is_base64_encoded,
+        multi_value_query_string_parameters: query_map.clone(),
         path: Some(path.to_owned()),
         // path: Some(resource_path.to_owned()),
         path_parameters: vec![resource_path.to_owned()]
             .iter()
             .map(|path| ("path".to_owned(), path.to_owned()))
             .collect(),
-        query_string_parameters: params.into(),
+        query_string_parameters: query_map,
         request_context,
         resource: Some("/{path+}".to_string()),
         stage_variables: Default::default(),
@@ -115,35 +180,53 @@ pub fn create_api_request(
 
 fn create_api_request_context(
     path: &str,
-    request_id: &Uuid,
+    origin: &RequestOrigin,
+    source_ip: &str,
     method: &Method,
     headers: &HeaderMap,
 ) -> ApiGatewayProxyRequestContext {
     let dt = chrono::Local::now();
+    // HTTP/1.0 clients don't have to send a host header
+    let domain_name = headers
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok())
+        .unwrap_or("localhost")
+        .to_string();
+    let domain_prefix = domain_name
+        .split(['.', ':'])
+        .next()
+        .unwrap_or_default()
+        .to_string();
+
     let request_context: ApiGatewayProxyRequestContext = ApiGatewayProxyRequestContext {
-        account_id: Some("123456789012".to_string()),
-        apiid: Some("1234567890".to_owned()),
+        account_id: Some(origin.account_id.to_string()),
+        apiid: Some(origin.api_id.to_string()),
         resource_id: Some("123456".to_string()),
         // resource_path: Some(path.to_string()),
         resource_path: Some("/{path+}".to_owned()),
-        path: Some(format!("/Prod{}", path).to_owned()),
-        stage: Some("Prod".to_string()),
-        domain_name: Some(headers.get("host").unwrap().to_str().unwrap().to_string()),
-        domain_prefix: Some(headers.get("host").unwrap().to_str().unwrap().to_string()),
-        request_id: Some(request_id.to_string()),
+        // The $default stage isn't part of the path
+        path: Some(if origin.stage == "$default" {
+            path.to_owned()
+        } else {
+            format!("/{}{}", origin.stage, path)
+        }),
+        stage: Some(origin.stage.to_string()),
+        domain_name: Some(domain_name),
+        domain_prefix: Some(domain_prefix),
+        request_id: Some(origin.request_id.to_string()),
         protocol: Some("HTTP".to_string()),
         http_method: method.clone(),
         request_time: Some(dt.to_rfc3339()),
         request_time_epoch: dt.timestamp_millis(),
         operation_name: None,
-        identity: create_api_request_identity(headers),
+        identity: create_api_request_identity(headers, source_ip),
         authorizer: create_api_request_authorizer(),
     };
 
     request_context
 }
 
-fn create_api_request_identity(headers: &HeaderMap) -> ApiGatewayRequestIdentity {
+fn create_api_request_identity(headers: &HeaderMap, source_ip: &str) -> ApiGatewayRequestIdentity {
     ApiGatewayRequestIdentity {
         access_key: None,
         account_id: None,
@@ -154,7 +237,7 @@ fn create_api_request_identity(headers: &HeaderMap) -> ApiGatewayRequestIdentity
         cognito_authentication_type: None,
         cognito_identity_id: None,
         cognito_identity_pool_id: None,
-        source_ip: Some("0.0.0.0".to_string()),
+        source_ip: Some(source_ip.to_string()),
         user: None,
         user_agent: headers
             .get("user-agent")
@@ -170,3 +253,37 @@ fn create_api_request_authorizer() -> ApiGatewayRequestAuthorizer {
         fields: HashMap::new(),
     }
 }
+
+/// The caller's address, which is the first address in X-Forwarded-For so requests sent through a
+/// local proxy keep the client's address
+pub fn get_source_ip(headers: &HeaderMap, peer: &SocketAddr) -> String {
+    headers
+        .get(X_FORWARDED_FOR)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.split(',').next())
+        .map(|ip| ip.trim())
+        .filter(|ip| !ip.is_empty())
+        .map(|ip| ip.to_string())
+        .unwrap_or_else(|| peer.ip().to_string())
+}
+
+/// Adds the X-Forwarded-For and X-Forwarded-Proto headers API Gateway sends to integrations, with
+/// the caller appended to any X-Forwarded-For already on the request
+pub fn add_forwarded_headers(headers: &mut HeaderMap, peer: &SocketAddr, scheme: &str) {
+    let peer_ip = peer.ip().to_string();
+    let forwarded_for = match headers
+        .get(X_FORWARDED_FOR)
+        .and_then(|h| h.to_str().ok())
+        .filter(|h| !h.trim().is_empty())
+    {
+        Some(forwarded_for) => format!("{}, {}", forwarded_for, peer_ip),
+        None => peer_ip,
+    };
+
+    if let Ok(forwarded_for) = HeaderValue::from_str(&forwarded_for) {
+        headers.insert(X_FORWARDED_FOR, forwarded_for);
+    }
+    if let Ok(scheme) = HeaderValue::from_str(scheme) {
+        headers.insert(HeaderName::from_static("x-forwarded-proto"), scheme);
+    }
+}
@@ -0,0 +1,105 @@
+use crate::data::ApiState;
+use sam_e_types::config::lambda::event::EventApiProperties;
+
+use aws_lambda_events::apigw::ApiGatewayProxyRequest;
+use axum::{
+    http::{HeaderName, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use tracing::{debug, trace, warn};
+
+/// Validates the request against the route's required parameters and request model before the
+/// function is invoked, as API Gateway's request validators do. Returns the response API Gateway
+/// gives when the request isn't valid, otherwise `None`.
+pub fn validate_request(
+    api_state: &ApiState,
+    api_props: &EventApiProperties,
+    api_request: &ApiGatewayProxyRequest,
+) -> Option<Response> {
+    let missing_parameters: Vec<&str> = api_props
+        .get_required_parameters()
+        .iter()
+        .filter_map(|parameter| {
+            if let Some(header) = parameter.strip_prefix("method.request.header.") {
+                let is_present = HeaderName::try_from(header)
+                    .is_ok_and(|header| api_request.headers.contains_key(header));
+                (!is_present).then_some(header)
+            } else if let Some(query) = parameter.strip_prefix("method.request.querystring.") {
+                let is_present = api_request.query_string_parameters.first(query).is_some();
+                (!is_present).then_some(query)
+            } else {
+                // Path parameters are always there once the route has matched
+                None
+            }
+        })
+        .collect();
+
+    if !missing_parameters.is_empty() {
+        debug!("Request is missing parameters: {:?}", missing_parameters);
+        return Some(bad_request(&format!(
+            "Missing required request parameters: [{}]",
+            missing_parameters.join(", ")
+        )));
+    }
+
+    let model_name = api_props.get_request_model()?;
+    let Some(model) = api_state
+        .get_api(api_props.get_api_id())
+        .and_then(|api| api.get_model(model_name))
+    else {
+        warn!(
+            "Model {} not found in the API's models. Skipping validation",
+            model_name
+        );
+        return None;
+    };
+
+    let validator = serde_json::to_value(model)
+        .map_err(anyhow::Error::from)
+        .and_then(|schema| jsonschema::draft4::new(&schema).map_err(anyhow::Error::from));
+    let validator = match validator {
+        Ok(validator) => validator,
+        Err(e) => {
+            warn!(
+                "Model {} is not a valid JSON schema: {}. Skipping validation",
+                model_name, e
+            );
+            return None;
+        }
+    };
+
+    // A missing body is checked as an empty object so any required properties are caught, unless
+    // the body isn't required
+    let body = match api_request.body.as_deref().filter(|body| !body.trim().is_empty()) {
+        Some(body) => match serde_json::from_str::<serde_json::Value>(body) {
+            Ok(body) => body,
+            Err(_) => {
+                debug!("Request body is not JSON");
+                return Some(bad_request("Invalid request body"));
+            }
+        },
+        None if !api_props.is_request_body_required() => {
+            debug!("No request body to validate against model {}", model_name);
+            return None;
+        }
+        None => serde_json::json!({}),
+    };
+
+    if let Err(e) = validator.validate(&body) {
+        debug!("Request body does not match model {}", model_name);
+        trace!("Validation error: {}", e);
+        return Some(bad_request("Invalid request body"));
+    }
+
+    None
+}
+
+fn bad_request(message: &str) -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        [("x-amzn-ErrorType", "BadRequestException")],
+        Json(serde_json::json!({ "message": message })),
+    )
+        .into_response()
+}
@@ -0,0 +1,422 @@
+use crate::{
+    data::ApiState, issuer::LOCAL_ISSUER_PORT, response::AppError, utils::get_source_ip,
+};
+use sam_e_types::{
+    config::infrastructure::{websocket_api::DEFAULT_ROUTE, WebSocketApiInfrastructure},
+    invocation::{EventRequest, InvocationBuilder},
+};
+
+use anyhow::Result;
+use aws_lambda_events::apigw::{
+    ApiGatewayRequestIdentity, ApiGatewayWebsocketProxyRequest,
+    ApiGatewayWebsocketProxyRequestContext,
+};
+use axum::{
+    body::Bytes,
+    extract::{
+        ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade},
+        ConnectInfo, Path, Query, State,
+    },
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::{engine::general_purpose::URL_SAFE, Engine};
+use chrono::{DateTime, Utc};
+use std::{collections::HashMap, net::SocketAddr};
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, trace, warn};
+use uuid::Uuid;
+
+/// The host functions use to reach the @connections API, as they run in their own containers on
+/// the same network as the API source
+const MANAGEMENT_HOST: &str = "sam-e-invoker";
+
+/// Open connections across every WebSocket API, keyed by connection ID
+pub type Connections = HashMap<String, Connection>;
+
+#[derive(Debug)]
+pub struct Connection {
+    details: ConnectionDetails,
+    last_active_at: DateTime<Utc>,
+    /// Messages sent here are passed on to the client
+    sender: mpsc::UnboundedSender<Message>,
+}
+
+/// What's known about a connection from when it was opened
+#[derive(Debug, Clone)]
+struct ConnectionDetails {
+    connection_id: String,
+    connected_at: DateTime<Utc>,
+    source_ip: String,
+    user_agent: Option<String>,
+    account_id: String,
+    /// Where functions reach the @connections API
+    domain_name: String,
+}
+
+/// Opens a new WebSocket connection. The $connect route (if there is one) is invoked first and the
+/// connection is only accepted if it succeeds, as API Gateway does.
+pub async fn handler(
+    ws: WebSocketUpgrade,
+    headers: HeaderMap,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    Query(params): Query<HashMap<String, String>>,
+    State(api_state): State<ApiState>,
+) -> Result<Response, AppError> {
+    let Some(api) = api_state.get_websocket_api().cloned() else {
+        warn!("No WebSocket API found for port {}", api_state.port);
+        return Ok((StatusCode::NOT_FOUND, "No WebSocket API found").into_response());
+    };
+
+    let details = ConnectionDetails {
+        connection_id: new_connection_id(),
+        connected_at: Utc::now(),
+        source_ip: get_source_ip(&headers, &peer),
+        user_agent: headers
+            .get(header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string()),
+        account_id: api_state.get_account_id().to_string(),
+        domain_name: get_management_domain_name(&api_state, &api),
+    };
+    info!(
+        "New connection to WebSocket API {}: {}",
+        api.name, details.connection_id
+    );
+
+    if let Some(route) = api.get_route("$connect") {
+        let mut request = create_websocket_request(&api, &details, "$connect", "CONNECT");
+        request.headers = headers.clone();
+        request.query_string_parameters = params.into();
+
+        let (status, _) = invoke(&api_state, &route.function_name, request).await?;
+        if !status.is_success() {
+            info!(
+                "Connection {} rejected by the $connect route with status {}",
+                details.connection_id, status
+            );
+            return Ok(status.into_response());
+        }
+    }
+
+    Ok(ws.on_upgrade(move |socket| handle_connection(socket, api_state, api, details)))
+}
+
+async fn handle_connection(
+    mut socket: WebSocket,
+    api_state: ApiState,
+    api: WebSocketApiInfrastructure,
+    details: ConnectionDetails,
+) {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    api_state.get_connections().write().insert(
+        details.connection_id.to_string(),
+        Connection {
+            details: details.clone(),
+            last_active_at: Utc::now(),
+            sender: sender.clone(),
+        },
+    );
+
+    // 1005 is used where the client closes the connection without giving a status code
+    let mut disconnect_status_code = 1005;
+    let mut disconnect_reason = String::new();
+    loop {
+        tokio::select! {
+            incoming = socket.recv() => {
+                let body = match incoming {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Binary(binary))) => String::from_utf8_lossy(&binary).to_string(),
+                    Some(Ok(Message::Close(frame))) => {
+                        if let Some(frame) = frame {
+                            disconnect_status_code = frame.code;
+                            disconnect_reason = frame.reason.to_string();
+                        }
+                        break;
+                    }
+                    // Pings and pongs are answered by axum
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => {
+                        warn!("Connection {} closed abnormally: {}", details.connection_id, e);
+                        disconnect_status_code = 1006;
+                        break;
+                    }
+                    None => {
+                        disconnect_status_code = 1006;
+                        break;
+                    }
+                };
+
+                if let Some(connection) = api_state
+                    .get_connections()
+                    .write()
+                    .get_mut(&details.connection_id)
+                {
+                    connection.last_active_at = Utc::now();
+                }
+
+                // Messages are routed in the background so the client can keep sending (and
+                // receiving) while a function runs
+                tokio::spawn(route_message(
+                    api_state.clone(),
+                    api.clone(),
+                    details.clone(),
+                    body,
+                    sender.clone(),
+                ));
+            }
+            outgoing = receiver.recv() => {
+                let Some(message) = outgoing else {
+                    break;
+                };
+                let closing = matches!(message, Message::Close(_));
+                if socket.send(message).await.is_err() || closing {
+                    break;
+                }
+            }
+        }
+    }
+
+    info!("Connection closed: {}", details.connection_id);
+    api_state
+        .get_connections()
+        .write()
+        .remove(&details.connection_id);
+
+    if let Some(route) = api.get_route("$disconnect") {
+        let mut request = create_websocket_request(&api, &details, "$disconnect", "DISCONNECT");
+        request.request_context.disconnect_status_code = Some(disconnect_status_code.into());
+        request.request_context.disconnect_reason = Some(disconnect_reason);
+
+        if let Err(e) = invoke(&api_state, &route.function_name, request).await {
+            error!("Failed to invoke the $disconnect route: {}", e);
+        }
+    }
+}
+
+/// Sends a message from the client to the function for its route. Two way routes send the
+/// function's response back to the client.
+async fn route_message(
+    api_state: ApiState,
+    api: WebSocketApiInfrastructure,
+    details: ConnectionDetails,
+    body: String,
+    sender: mpsc::UnboundedSender<Message>,
+) {
+    let route_key = select_route(&api, &body);
+    let Some((route_key, route)) =
+        route_key.and_then(|route_key| api.get_route(&route_key).map(|route| (route_key, route)))
+    else {
+        debug!(
+            "No route found for message on connection {}",
+            details.connection_id
+        );
+        let _ = sender.send(error_message("Forbidden", &details));
+        return;
+    };
+
+    debug!("Routing message to {}", route_key);
+    trace!("Message body: {}", body);
+    let mut request = create_websocket_request(&api, &details, &route_key, "MESSAGE");
+    request.request_context.message_id = Some(new_connection_id());
+    request.body = Some(body);
+
+    match invoke(&api_state, &route.function_name, request).await {
+        Ok((status, _)) if status.is_server_error() => {
+            let _ = sender.send(error_message("Internal server error", &details));
+        }
+        Ok((_, response_body)) => {
+            if route.two_way && !response_body.is_empty() {
+                let _ = sender.send(Message::Text(response_body));
+            }
+        }
+        Err(e) => {
+            error!("Failed to invoke route {}: {}", route_key, e);
+            let _ = sender.send(error_message("Internal server error", &details));
+        }
+    }
+}
+
+/// Picks the route for a message using the API's route selection expression (i.e.
+/// `$request.body.action`), falling back to the $default route
+fn select_route(api: &WebSocketApiInfrastructure, body: &str) -> Option<String> {
+    let selected = api
+        .route_selection_expression
+        .strip_prefix("$request.body.")
+        .and_then(|path| {
+            let body = serde_json::from_str::<serde_json::Value>(body).ok()?;
+            path.split('.')
+                .try_fold(&body, |value, key| value.get(key))
+                .and_then(|value| value.as_str())
+                .map(|value| value.to_string())
+        })
+        .filter(|route_key| api.get_route(route_key).is_some());
+
+    selected.or_else(|| {
+        api.get_route(DEFAULT_ROUTE)
+            .map(|_| DEFAULT_ROUTE.to_string())
+    })
+}
+
+/// Functions don't trust the certificate used for HTTPS, so with HTTPS they're pointed at the
+/// @connections API on the plain HTTP listener rather than the API's own port
+fn get_management_domain_name(api_state: &ApiState, api: &WebSocketApiInfrastructure) -> String {
+    let port = match api_state.use_https {
+        true => LOCAL_ISSUER_PORT,
+        false => api.port,
+    };
+    format!("{}:{}", MANAGEMENT_HOST, port)
+}
+
+fn create_websocket_request(
+    api: &WebSocketApiInfrastructure,
+    details: &ConnectionDetails,
+    route_key: &str,
+    event_type: &str,
+) -> ApiGatewayWebsocketProxyRequest {
+    let now = Utc::now();
+    let request_id = Uuid::new_v4().to_string();
+
+    ApiGatewayWebsocketProxyRequest {
+        is_base64_encoded: false,
+        request_context: ApiGatewayWebsocketProxyRequestContext {
+            account_id: Some(details.account_id.to_string()),
+            apiid: Some(api.name.to_string()),
+            stage: Some(api.stage_name.to_string()),
+            route_key: Some(route_key.to_string()),
+            event_type: Some(event_type.to_string()),
+            message_direction: Some("IN".to_string()),
+            connection_id: Some(details.connection_id.to_string()),
+            connected_at: details.connected_at.timestamp_millis(),
+            domain_name: Some(details.domain_name.to_string()),
+            request_id: Some(request_id.to_string()),
+            extended_request_id: Some(request_id),
+            request_time: Some(now.format("%d/%b/%Y:%H:%M:%S %z").to_string()),
+            request_time_epoch: now.timestamp_millis(),
+            identity: ApiGatewayRequestIdentity {
+                source_ip: Some(details.source_ip.to_string()),
+                user_agent: details.user_agent.clone(),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+async fn invoke(
+    api_state: &ApiState,
+    function_name: &str,
+    request: ApiGatewayWebsocketProxyRequest,
+) -> Result<(StatusCode, String)> {
+    let new_invocation = InvocationBuilder::new()
+        .with_request(EventRequest::WebSocket(request))
+        .with_lambda_name(function_name.to_string())
+        .build()?;
+
+    debug!("Invoking {} for WebSocket route", function_name);
+    let response = api_state
+        .get_client()
+        .post("http://0.0.0.0:3030/invoke")
+        .json(&serde_json::json!(new_invocation))
+        .send()
+        .await?;
+
+    let status = StatusCode::from_u16(response.status().as_u16())
+        .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    let body = response.text().await?;
+    trace!("WebSocket route response: {} {}", status, body);
+
+    Ok((status, body))
+}
+
+/// The message API Gateway sends to the client when a message can't be handled
+fn error_message(message: &str, details: &ConnectionDetails) -> Message {
+    Message::Text(
+        serde_json::json!({
+            "message": message,
+            "connectionId": details.connection_id,
+            "requestId": Uuid::new_v4().to_string(),
+        })
+        .to_string(),
+    )
+}
+
+/// Connection IDs look like those from API Gateway (i.e. "L0SM9cOFvHcCIhw=")
+fn new_connection_id() -> String {
+    URL_SAFE.encode(&Uuid::new_v4().as_bytes()[..10])
+}
+
+/// Sends data to a connected client - `POST /@connections/{connection_id}`
+pub async fn post_to_connection(
+    Path(params): Path<HashMap<String, String>>,
+    State(api_state): State<ApiState>,
+    body: Bytes,
+) -> Response {
+    let connection_id = params.get("connection_id").cloned().unwrap_or_default();
+    let connections = api_state.get_connections().read();
+    let Some(connection) = connections.get(&connection_id) else {
+        info!("Unable to post to connection {}: gone", connection_id);
+        return gone();
+    };
+
+    debug!("Posting to connection: {}", connection_id);
+    let message = Message::Text(String::from_utf8_lossy(&body).to_string());
+    if connection.sender.send(message).is_err() {
+        return gone();
+    }
+
+    StatusCode::OK.into_response()
+}
+
+/// Gets the details of a connection - `GET /@connections/{connection_id}`
+pub async fn get_connection(
+    Path(params): Path<HashMap<String, String>>,
+    State(api_state): State<ApiState>,
+) -> Response {
+    let connection_id = params.get("connection_id").cloned().unwrap_or_default();
+    let connections = api_state.get_connections().read();
+    let Some(connection) = connections.get(&connection_id) else {
+        return gone();
+    };
+
+    Json(serde_json::json!({
+        "connectedAt": connection.details.connected_at.to_rfc3339(),
+        "identity": {
+            "sourceIp": connection.details.source_ip,
+            "userAgent": connection.details.user_agent,
+        },
+        "lastActiveAt": connection.last_active_at.to_rfc3339(),
+    }))
+    .into_response()
+}
+
+/// Closes a connection from the server side - `DELETE /@connections/{connection_id}`
+pub async fn delete_connection(
+    Path(params): Path<HashMap<String, String>>,
+    State(api_state): State<ApiState>,
+) -> Response {
+    let connection_id = params.get("connection_id").cloned().unwrap_or_default();
+    let connections = api_state.get_connections().read();
+    let Some(connection) = connections.get(&connection_id) else {
+        return gone();
+    };
+
+    info!("Closing connection: {}", connection_id);
+    let _ = connection.sender.send(Message::Close(Some(CloseFrame {
+        code: 1000,
+        reason: "Connection closed by the server".into(),
+    })));
+
+    StatusCode::NO_CONTENT.into_response()
+}
+
+/// The AWS SDK treats a 410 as the connection no longer existing (GoneException)
+fn gone() -> Response {
+    (
+        StatusCode::GONE,
+        Json(serde_json::json!({ "message": "Gone" })),
+    )
+        .into_response()
+}
@@ -9,6 +9,8 @@ use tracing::{debug, trace, warn};
 use tokio::time::{sleep, Duration};
 use uuid::Uuid;
 
+pub const DEFAULT_EVENT_BUS: &str = "default";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EventRequestItem {
     pub id: Uuid,
@@ -56,6 +58,8 @@ pub struct EventRule {
 pub struct EventStore {
     pub event_buses: Arc<RwLock<HashMap<String, Vec<EventRequestItem>>>>,
     pub event_rules: Vec<EventRule>,
+    pub region: String,
+    pub account_id: String,
 }
 
 impl EventStore {
@@ -63,6 +67,8 @@ impl EventStore {
         Self {
             event_buses: Arc::new(RwLock::new(HashMap::new())),
             event_rules: Vec::new(),
+            region: String::new(),
+            account_id: String::new(),
         }
     }
 
@@ -85,8 +91,14 @@ impl EventStore {
                     let event_rule_props = &event_rule.properties;
                     debug!("Found event rule: {}", event_rule_props.name);
 
+                    let mut triggers = event_rule_props.triggers.clone().unwrap(); // TODO this unwrap
+                    if let Some(queues) = triggers.get_queues() {
+                        let queue_names = queues.iter().map(|queue| get_queue_name(infrastructure, queue)).collect();
+                        triggers.set_queues(queue_names);
+                    }
+
                     let event_rule = EventRule {
-                        triggers: event_rule_props.triggers.clone().unwrap(), // TODO this unwrap
+                        triggers,
                         event_pattern: event_rule_props.event_pattern.clone(),
                     };
                     event_rules.push(event_rule);
@@ -95,6 +107,11 @@ impl EventStore {
             }
         }
 
+        // Every account has a default event bus, which AWS services such as S3 send their events to
+        if !event_bus_names.iter().any(|name| name == DEFAULT_EVENT_BUS) {
+            event_bus_names.push(DEFAULT_EVENT_BUS.to_string());
+        }
+
         debug!("Adding all found event buses to the event store...");
         let mut event_buses = HashMap::new();
         for event_bus_name in event_bus_names {
@@ -106,6 +123,8 @@ impl EventStore {
         Self { 
             event_buses: Arc::new(RwLock::new(event_buses)),
             event_rules,
+            region: config.get_runtime().get_region().to_string(),
+            account_id: config.get_runtime().get_account_id().to_string(),
         }
     }
 
@@ -187,29 +206,32 @@ impl EventStore {
                                     }
                                 }
 
+                                let event_detail_as_value: serde_json::Value = serde_json::from_str(&event.event.detail).unwrap_or_default();
+                                if let (Some(detail), true) = (&event_pattern.detail, matched) {
+                                    debug!("Checking detail...");
+                                    matched = matches_detail(detail, &event_detail_as_value);
+                                }
+
                                 if matched {
                                     debug!("Event matched rule: {:#?}", rule);
                                     debug!("Sending event to trigger...");
 
-                                    // let event_string = serde_json::to_string(&event).unwrap();
-                                    let event_detail_as_value: serde_json::Value = serde_json::from_str(&event.event.detail).unwrap();
-
                                     let datetime = chrono::Utc::now();
                                     let lambda_event = aws_lambda_events::event::eventbridge::EventBridgeEvent {
                                         version: Some("0".to_string()),
                                         id: Some(event.id.to_string()),
                                         detail_type: event.event.detail_type.clone(),
                                         source: event.event.source.clone(),
-                                        account: Some("123456789012".to_string()),
+                                        account: Some(read_store.account_id.clone()),
                                         time: Some(datetime),
-                                        region: Some("eu-west-1".to_string()),
-                                        resources: Some(vec!["resource1".to_string(), "resource2".to_string()]),
+                                        region: Some(read_store.region.clone()),
+                                        resources: event.event.resources.clone(),
                                         detail: event_detail_as_value,
                                     };
 
                                     let event_string = serde_json::to_string(&lambda_event).unwrap();
 
-                                    let send_res = rule.triggers.send(event_string).await;
+                                    let send_res = rule.triggers.send(event_string, &event.id.to_string(), &read_store.region).await;
                                     if let Err(e) = send_res {
                                         warn!("Error sending event to trigger: {:#?}", e);
                                     } else {
@@ -240,3 +262,65 @@ impl EventStore {
         self.event_buses.read().keys().cloned().collect()
     }
 }
+
+/// Checks the event's detail against the rule's detail pattern. Each field in the pattern has to be
+/// in the detail, with its value matching one of the values listed for it.
+fn matches_detail(pattern: &serde_yaml::Value, detail: &serde_json::Value) -> bool {
+    let Ok(pattern) = serde_json::to_value(pattern) else {
+        warn!("Unable to parse the detail pattern: {:?}", pattern);
+        return false;
+    };
+
+    matches_pattern(&pattern, detail)
+}
+
+fn matches_pattern(pattern: &serde_json::Value, value: &serde_json::Value) -> bool {
+    match pattern {
+        serde_json::Value::Object(fields) => fields.iter().all(|(field, field_pattern)| {
+            let field_value = value.get(field).unwrap_or(&serde_json::Value::Null);
+            matches_pattern(field_pattern, field_value)
+        }),
+        serde_json::Value::Array(values) => values.iter().any(|expected| matches_value(expected, value)),
+        expected => matches_value(expected, value),
+    }
+}
+
+/// Matches a single value from a pattern, which can be a literal or a content filter such as
+/// `{"prefix": "images/"}`. Arrays in the event match if any of their items do.
+fn matches_value(expected: &serde_json::Value, value: &serde_json::Value) -> bool {
+    if let serde_json::Value::Array(values) = value {
+        return values.iter().any(|value| matches_value(expected, value));
+    }
+
+    let serde_json::Value::Object(filter) = expected else {
+        return expected == value;
+    };
+
+    filter.iter().all(|(operator, operand)| match operator.as_str() {
+        "prefix" => matches!((operand.as_str(), value.as_str()), (Some(prefix), Some(value)) if value.starts_with(prefix)),
+        "suffix" => matches!((operand.as_str(), value.as_str()), (Some(suffix), Some(value)) if value.ends_with(suffix)),
+        "exists" => operand.as_bool() == Some(!value.is_null()),
+        "anything-but" => match operand {
+            serde_json::Value::Array(excluded) => !value.is_null() && !excluded.contains(value),
+            excluded => !value.is_null() && !matches_value(excluded, value),
+        },
+        _ => {
+            warn!("Unsupported event pattern filter: {}", operator);
+            false
+        }
+    })
+}
+
+/// The name the queue is created with locally, which differs from its name in the template for
+/// FIFO queues
+fn get_queue_name(infrastructure: &[Infrastructure], queue: &str) -> String {
+    infrastructure
+        .iter()
+        .find_map(|i| match i {
+            Infrastructure::Sqs(sqs_data) if sqs_data.properties.name == queue => {
+                Some(sqs_data.properties.get_queue_name())
+            }
+            _ => None,
+        })
+        .unwrap_or_else(|| queue.to_string())
+}
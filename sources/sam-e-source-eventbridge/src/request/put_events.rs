@@ -1,6 +1,6 @@
-use crate::data::{store::{EventRequestItemBuilder, EventStore}, PutEventsRequest, PutEventsResponse, PutEventsResponseBuilder, PutEventsResultEntryBuilder};
+use crate::data::{store::{EventRequestItemBuilder, EventStore, DEFAULT_EVENT_BUS}, PutEventsRequest, PutEventsResponse, PutEventsResponseBuilder, PutEventsResultEntryBuilder};
 
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use tracing::{debug, trace};
 
 pub async fn put_events_handler(put_events_request: PutEventsRequest, event_store: &EventStore) -> Result<PutEventsResponse> {
@@ -10,9 +10,8 @@ pub async fn put_events_handler(put_events_request: PutEventsRequest, event_stor
     debug!("Adding event to event store");
     let mut entry_ids = Vec::new();
     for entry in put_events_request.entries {
-        let Some(event_bus_name) = &entry.event_bus_name else {
-            return Err(anyhow!("Event bus name is required"));
-        };
+        // Events without a bus are sent to the default bus, as in AWS
+        let event_bus_name = entry.event_bus_name.clone().unwrap_or_else(|| DEFAULT_EVENT_BUS.to_string());
         trace!("Adding entry to event bus: {:#?}", &entry.event_bus_name);
         
         let event_id = uuid::Uuid::new_v4();
@@ -22,7 +21,7 @@ pub async fn put_events_handler(put_events_request: PutEventsRequest, event_stor
             .build()?;
 
         let mut event_buses = event_store.event_buses.write();
-        event_buses.entry(event_bus_name.clone()).or_insert_with(Vec::new).push(new_event_request);
+        event_buses.entry(event_bus_name).or_insert_with(Vec::new).push(new_event_request);
         debug!("Entry added to the event bus successfully");
         trace!("New state: {:#?}", event_buses);
 
@@ -1,5 +1,9 @@
-use sam_e_types::config::{infrastructure::Infrastructure, Config};
+use sam_e_types::config::{
+    infrastructure::{s3::S3Infrastructure, Infrastructure},
+    Config,
+};
 
+use aws_sdk_eventbridge::Client as EventBridgeClient;
 use aws_sdk_sqs::{config::Region, Client as QueueClient};
 
 use reqwest::Client as RequestClient;
@@ -10,6 +14,8 @@ pub struct ApiState {
     pub infrastructure: Vec<Infrastructure>,
     pub request_client: RequestClient,
     pub queue_client: QueueClient,
+    pub event_bridge_client: EventBridgeClient,
+    pub region: String,
 }
 
 impl ApiState {
@@ -20,17 +26,24 @@ impl ApiState {
         debug!("Reqwest client created");
 
         debug!("Creating SQS Client...");
-        let queue_client = create_sqs_client().await;
+        let region = config.get_runtime().get_region().to_string();
+        let queue_client = create_sqs_client(&region).await;
         debug!("Queue client created");
 
+        debug!("Creating EventBridge Client...");
+        let event_bridge_client = create_event_bridge_client(&region).await;
+        debug!("EventBridge client created");
+
         Self {
             infrastructure: config.get_infrastructure().to_owned(),
             request_client,
             queue_client,
+            event_bridge_client,
+            region,
         }
     }
 
-    pub fn _get_request_client(&self) -> &RequestClient {
+    pub fn get_request_client(&self) -> &RequestClient {
         &self.request_client
     }
 
@@ -38,21 +51,69 @@ impl ApiState {
         &self.queue_client
     }
 
-    pub fn get_infrastructure(&self) -> &Vec<Infrastructure> {
-        &self.infrastructure
+    pub fn get_event_bridge_client(&self) -> &EventBridgeClient {
+        &self.event_bridge_client
+    }
+
+    pub fn get_region(&self) -> &str {
+        &self.region
+    }
+
+    pub fn get_buckets(&self) -> Vec<&S3Infrastructure> {
+        self.infrastructure
+            .iter()
+            .filter_map(|i| match i {
+                Infrastructure::S3(s3_data) => Some(&s3_data.properties),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the bucket's infrastructure, if the bucket is in the template
+    pub fn get_bucket(&self, bucket_name: &str) -> Option<&S3Infrastructure> {
+        self.get_buckets()
+            .into_iter()
+            .find(|bucket| bucket.name == bucket_name)
+    }
+
+    /// The name the queue is created with locally, which differs from its name in the template
+    /// for FIFO queues
+    pub fn get_queue_name(&self, queue: &str) -> String {
+        self.infrastructure
+            .iter()
+            .find_map(|i| match i {
+                Infrastructure::Sqs(sqs_data) if sqs_data.properties.name == queue => {
+                    Some(sqs_data.properties.get_queue_name())
+                }
+                _ => None,
+            })
+            .unwrap_or_else(|| queue.to_string())
     }
 }
 
-async fn create_sqs_client() -> QueueClient {
+async fn create_sqs_client(region: &str) -> QueueClient {
     debug!("Creating AWS SQS client");
-    let region = Region::new("eu-west-1");
+    let region = Region::new(region.to_string());
 
     // let config = aws_config::defaults(BehaviorVersion::v2023_11_09())
     let config = aws_config::from_env()
         .region(region)
-        .endpoint_url("http://sqs-local:9324")
+        .endpoint_url("http://localhost:9324")
         .load()
         .await;
 
     QueueClient::new(&config)
 }
+
+async fn create_event_bridge_client(region: &str) -> EventBridgeClient {
+    debug!("Creating AWS EventBridge client");
+    let region = Region::new(region.to_string());
+
+    let config = aws_config::from_env()
+        .region(region)
+        .endpoint_url("http://localhost:3002")
+        .load()
+        .await;
+
+    EventBridgeClient::new(&config)
+}
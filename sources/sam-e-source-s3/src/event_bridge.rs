@@ -0,0 +1,98 @@
+use aws_lambda_events::s3::S3EventRecord;
+use aws_sdk_eventbridge::{types::PutEventsRequestEntry, Client};
+use serde_json::json;
+use tracing::{debug, error, trace};
+
+const EVENT_SOURCE: &str = "aws.s3";
+const EVENT_BUS: &str = "default";
+
+/// Sends the record to the default event bus as the event S3 sends to EventBridge. Only object
+/// creations and deletions are sent, as these are the only notifications MinIO or the S3 store
+/// send.
+pub async fn send_event(record: &S3EventRecord, key: &str, client: &Client) {
+    let event_name = record.event_name.as_deref().unwrap_or_default();
+    let Some((detail_type, detail)) = create_event(record, event_name, key) else {
+        trace!("No EventBridge event for: {}. Skipping", event_name);
+        return;
+    };
+    debug!("Sending: {} event for: {} to EventBridge", detail_type, key);
+
+    let entry = PutEventsRequestEntry::builder()
+        .source(EVENT_SOURCE)
+        .detail_type(detail_type)
+        .detail(detail.to_string())
+        .set_resources(record.s3.bucket.arn.clone().map(|arn| vec![arn]))
+        .event_bus_name(EVENT_BUS)
+        .build();
+
+    match client.put_events().entries(entry).send().await {
+        Ok(output) if output.failed_entry_count() == 0 => {
+            debug!("Successfully sent event to EventBridge");
+        }
+        Ok(output) => {
+            error!(
+                "EventBridge failed to accept the event: {:?}",
+                output.entries()
+            );
+        }
+        Err(e) => {
+            error!("Failed to send event to EventBridge: {}", e);
+        }
+    }
+}
+
+/// Creates the detail type and detail of the event from the record. The reason and deletion type
+/// come from the event name, as EventBridge names the API call where notifications name the event.
+fn create_event(
+    record: &S3EventRecord,
+    event_name: &str,
+    key: &str,
+) -> Option<(&'static str, serde_json::Value)> {
+    let object = &record.s3.object;
+    let (detail_type, reason, deletion_type) = match event_name {
+        "ObjectCreated:Put" => ("Object Created", "PutObject", None),
+        "ObjectCreated:Post" => ("Object Created", "POST Object", None),
+        "ObjectCreated:Copy" => ("Object Created", "CopyObject", None),
+        "ObjectCreated:CompleteMultipartUpload" => {
+            ("Object Created", "CompleteMultipartUpload", None)
+        }
+        "ObjectRemoved:Delete" => (
+            "Object Deleted",
+            "DeleteObject",
+            Some("Permanently Deleted"),
+        ),
+        "ObjectRemoved:DeleteMarkerCreated" => (
+            "Object Deleted",
+            "DeleteObject",
+            Some("Delete Marker Created"),
+        ),
+        _ => return None,
+    };
+
+    let mut detail_object = json!({ "key": key });
+    if deletion_type.is_none() {
+        detail_object["size"] = json!(object.size);
+        detail_object["etag"] = json!(object.e_tag);
+    }
+    if let Some(version_id) = &object.version_id {
+        detail_object["version-id"] = json!(version_id);
+    }
+    detail_object["sequencer"] = json!(object.sequencer);
+
+    let mut detail = json!({
+        "version": "0",
+        "bucket": {
+            "name": record.s3.bucket.name,
+        },
+        "object": detail_object,
+        "request-id": record.response_elements.get("x-amz-request-id"),
+        "requester": record.principal_id.principal_id,
+        "source-ip-address": record.request_parameters.source_ip_address,
+        "reason": reason,
+    });
+    if let Some(deletion_type) = deletion_type {
+        detail["deletion-type"] = json!(deletion_type);
+    }
+
+    Some((detail_type, detail))
+}
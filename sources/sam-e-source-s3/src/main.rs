@@ -1,9 +1,12 @@
 mod data;
+mod event_bridge;
 mod response;
+mod store;
 mod webhook;
 
 use axum::{routing::post, Router};
 
+use tokio::sync::mpsc;
 use tracing::{debug, info};
 use tracing_subscriber::EnvFilter;
 
@@ -27,6 +30,16 @@ async fn main() -> anyhow::Result<()> {
     debug!("Creating the API state");
     let api_state = data::ApiState::from_config(&config).await;
 
+    tokio::spawn(webhook::send_test_events(api_state.clone()));
+
+    if config.get_runtime().get_use_s3_store() {
+        debug!("Setting up the S3 store");
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let object_store = store::ObjectStore::from_config(&config, sender)?;
+        tokio::spawn(store::serve(object_store));
+        tokio::spawn(webhook::handle_notifications(api_state.clone(), receiver));
+    }
+
     debug!("Setting up the webhook route");
     let app = Router::new()
         .route("/", post(webhook::handler))
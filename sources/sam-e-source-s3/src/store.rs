@@ -0,0 +1,41 @@
+mod cors;
+mod data;
+mod middleware;
+mod request;
+mod response;
+mod utils;
+mod xml;
+
+pub use data::ObjectStore;
+
+use axum::Router;
+use std::net::SocketAddr;
+use tracing::{error, info};
+
+const STORE_PORT: u16 = 9000;
+
+/// Serves the buckets on the port MinIO would, where every request is routed by the middleware.
+/// Uploads are streamed to disk, so only the other bodies are held to the default body limit.
+pub async fn serve(object_store: ObjectStore) {
+    let app = Router::new()
+        .fallback(request::handler)
+        .with_state(object_store);
+
+    let listener = match tokio::net::TcpListener::bind(("0.0.0.0", STORE_PORT)).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("Failed to bind the S3 store to port {}: {}", STORE_PORT, e);
+            return;
+        }
+    };
+    info!("S3 store listening on port {}", STORE_PORT);
+
+    let served = axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await;
+    if let Err(e) = served {
+        error!("S3 store failed: {}", e);
+    }
+}
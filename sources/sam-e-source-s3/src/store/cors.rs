@@ -0,0 +1,157 @@
+use crate::store::{
+    data::{ObjectStore, S3Request},
+    response::{empty_response, S3Error},
+};
+
+use axum::{
+    http::{HeaderMap, HeaderValue, StatusCode},
+    response::Response,
+};
+use sam_e_types::config::infrastructure::s3::CorsRule;
+use tracing::{debug, trace};
+
+/// Answers a preflight request with the first of the bucket's rules that allows the origin, method
+/// and headers. As in S3, the request is refused when no rule allows it.
+pub fn preflight(s3_request: &S3Request, object_store: &ObjectStore) -> Result<Response, S3Error> {
+    let (Some(origin), Some(method)) = (
+        s3_request.get_header("origin"),
+        s3_request.get_header("access-control-request-method"),
+    ) else {
+        return Err(S3Error::invalid_request(
+            "Insufficient information. Origin request header needed.",
+        ));
+    };
+    let request_headers = s3_request
+        .get_header("access-control-request-headers")
+        .map(|headers| {
+            headers
+                .split(',')
+                .map(|header| header.trim().to_lowercase())
+                .filter(|header| !header.is_empty())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    let cors_rules =
+        object_store.read_bucket(&s3_request.bucket, |bucket| Ok(bucket.cors_rules.clone()))?;
+    if cors_rules.is_empty() {
+        return Err(S3Error::access_denied(
+            "CORSResponse: CORS is not enabled for this bucket.",
+        ));
+    }
+
+    let Some(rule) = find_rule(&cors_rules, origin, method, &request_headers) else {
+        debug!("No CORS rule allows: {} {}", method, origin);
+        return Err(S3Error::access_denied(
+            "CORSResponse: This CORS request is not allowed. This is usually because the evaluation of Origin, request method / Access-Control-Request-Method or Access-Control-Request-Headers are not whitelisted by the resource's CORS spec.",
+        ));
+    };
+    trace!("Matched CORS rule: {:?}", rule);
+
+    let mut response = empty_response(StatusCode::OK);
+    add_rule_headers(response.headers_mut(), rule, origin);
+    if !request_headers.is_empty() {
+        insert_header(
+            response.headers_mut(),
+            "access-control-allow-headers",
+            &request_headers.join(", "),
+        );
+    }
+
+    Ok(response)
+}
+
+/// Adds the CORS headers to the response of a cross-origin request that one of the bucket's rules
+/// allows
+pub fn add_response_headers(
+    s3_request: &S3Request,
+    method: &str,
+    object_store: &ObjectStore,
+    headers: &mut HeaderMap,
+) {
+    let Some(origin) = s3_request.get_header("origin") else {
+        return;
+    };
+    let Ok(cors_rules) =
+        object_store.read_bucket(&s3_request.bucket, |bucket| Ok(bucket.cors_rules.clone()))
+    else {
+        return;
+    };
+
+    if let Some(rule) = find_rule(&cors_rules, origin, method, &[]) {
+        add_rule_headers(headers, rule, origin);
+    }
+}
+
+fn find_rule<'a>(
+    cors_rules: &'a [CorsRule],
+    origin: &str,
+    method: &str,
+    request_headers: &[String],
+) -> Option<&'a CorsRule> {
+    cors_rules.iter().find(|rule| {
+        rule.allowed_origins
+            .iter()
+            .any(|allowed_origin| matches_wildcard(allowed_origin, origin))
+            && rule
+                .allowed_methods
+                .iter()
+                .any(|allowed_method| allowed_method.eq_ignore_ascii_case(method))
+            && request_headers.iter().all(|request_header| {
+                rule.allowed_headers.iter().any(|allowed_header| {
+                    matches_wildcard(&allowed_header.to_lowercase(), request_header)
+                })
+            })
+    })
+}
+
+fn add_rule_headers(headers: &mut HeaderMap, rule: &CorsRule, origin: &str) {
+    let allows_any_origin = rule
+        .allowed_origins
+        .iter()
+        .any(|allowed_origin| allowed_origin == "*");
+    if allows_any_origin {
+        insert_header(headers, "access-control-allow-origin", "*");
+    } else {
+        insert_header(headers, "access-control-allow-origin", origin);
+        insert_header(headers, "access-control-allow-credentials", "true");
+    }
+    insert_header(
+        headers,
+        "access-control-allow-methods",
+        &rule.allowed_methods.join(", "),
+    );
+    if !rule.exposed_headers.is_empty() {
+        insert_header(
+            headers,
+            "access-control-expose-headers",
+            &rule.exposed_headers.join(", "),
+        );
+    }
+    if let Some(max_age) = rule.max_age {
+        insert_header(headers, "access-control-max-age", &max_age.to_string());
+    }
+    insert_header(
+        headers,
+        "vary",
+        "Origin, Access-Control-Request-Headers, Access-Control-Request-Method",
+    );
+}
+
+fn insert_header(headers: &mut HeaderMap, name: &'static str, value: &str) {
+    if let Ok(value) = HeaderValue::from_str(value) {
+        headers.insert(name, value);
+    }
+}
+
+/// Origins and headers in rules can contain a single `*` matching anything
+fn matches_wildcard(pattern: &str, value: &str) -> bool {
+    match pattern.split_once('*') {
+        Some((start, end)) => {
+            value.len() >= start.len() + end.len()
+                && value.starts_with(start)
+                && value.ends_with(end)
+        }
+        None => pattern == value,
+    }
+}
@@ -0,0 +1,513 @@
+use crate::store::{response::S3Error, utils::encode_key};
+
+use sam_e_types::config::{
+    infrastructure::{s3::CorsRule, Infrastructure},
+    Config,
+};
+
+use aws_lambda_events::s3::{
+    S3Bucket, S3Entity, S3EventRecord, S3Object, S3RequestParameters, S3UserIdentity,
+};
+use axum::{
+    body::{Body, Bytes},
+    http::HeaderMap,
+};
+use chrono::{DateTime, Utc};
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    path::PathBuf,
+    sync::Arc,
+};
+use tokio::{fs::File, sync::mpsc::UnboundedSender};
+use tracing::{debug, error, info, trace, warn};
+use uuid::Uuid;
+
+/// Where buckets are kept, which is mounted from a volume so objects outlive the environment
+const STORE_LOCATION: &str = "/s3-store";
+const INDEX_FILE: &str = "index.json";
+const OBJECTS_DIRECTORY: &str = "objects";
+
+/// The operation a request is for, worked out from its method, path and query
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum S3Action {
+    ListBuckets,
+    CreateBucket,
+    HeadBucket,
+    DeleteBucket,
+    GetBucketLocation,
+    ListObjects,
+    ListObjectsV2,
+    GetBucketCors,
+    PutBucketCors,
+    DeleteBucketCors,
+    DeleteObjects,
+    PutObject,
+    CopyObject,
+    GetObject,
+    HeadObject,
+    DeleteObject,
+    CreateMultipartUpload,
+    UploadPart,
+    CompleteMultipartUpload,
+    AbortMultipartUpload,
+    ListParts,
+    ListMultipartUploads,
+    /// A CORS preflight request
+    Options,
+    Unsupported,
+}
+
+impl S3Action {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            S3Action::ListBuckets => "ListBuckets",
+            S3Action::CreateBucket => "CreateBucket",
+            S3Action::HeadBucket => "HeadBucket",
+            S3Action::DeleteBucket => "DeleteBucket",
+            S3Action::GetBucketLocation => "GetBucketLocation",
+            S3Action::ListObjects => "ListObjects",
+            S3Action::ListObjectsV2 => "ListObjectsV2",
+            S3Action::GetBucketCors => "GetBucketCors",
+            S3Action::PutBucketCors => "PutBucketCors",
+            S3Action::DeleteBucketCors => "DeleteBucketCors",
+            S3Action::DeleteObjects => "DeleteObjects",
+            S3Action::PutObject => "PutObject",
+            S3Action::CopyObject => "CopyObject",
+            S3Action::GetObject => "GetObject",
+            S3Action::HeadObject => "HeadObject",
+            S3Action::DeleteObject => "DeleteObject",
+            S3Action::CreateMultipartUpload => "CreateMultipartUpload",
+            S3Action::UploadPart => "UploadPart",
+            S3Action::CompleteMultipartUpload => "CompleteMultipartUpload",
+            S3Action::AbortMultipartUpload => "AbortMultipartUpload",
+            S3Action::ListParts => "ListParts",
+            S3Action::ListMultipartUploads => "ListMultipartUploads",
+            S3Action::Options => "OPTIONS",
+            S3Action::Unsupported => "Unsupported",
+        }
+    }
+}
+
+/// A request to the store, parsed via the middleware
+#[derive(Debug)]
+pub struct S3Request {
+    pub action: S3Action,
+    pub bucket: String,
+    pub key: String,
+    pub query: HashMap<String, String>,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+    /// The body of uploads, which is streamed to disk rather than read up front. It's behind a lock
+    /// so the request can be shared while the upload is read.
+    pub upload: Mutex<Option<Body>>,
+    pub request_id: String,
+    pub source_ip_address: String,
+}
+
+impl S3Request {
+    pub fn get_query(&self, name: &str) -> Option<&str> {
+        self.query.get(name).map(|value| value.as_str())
+    }
+
+    pub fn get_header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|value| value.to_str().ok())
+    }
+
+    pub fn take_upload(&self) -> Body {
+        self.upload.lock().take().unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Bucket {
+    pub name: String,
+    pub created: DateTime<Utc>,
+    #[serde(default)]
+    pub cors_rules: Vec<CorsRule>,
+    #[serde(default)]
+    pub objects: BTreeMap<String, Object>,
+    /// Uploads in progress aren't kept between runs
+    #[serde(skip)]
+    pub uploads: HashMap<String, MultipartUpload>,
+}
+
+impl Bucket {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            created: Utc::now(),
+            cors_rules: vec![],
+            objects: BTreeMap::new(),
+            uploads: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Object {
+    /// The file holding the object's data
+    pub file_id: Uuid,
+    pub size: u64,
+    pub etag: String,
+    pub last_modified: DateTime<Utc>,
+    /// Headers the object is returned with, such as its content type and user metadata
+    pub headers: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MultipartUpload {
+    pub key: String,
+    pub initiated: DateTime<Utc>,
+    pub headers: BTreeMap<String, String>,
+    pub parts: BTreeMap<u32, Part>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Part {
+    pub file_id: Uuid,
+    pub size: u64,
+    pub md5: Vec<u8>,
+    pub etag: String,
+    pub last_modified: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ObjectStore {
+    location: PathBuf,
+    buckets: Arc<RwLock<BTreeMap<String, Bucket>>>,
+    notification_sender: UnboundedSender<S3EventRecord>,
+    region: String,
+    account_id: String,
+}
+
+impl ObjectStore {
+    /// Loads the buckets kept from previous runs and creates any in the config that don't exist
+    /// yet. CORS rules from the template replace those set on the bucket since.
+    pub fn from_config(
+        config: &Config,
+        notification_sender: UnboundedSender<S3EventRecord>,
+    ) -> anyhow::Result<Self> {
+        debug!("Building the object store from config...");
+        let location = PathBuf::from(STORE_LOCATION);
+        fs::create_dir_all(&location)?;
+
+        let mut buckets = BTreeMap::new();
+        for entry in fs::read_dir(&location)?.flatten() {
+            let index_location = entry.path().join(INDEX_FILE);
+            let Ok(index) = fs::read_to_string(&index_location) else {
+                continue;
+            };
+
+            match serde_json::from_str::<Bucket>(&index) {
+                Ok(bucket) => {
+                    debug!("Loaded bucket: {}", bucket.name);
+                    buckets.insert(bucket.name.clone(), bucket);
+                }
+                Err(e) => {
+                    warn!("Unable to read the index of: {:?}: {}", index_location, e);
+                }
+            }
+        }
+
+        for infrastructure in config.get_infrastructure() {
+            let Infrastructure::S3(s3_data) = infrastructure else {
+                continue;
+            };
+            let s3 = &s3_data.properties;
+
+            let bucket = buckets
+                .entry(s3.name.clone())
+                .or_insert_with(|| Bucket::new(&s3.name));
+            if !s3.cors_rules.is_empty() {
+                bucket.cors_rules = s3.cors_rules.clone();
+            }
+        }
+
+        let object_store = Self {
+            location,
+            buckets: Arc::new(RwLock::new(buckets)),
+            notification_sender,
+            region: config.get_runtime().get_region().to_string(),
+            account_id: config.get_runtime().get_account_id().to_string(),
+        };
+
+        for bucket in object_store.buckets.read().values() {
+            object_store.save_bucket(bucket)?;
+            object_store.remove_unused_files(bucket);
+        }
+
+        debug!("Object store built successfully");
+        Ok(object_store)
+    }
+
+    pub fn get_region(&self) -> &str {
+        &self.region
+    }
+
+    pub fn get_account_id(&self) -> &str {
+        &self.account_id
+    }
+
+    pub fn has_bucket(&self, bucket: &str) -> bool {
+        self.buckets.read().contains_key(bucket)
+    }
+
+    pub fn list_buckets(&self) -> Vec<(String, DateTime<Utc>)> {
+        self.buckets
+            .read()
+            .values()
+            .map(|bucket| (bucket.name.clone(), bucket.created))
+            .collect()
+    }
+
+    pub fn create_bucket(&self, bucket_name: &str) -> Result<(), S3Error> {
+        let mut buckets = self.buckets.write();
+        if buckets.contains_key(bucket_name) {
+            return Err(S3Error::bucket_already_owned_by_you(bucket_name));
+        }
+
+        let bucket = Bucket::new(bucket_name);
+        self.save_bucket(&bucket)
+            .map_err(|e| S3Error::internal_error(e.to_string()))?;
+        buckets.insert(bucket_name.to_string(), bucket);
+        info!("Created bucket: {}", bucket_name);
+
+        Ok(())
+    }
+
+    pub fn delete_bucket(&self, bucket_name: &str) -> Result<(), S3Error> {
+        let mut buckets = self.buckets.write();
+        let bucket = buckets
+            .get(bucket_name)
+            .ok_or_else(|| S3Error::no_such_bucket(bucket_name))?;
+        if !bucket.objects.is_empty() {
+            return Err(S3Error::bucket_not_empty(bucket_name));
+        }
+
+        buckets.remove(bucket_name);
+        if let Err(e) = fs::remove_dir_all(self.location.join(bucket_name)) {
+            warn!(
+                "Unable to remove the files of bucket: {}: {}",
+                bucket_name, e
+            );
+        }
+        info!("Deleted bucket: {}", bucket_name);
+
+        Ok(())
+    }
+
+    /// Reads from the bucket while it's locked
+    pub fn read_bucket<T>(
+        &self,
+        bucket_name: &str,
+        read: impl FnOnce(&Bucket) -> Result<T, S3Error>,
+    ) -> Result<T, S3Error> {
+        let buckets = self.buckets.read();
+        let bucket = buckets
+            .get(bucket_name)
+            .ok_or_else(|| S3Error::no_such_bucket(bucket_name))?;
+
+        read(bucket)
+    }
+
+    /// Changes the bucket while it's locked, saving its index once the change is made. Files no
+    /// longer used by the bucket after the change are removed.
+    pub fn update_bucket<T>(
+        &self,
+        bucket_name: &str,
+        update: impl FnOnce(&mut Bucket) -> Result<T, S3Error>,
+    ) -> Result<T, S3Error> {
+        let mut buckets = self.buckets.write();
+        let bucket = buckets
+            .get_mut(bucket_name)
+            .ok_or_else(|| S3Error::no_such_bucket(bucket_name))?;
+
+        let file_ids = get_file_ids(bucket);
+        let result = update(bucket)?;
+        self.save_bucket(bucket)
+            .map_err(|e| S3Error::internal_error(e.to_string()))?;
+
+        let remaining_file_ids = get_file_ids(bucket);
+        for file_id in file_ids.difference(&remaining_file_ids) {
+            self.remove_file(bucket_name, file_id);
+        }
+
+        Ok(result)
+    }
+
+    pub fn get_object(&self, bucket_name: &str, key: &str) -> Result<Object, S3Error> {
+        self.read_bucket(bucket_name, |bucket| {
+            bucket
+                .objects
+                .get(key)
+                .cloned()
+                .ok_or_else(|| S3Error::no_such_key(key))
+        })
+    }
+
+    /// Creates a new file in the bucket to write to, which isn't used until it's added to an object
+    /// or part
+    pub async fn create_file(&self, bucket_name: &str) -> Result<(Uuid, File), S3Error> {
+        let file_id = Uuid::new_v4();
+        let directory = self.location.join(bucket_name).join(OBJECTS_DIRECTORY);
+        tokio::fs::create_dir_all(&directory)
+            .await
+            .map_err(|e| S3Error::internal_error(e.to_string()))?;
+        let file = File::create(directory.join(file_id.to_string()))
+            .await
+            .map_err(|e| S3Error::internal_error(e.to_string()))?;
+        trace!("Created file: {} in bucket: {}", file_id, bucket_name);
+
+        Ok((file_id, file))
+    }
+
+    pub async fn open_file(&self, bucket_name: &str, file_id: &Uuid) -> Result<File, S3Error> {
+        File::open(self.get_file_location(bucket_name, file_id))
+            .await
+            .map_err(|e| S3Error::internal_error(e.to_string()))
+    }
+
+    /// Copies the file to a new file in the other bucket, which isn't used until it's added to an
+    /// object
+    pub async fn copy_file(
+        &self,
+        bucket_name: &str,
+        file_id: &Uuid,
+        to_bucket_name: &str,
+    ) -> Result<Uuid, S3Error> {
+        let (to_file_id, _) = self.create_file(to_bucket_name).await?;
+        let copied = tokio::fs::copy(
+            self.get_file_location(bucket_name, file_id),
+            self.get_file_location(to_bucket_name, &to_file_id),
+        )
+        .await;
+        if let Err(e) = copied {
+            self.remove_file(to_bucket_name, &to_file_id);
+            return Err(S3Error::internal_error(e.to_string()));
+        }
+
+        Ok(to_file_id)
+    }
+
+    /// Removes a file that was written but never used, such as when a request fails after its body
+    /// was saved
+    pub fn remove_file(&self, bucket_name: &str, file_id: &Uuid) {
+        if let Err(e) = fs::remove_file(self.get_file_location(bucket_name, file_id)) {
+            warn!(
+                "Unable to remove file: {} from bucket: {}: {}",
+                file_id, bucket_name, e
+            );
+        }
+    }
+
+    /// Sends the notification S3 sends for the event, for the S3 source to route to the bucket's
+    /// destinations
+    pub fn notify(
+        &self,
+        s3_request: &S3Request,
+        key: &str,
+        event_name: &str,
+        object: Option<&Object>,
+    ) {
+        let record = S3EventRecord {
+            event_version: Some("2.1".to_string()),
+            event_source: Some("aws:s3".to_string()),
+            aws_region: Some(self.region.clone()),
+            event_time: Utc::now(),
+            event_name: Some(event_name.to_string()),
+            principal_id: S3UserIdentity {
+                principal_id: Some(format!("AWS:{}", self.account_id)),
+            },
+            request_parameters: S3RequestParameters {
+                source_ip_address: Some(s3_request.source_ip_address.clone()),
+            },
+            response_elements: HashMap::from([(
+                "x-amz-request-id".to_string(),
+                s3_request.request_id.clone(),
+            )]),
+            s3: S3Entity {
+                schema_version: Some("1.0".to_string()),
+                configuration_id: None,
+                bucket: S3Bucket {
+                    name: Some(s3_request.bucket.clone()),
+                    owner_identity: Some(S3UserIdentity {
+                        principal_id: Some(self.account_id.clone()),
+                    }),
+                    arn: Some(format!("arn:aws:s3:::{}", s3_request.bucket)),
+                },
+                object: S3Object {
+                    // Notifications encode spaces in keys as `+`
+                    key: Some(encode_key(key).replace("%20", "+")),
+                    size: object.map(|object| object.size as i64),
+                    url_decoded_key: None,
+                    version_id: None,
+                    e_tag: object.map(|object| object.etag.trim_matches('"').to_string()),
+                    sequencer: Some(format!(
+                        "{:016X}",
+                        Utc::now().timestamp_nanos_opt().unwrap_or_default()
+                    )),
+                },
+            },
+        };
+        trace!("Notification: {:#?}", record);
+
+        if let Err(e) = self.notification_sender.send(record) {
+            error!("Failed to send notification for: {}: {}", key, e);
+        }
+    }
+
+    fn get_file_location(&self, bucket_name: &str, file_id: &Uuid) -> PathBuf {
+        self.location
+            .join(bucket_name)
+            .join(OBJECTS_DIRECTORY)
+            .join(file_id.to_string())
+    }
+
+    fn save_bucket(&self, bucket: &Bucket) -> anyhow::Result<()> {
+        let directory = self.location.join(&bucket.name);
+        fs::create_dir_all(&directory)?;
+        fs::write(directory.join(INDEX_FILE), serde_json::to_string(bucket)?)?;
+
+        Ok(())
+    }
+
+    /// Files left by uploads that weren't completed before the store last stopped
+    fn remove_unused_files(&self, bucket: &Bucket) {
+        let file_ids = get_file_ids(bucket);
+        let directory = self.location.join(&bucket.name).join(OBJECTS_DIRECTORY);
+        let Ok(entries) = fs::read_dir(directory) else {
+            return;
+        };
+
+        for entry in entries.flatten() {
+            let is_used = entry
+                .file_name()
+                .to_str()
+                .and_then(|name| Uuid::parse_str(name).ok())
+                .is_some_and(|file_id| file_ids.contains(&file_id));
+            if !is_used {
+                debug!("Removing unused file: {:?}", entry.path());
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+    }
+}
+
+fn get_file_ids(bucket: &Bucket) -> HashSet<Uuid> {
+    bucket
+        .objects
+        .values()
+        .map(|object| object.file_id)
+        .chain(
+            bucket
+                .uploads
+                .values()
+                .flat_map(|upload| upload.parts.values().map(|part| part.file_id)),
+        )
+        .collect()
+}
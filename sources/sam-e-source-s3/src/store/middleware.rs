@@ -0,0 +1,254 @@
+use crate::store::{
+    data::{ObjectStore, S3Action, S3Request},
+    response::S3Error,
+    utils::{is_aws_chunked, AwsChunkedDecoder},
+};
+
+use axum::{
+    async_trait,
+    body::Bytes,
+    extract::{ConnectInfo, FromRequest, Request},
+    http::{header, HeaderMap, Method},
+    response::{IntoResponse, Response},
+};
+use chrono::{NaiveDateTime, Utc};
+use parking_lot::Mutex;
+use percent_encoding::percent_decode_str;
+use std::{collections::HashMap, net::SocketAddr};
+use tracing::{debug, trace};
+use uuid::Uuid;
+
+/// Sub-resources of buckets and objects that aren't emulated. Requests for them are answered as
+/// not implemented rather than mistaken for the bucket or object itself.
+const UNSUPPORTED_SUBRESOURCES: [&str; 24] = [
+    "accelerate",
+    "acl",
+    "analytics",
+    "attributes",
+    "encryption",
+    "intelligent-tiering",
+    "inventory",
+    "legal-hold",
+    "lifecycle",
+    "logging",
+    "metrics",
+    "notification",
+    "object-lock",
+    "ownershipControls",
+    "policy",
+    "policyStatus",
+    "publicAccessBlock",
+    "replication",
+    "requestPayment",
+    "restore",
+    "retention",
+    "tagging",
+    "versioning",
+    "website",
+];
+
+#[async_trait]
+impl FromRequest<ObjectStore> for S3Request {
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &ObjectStore) -> Result<Self, Self::Rejection> {
+        debug!("Parsing the S3 request middleware...");
+        let request_id = Uuid::new_v4().simple().to_string().to_uppercase();
+        let method = req.method().clone();
+        let headers = req.headers().clone();
+        let source_ip_address = req
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(address)| address.ip().to_string())
+            .unwrap_or_else(|| "127.0.0.1".to_string());
+
+        let query = parse_query(req.uri().query().unwrap_or_default());
+        trace!("Query: {:?}", query);
+        if let Err(e) = check_presigned_expiry(&query) {
+            return Err(e.to_response(&request_id));
+        }
+
+        let path = percent_decode_str(req.uri().path())
+            .decode_utf8_lossy()
+            .to_string();
+        let (bucket, key) = get_bucket_and_key(&headers, &path, state);
+        debug!("Bucket: {}, key: {}", bucket, key);
+
+        let action = get_action(&method, &bucket, &key, &query, &headers);
+
+        // Uploads are streamed to disk as they're handled. Other bodies are small, so they're read
+        // up front.
+        if matches!(action, S3Action::PutObject | S3Action::UploadPart) {
+            return Ok(S3Request {
+                action,
+                bucket,
+                key,
+                query,
+                headers,
+                body: Bytes::new(),
+                upload: Mutex::new(Some(req.into_body())),
+                request_id,
+                source_ip_address,
+            });
+        }
+
+        let body = Bytes::from_request(req, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        let body = match is_aws_chunked(&headers) {
+            true => {
+                let mut decoder = AwsChunkedDecoder::default();
+                match decoder.decode(&body).filter(|_| decoder.is_finished()) {
+                    Some(decoded) => Bytes::from(decoded),
+                    None => {
+                        return Err(S3Error::invalid_chunked_body().to_response(&request_id))
+                    }
+                }
+            }
+            false => body,
+        };
+
+        Ok(S3Request {
+            action,
+            bucket,
+            key,
+            query,
+            headers,
+            body,
+            upload: Mutex::new(None),
+            request_id,
+            source_ip_address,
+        })
+    }
+}
+
+/// Query values are form encoded, so `+` is a space
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let decode = |value: &str| {
+                percent_decode_str(&value.replace('+', " "))
+                    .decode_utf8_lossy()
+                    .to_string()
+            };
+            (decode(name), decode(value))
+        })
+        .collect()
+}
+
+/// Presigned URLs stop working once they expire. Signatures aren't checked, as any credentials
+/// are accepted locally.
+fn check_presigned_expiry(query: &HashMap<String, String>) -> Result<(), S3Error> {
+    let (Some(date), Some(expires)) = (query.get("X-Amz-Date"), query.get("X-Amz-Expires")) else {
+        return Ok(());
+    };
+
+    let signed_at = NaiveDateTime::parse_from_str(date, "%Y%m%dT%H%M%SZ")
+        .map_err(|_| S3Error::access_denied("X-Amz-Date must be in the ISO8601 Long Format"))?
+        .and_utc();
+    let expires = expires
+        .parse::<i64>()
+        .map_err(|_| S3Error::access_denied("X-Amz-Expires should be a number"))?;
+
+    if signed_at + chrono::Duration::seconds(expires) < Utc::now() {
+        debug!("Presigned URL expired at: {}", signed_at);
+        return Err(S3Error::access_denied("Request has expired"));
+    }
+
+    Ok(())
+}
+
+/// Buckets are addressed either in the path (`localhost:9000/bucket/key`) or as the first label
+/// of the host (`bucket.localhost:9000/key`), which is only used when the bucket exists
+fn get_bucket_and_key(
+    headers: &HeaderMap,
+    path: &str,
+    object_store: &ObjectStore,
+) -> (String, String) {
+    let host = headers
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok())
+        .unwrap_or_default();
+    let host = host.rsplit_once(':').map_or(host, |(host, _)| host);
+
+    if let Some((bucket, _)) = host.split_once('.') {
+        if object_store.has_bucket(bucket) {
+            return (bucket.to_string(), path.trim_start_matches('/').to_string());
+        }
+    }
+
+    let path = path.strip_prefix('/').unwrap_or(path);
+    let (bucket, key) = path.split_once('/').unwrap_or((path, ""));
+
+    (bucket.to_string(), key.to_string())
+}
+
+fn get_action(
+    method: &Method,
+    bucket: &str,
+    key: &str,
+    query: &HashMap<String, String>,
+    headers: &HeaderMap,
+) -> S3Action {
+    let has = |name: &str| query.contains_key(name);
+    if *method == Method::OPTIONS {
+        return S3Action::Options;
+    }
+    if UNSUPPORTED_SUBRESOURCES.iter().any(|name| has(name)) {
+        return S3Action::Unsupported;
+    }
+
+    if bucket.is_empty() {
+        return match *method {
+            Method::GET => S3Action::ListBuckets,
+            _ => S3Action::Unsupported,
+        };
+    }
+
+    if key.is_empty() {
+        return match *method {
+            Method::PUT if has("cors") => S3Action::PutBucketCors,
+            Method::PUT => S3Action::CreateBucket,
+            Method::HEAD => S3Action::HeadBucket,
+            Method::GET if has("cors") => S3Action::GetBucketCors,
+            Method::GET if has("location") => S3Action::GetBucketLocation,
+            Method::GET if has("uploads") => S3Action::ListMultipartUploads,
+            Method::GET if has("versions") => S3Action::Unsupported,
+            Method::GET
+                if query
+                    .get("list-type")
+                    .is_some_and(|list_type| list_type == "2") =>
+            {
+                S3Action::ListObjectsV2
+            }
+            Method::GET => S3Action::ListObjects,
+            Method::DELETE if has("cors") => S3Action::DeleteBucketCors,
+            Method::DELETE => S3Action::DeleteBucket,
+            Method::POST if has("delete") => S3Action::DeleteObjects,
+            _ => S3Action::Unsupported,
+        };
+    }
+
+    match *method {
+        Method::PUT if has("uploadId") && has("partNumber") => {
+            if headers.contains_key("x-amz-copy-source") {
+                S3Action::Unsupported
+            } else {
+                S3Action::UploadPart
+            }
+        }
+        Method::PUT if headers.contains_key("x-amz-copy-source") => S3Action::CopyObject,
+        Method::PUT => S3Action::PutObject,
+        Method::GET if has("uploadId") => S3Action::ListParts,
+        Method::GET => S3Action::GetObject,
+        Method::HEAD => S3Action::HeadObject,
+        Method::DELETE if has("uploadId") => S3Action::AbortMultipartUpload,
+        Method::DELETE => S3Action::DeleteObject,
+        Method::POST if has("uploads") => S3Action::CreateMultipartUpload,
+        Method::POST if has("uploadId") => S3Action::CompleteMultipartUpload,
+        _ => S3Action::Unsupported,
+    }
+}
@@ -0,0 +1,103 @@
+mod buckets;
+mod multipart;
+mod objects;
+
+use crate::store::{
+    cors,
+    data::{ObjectStore, S3Action, S3Request},
+    response::S3Error,
+};
+
+use axum::{
+    extract::State,
+    http::{HeaderValue, Method},
+    response::Response,
+};
+use tracing::debug;
+
+// The S3 request is parsed to the correct action via the middleware
+pub async fn handler(
+    State(object_store): State<ObjectStore>,
+    method: Method,
+    s3_request: S3Request,
+) -> Response {
+    let action = s3_request.action;
+    debug!("Recognised a {} request", action.get_name());
+
+    let result = match action {
+        S3Action::ListBuckets => Ok(buckets::list_buckets(&object_store)),
+        S3Action::CreateBucket => buckets::create_bucket(&s3_request, &object_store),
+        S3Action::HeadBucket => buckets::head_bucket(&s3_request, &object_store),
+        S3Action::DeleteBucket => buckets::delete_bucket(&s3_request, &object_store),
+        S3Action::GetBucketLocation => buckets::get_bucket_location(&s3_request, &object_store),
+        S3Action::ListObjects => buckets::list_objects(&s3_request, &object_store, false),
+        S3Action::ListObjectsV2 => buckets::list_objects(&s3_request, &object_store, true),
+        S3Action::GetBucketCors => buckets::get_bucket_cors(&s3_request, &object_store),
+        S3Action::PutBucketCors => buckets::put_bucket_cors(&s3_request, &object_store),
+        S3Action::DeleteBucketCors => buckets::delete_bucket_cors(&s3_request, &object_store),
+        S3Action::DeleteObjects => objects::delete_objects(&s3_request, &object_store),
+        S3Action::PutObject => {
+            let upload = s3_request.take_upload();
+            objects::put_object(&s3_request, upload, &object_store).await
+        }
+        S3Action::CopyObject => objects::copy_object(&s3_request, &object_store).await,
+        S3Action::GetObject => objects::get_object(&s3_request, &object_store, true).await,
+        S3Action::HeadObject => objects::get_object(&s3_request, &object_store, false).await,
+        S3Action::DeleteObject => objects::delete_object(&s3_request, &object_store),
+        S3Action::CreateMultipartUpload => {
+            multipart::create_multipart_upload(&s3_request, &object_store)
+        }
+        S3Action::UploadPart => {
+            let upload = s3_request.take_upload();
+            multipart::upload_part(&s3_request, upload, &object_store).await
+        }
+        S3Action::CompleteMultipartUpload => {
+            multipart::complete_multipart_upload(&s3_request, &object_store).await
+        }
+        S3Action::AbortMultipartUpload => {
+            multipart::abort_multipart_upload(&s3_request, &object_store)
+        }
+        S3Action::ListParts => multipart::list_parts(&s3_request, &object_store),
+        S3Action::ListMultipartUploads => {
+            multipart::list_multipart_uploads(&s3_request, &object_store)
+        }
+        S3Action::Options => cors::preflight(&s3_request, &object_store),
+        S3Action::Unsupported => {
+            let mut subresources = s3_request.query.keys().cloned().collect::<Vec<_>>();
+            subresources.sort();
+            Err(S3Error::not_implemented(&format!(
+                "{} /{}/{}?{}",
+                method,
+                s3_request.bucket,
+                s3_request.key,
+                subresources.join("&")
+            )))
+        }
+    };
+
+    let mut response = result.unwrap_or_else(|e| {
+        debug!(
+            "{} failed with: {}: {}",
+            action.get_name(),
+            e.get_code(),
+            e.get_message()
+        );
+        e.to_response(&s3_request.request_id)
+    });
+
+    if let Ok(request_id) = HeaderValue::from_str(&s3_request.request_id) {
+        response
+            .headers_mut()
+            .insert("x-amz-request-id", request_id);
+    }
+    if action != S3Action::Options {
+        cors::add_response_headers(
+            &s3_request,
+            method.as_str(),
+            &object_store,
+            response.headers_mut(),
+        );
+    }
+
+    response
+}
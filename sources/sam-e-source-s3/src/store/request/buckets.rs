@@ -0,0 +1,361 @@
+use crate::store::{
+    data::{ObjectStore, S3Request},
+    response::{empty_response, xml_response, S3Error},
+    utils::{encode_key, to_xml_date},
+    xml::{escape_xml, parse_xml, XmlElement},
+};
+
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use sam_e_types::config::infrastructure::s3::CorsRule;
+use std::ops::Bound;
+use tracing::{debug, info};
+
+/// The most keys S3 returns in a listing, whatever the request asks for
+const MAX_KEYS: usize = 1000;
+
+pub fn list_buckets(object_store: &ObjectStore) -> Response {
+    let buckets = object_store
+        .list_buckets()
+        .iter()
+        .map(|(name, created)| {
+            format!(
+                "<Bucket><Name>{}</Name><CreationDate>{}</CreationDate></Bucket>",
+                escape_xml(name),
+                to_xml_date(created)
+            )
+        })
+        .collect::<String>();
+
+    xml_response(
+        "ListAllMyBucketsResult",
+        format!(
+            "<Owner><ID>{}</ID></Owner><Buckets>{}</Buckets>",
+            object_store.get_account_id(),
+            buckets
+        ),
+    )
+}
+
+pub fn create_bucket(
+    s3_request: &S3Request,
+    object_store: &ObjectStore,
+) -> Result<Response, S3Error> {
+    if !is_valid_bucket_name(&s3_request.bucket) {
+        return Err(S3Error::invalid_bucket_name(&s3_request.bucket));
+    }
+    object_store.create_bucket(&s3_request.bucket)?;
+
+    Ok((
+        StatusCode::OK,
+        [(header::LOCATION, format!("/{}", s3_request.bucket))],
+    )
+        .into_response())
+}
+
+pub fn head_bucket(
+    s3_request: &S3Request,
+    object_store: &ObjectStore,
+) -> Result<Response, S3Error> {
+    object_store.read_bucket(&s3_request.bucket, |_| Ok(()))?;
+
+    Ok((
+        StatusCode::OK,
+        [("x-amz-bucket-region", object_store.get_region().to_string())],
+    )
+        .into_response())
+}
+
+pub fn delete_bucket(
+    s3_request: &S3Request,
+    object_store: &ObjectStore,
+) -> Result<Response, S3Error> {
+    object_store.delete_bucket(&s3_request.bucket)?;
+
+    Ok(empty_response(StatusCode::NO_CONTENT))
+}
+
+pub fn get_bucket_location(
+    s3_request: &S3Request,
+    object_store: &ObjectStore,
+) -> Result<Response, S3Error> {
+    object_store.read_bucket(&s3_request.bucket, |_| Ok(()))?;
+
+    // Buckets in us-east-1 have no location constraint
+    let region = match object_store.get_region() {
+        "us-east-1" => "",
+        region => region,
+    };
+
+    Ok(xml_response("LocationConstraint", escape_xml(region)))
+}
+
+/// Lists the objects in the bucket in key order, with keys sharing a prefix up to the delimiter
+/// grouped into common prefixes. Version 2 listings continue from a token, where version 1
+/// listings continue from a marker - both of which are the last key (or prefix) returned.
+pub fn list_objects(
+    s3_request: &S3Request,
+    object_store: &ObjectStore,
+    is_v2: bool,
+) -> Result<Response, S3Error> {
+    let prefix = s3_request.get_query("prefix").unwrap_or_default();
+    let delimiter = s3_request
+        .get_query("delimiter")
+        .filter(|delimiter| !delimiter.is_empty());
+    let max_keys = match s3_request.get_query("max-keys") {
+        Some(max_keys) => max_keys
+            .parse::<usize>()
+            .map_err(|_| {
+                S3Error::invalid_argument(
+                    "Provided max-keys not an integer or within integer range",
+                )
+            })?
+            .min(MAX_KEYS),
+        None => MAX_KEYS,
+    };
+    let is_url_encoded = s3_request.get_query("encoding-type") == Some("url");
+    let encode = |value: &str| match is_url_encoded {
+        true => encode_key(value),
+        false => value.to_string(),
+    };
+
+    let continuation_token = s3_request.get_query("continuation-token");
+    let start_after = match is_v2 {
+        true => continuation_token.or(s3_request.get_query("start-after")),
+        false => s3_request.get_query("marker"),
+    }
+    .unwrap_or_default();
+
+    let (contents, common_prefixes, last_entry, is_truncated) =
+        object_store.read_bucket(&s3_request.bucket, |bucket| {
+            let mut contents = vec![];
+            let mut common_prefixes: Vec<String> = vec![];
+            let mut last_entry = None;
+            let mut is_truncated = false;
+
+            let start = match start_after.is_empty() {
+                true => Bound::Unbounded,
+                false => Bound::Excluded(start_after.to_string()),
+            };
+            for (key, object) in bucket.objects.range((start, Bound::Unbounded)) {
+                if !key.starts_with(prefix) {
+                    continue;
+                }
+
+                // Listings continuing from a common prefix skip the rest of its keys
+                if delimiter.is_some_and(|delimiter| start_after.ends_with(delimiter))
+                    && key.starts_with(start_after)
+                {
+                    continue;
+                }
+
+                let common_prefix = delimiter.and_then(|delimiter| {
+                    key[prefix.len()..]
+                        .find(delimiter)
+                        .map(|index| key[..prefix.len() + index + delimiter.len()].to_string())
+                });
+                if common_prefix.is_some() && common_prefixes.last() == common_prefix.as_ref() {
+                    continue;
+                }
+
+                if contents.len() + common_prefixes.len() >= max_keys {
+                    is_truncated = true;
+                    break;
+                }
+
+                match common_prefix {
+                    Some(common_prefix) => {
+                        last_entry = Some(common_prefix.clone());
+                        common_prefixes.push(common_prefix);
+                    }
+                    None => {
+                        last_entry = Some(key.clone());
+                        contents.push((key.clone(), object.clone()));
+                    }
+                }
+            }
+
+            Ok((contents, common_prefixes, last_entry, is_truncated))
+        })?;
+    debug!(
+        "Listing {} objects and {} common prefixes",
+        contents.len(),
+        common_prefixes.len()
+    );
+
+    let mut xml = format!(
+        "<Name>{}</Name><Prefix>{}</Prefix><MaxKeys>{}</MaxKeys><IsTruncated>{}</IsTruncated>",
+        escape_xml(&s3_request.bucket),
+        escape_xml(&encode(prefix)),
+        max_keys,
+        is_truncated
+    );
+    if let Some(delimiter) = delimiter {
+        xml.push_str(&format!(
+            "<Delimiter>{}</Delimiter>",
+            escape_xml(&encode(delimiter))
+        ));
+    }
+    if is_url_encoded {
+        xml.push_str("<EncodingType>url</EncodingType>");
+    }
+
+    let next_entry = last_entry.filter(|_| is_truncated);
+    if is_v2 {
+        xml.push_str(&format!(
+            "<KeyCount>{}</KeyCount>",
+            contents.len() + common_prefixes.len()
+        ));
+        if let Some(continuation_token) = continuation_token {
+            xml.push_str(&format!(
+                "<ContinuationToken>{}</ContinuationToken>",
+                escape_xml(continuation_token)
+            ));
+        }
+        if let Some(start_after) = s3_request.get_query("start-after") {
+            xml.push_str(&format!(
+                "<StartAfter>{}</StartAfter>",
+                escape_xml(&encode(start_after))
+            ));
+        }
+        if let Some(next_entry) = next_entry {
+            xml.push_str(&format!(
+                "<NextContinuationToken>{}</NextContinuationToken>",
+                escape_xml(&next_entry)
+            ));
+        }
+    } else {
+        xml.push_str(&format!(
+            "<Marker>{}</Marker>",
+            escape_xml(&encode(start_after))
+        ));
+        if let Some(next_entry) = next_entry {
+            xml.push_str(&format!(
+                "<NextMarker>{}</NextMarker>",
+                escape_xml(&encode(&next_entry))
+            ));
+        }
+    }
+
+    for (key, object) in contents {
+        xml.push_str(&format!(
+            "<Contents><Key>{}</Key><LastModified>{}</LastModified><ETag>{}</ETag><Size>{}</Size><StorageClass>STANDARD</StorageClass></Contents>",
+            escape_xml(&encode(&key)),
+            to_xml_date(&object.last_modified),
+            escape_xml(&object.etag),
+            object.size
+        ));
+    }
+    for common_prefix in common_prefixes {
+        xml.push_str(&format!(
+            "<CommonPrefixes><Prefix>{}</Prefix></CommonPrefixes>",
+            escape_xml(&encode(&common_prefix))
+        ));
+    }
+
+    Ok(xml_response("ListBucketResult", xml))
+}
+
+pub fn get_bucket_cors(
+    s3_request: &S3Request,
+    object_store: &ObjectStore,
+) -> Result<Response, S3Error> {
+    let cors_rules =
+        object_store.read_bucket(&s3_request.bucket, |bucket| Ok(bucket.cors_rules.clone()))?;
+    if cors_rules.is_empty() {
+        return Err(S3Error::no_such_cors_configuration(&s3_request.bucket));
+    }
+
+    let to_elements = |name: &str, values: &[String]| {
+        values
+            .iter()
+            .map(|value| format!("<{}>{}</{}>", name, escape_xml(value), name))
+            .collect::<String>()
+    };
+    let rules = cors_rules
+        .iter()
+        .map(|rule| {
+            format!(
+                "<CORSRule>{}{}{}{}{}</CORSRule>",
+                to_elements("AllowedHeader", &rule.allowed_headers),
+                to_elements("AllowedMethod", &rule.allowed_methods),
+                to_elements("AllowedOrigin", &rule.allowed_origins),
+                to_elements("ExposeHeader", &rule.exposed_headers),
+                rule.max_age
+                    .map(|max_age| format!("<MaxAgeSeconds>{}</MaxAgeSeconds>", max_age))
+                    .unwrap_or_default()
+            )
+        })
+        .collect::<String>();
+
+    Ok(xml_response("CORSConfiguration", rules))
+}
+
+pub fn put_bucket_cors(
+    s3_request: &S3Request,
+    object_store: &ObjectStore,
+) -> Result<Response, S3Error> {
+    let configuration = parse_xml(&s3_request.body)?;
+    let to_strings = |rule: &XmlElement, name: &str| {
+        rule.get_children(name)
+            .map(|child| child.text.trim().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    let mut cors_rules = vec![];
+    for rule in configuration.get_children("CORSRule") {
+        let cors_rule = CorsRule {
+            allowed_headers: to_strings(rule, "AllowedHeader"),
+            allowed_methods: to_strings(rule, "AllowedMethod"),
+            allowed_origins: to_strings(rule, "AllowedOrigin"),
+            exposed_headers: to_strings(rule, "ExposeHeader"),
+            max_age: rule
+                .get_child_text("MaxAgeSeconds")
+                .and_then(|max_age| max_age.parse().ok()),
+        };
+        if cors_rule.allowed_methods.is_empty() || cors_rule.allowed_origins.is_empty() {
+            return Err(S3Error::malformed_xml());
+        }
+        cors_rules.push(cors_rule);
+    }
+    if cors_rules.is_empty() {
+        return Err(S3Error::malformed_xml());
+    }
+
+    object_store.update_bucket(&s3_request.bucket, |bucket| {
+        bucket.cors_rules = cors_rules;
+        Ok(())
+    })?;
+    info!("Updated the CORS rules of bucket: {}", s3_request.bucket);
+
+    Ok(empty_response(StatusCode::OK))
+}
+
+pub fn delete_bucket_cors(
+    s3_request: &S3Request,
+    object_store: &ObjectStore,
+) -> Result<Response, S3Error> {
+    object_store.update_bucket(&s3_request.bucket, |bucket| {
+        bucket.cors_rules.clear();
+        Ok(())
+    })?;
+
+    Ok(empty_response(StatusCode::NO_CONTENT))
+}
+
+/// Bucket names are 3 to 63 lowercase letters, numbers, dots and hyphens, starting and ending with
+/// a letter or number
+fn is_valid_bucket_name(bucket: &str) -> bool {
+    let is_alphanumeric =
+        |character: char| character.is_ascii_lowercase() || character.is_ascii_digit();
+
+    (3..=63).contains(&bucket.len())
+        && bucket
+            .chars()
+            .all(|character| is_alphanumeric(character) || character == '.' || character == '-')
+        && bucket.starts_with(is_alphanumeric)
+        && bucket.ends_with(is_alphanumeric)
+        && !bucket.contains("..")
+}
@@ -0,0 +1,336 @@
+use crate::store::{
+    data::{MultipartUpload, Object, ObjectStore, Part, S3Request},
+    request::objects::{get_object_headers, insert_object, write_upload},
+    response::{empty_response, xml_response, S3Error},
+    utils::{md5_digest, to_etag, to_hex, to_xml_date},
+    xml::{escape_xml, parse_xml},
+};
+
+use axum::{
+    body::Body,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use std::collections::BTreeMap;
+use tokio::{fs::File, io::AsyncWriteExt};
+use tracing::{debug, info};
+use uuid::Uuid;
+
+/// Every part but the last has to be at least 5MiB
+const MIN_PART_SIZE: u64 = 5 * 1024 * 1024;
+const MAX_PART_NUMBER: u32 = 10000;
+const MAX_PARTS: usize = 1000;
+
+pub fn create_multipart_upload(
+    s3_request: &S3Request,
+    object_store: &ObjectStore,
+) -> Result<Response, S3Error> {
+    let upload_id = Uuid::new_v4().simple().to_string();
+    let upload = MultipartUpload {
+        key: s3_request.key.clone(),
+        initiated: Utc::now(),
+        headers: get_object_headers(&s3_request.headers),
+        parts: BTreeMap::new(),
+    };
+
+    object_store.update_bucket(&s3_request.bucket, |bucket| {
+        bucket.uploads.insert(upload_id.clone(), upload);
+        Ok(())
+    })?;
+    debug!(
+        "Created multipart upload: {} for: {}",
+        upload_id, s3_request.key
+    );
+
+    Ok(xml_response(
+        "InitiateMultipartUploadResult",
+        format!(
+            "<Bucket>{}</Bucket><Key>{}</Key><UploadId>{}</UploadId>",
+            escape_xml(&s3_request.bucket),
+            escape_xml(&s3_request.key),
+            upload_id
+        ),
+    ))
+}
+
+pub async fn upload_part(
+    s3_request: &S3Request,
+    upload: Body,
+    object_store: &ObjectStore,
+) -> Result<Response, S3Error> {
+    let upload_id = get_upload_id(s3_request);
+    let part_number = s3_request
+        .get_query("partNumber")
+        .and_then(|part_number| part_number.parse::<u32>().ok())
+        .filter(|part_number| (1..=MAX_PART_NUMBER).contains(part_number))
+        .ok_or_else(|| {
+            S3Error::invalid_argument(
+                "Part number must be an integer between 1 and 10000, inclusive",
+            )
+        })?;
+    get_upload(s3_request, object_store, upload_id)?;
+
+    let uploaded_file = write_upload(s3_request, upload, object_store).await?;
+    let file_id = uploaded_file.file_id;
+    let part = Part {
+        file_id,
+        size: uploaded_file.size,
+        etag: to_etag(&uploaded_file.md5),
+        md5: uploaded_file.md5,
+        last_modified: Utc::now(),
+    };
+    let etag = part.etag.clone();
+
+    let result = object_store.update_bucket(&s3_request.bucket, |bucket| {
+        let upload = bucket
+            .uploads
+            .get_mut(upload_id)
+            .ok_or_else(|| S3Error::no_such_upload(upload_id))?;
+        upload.parts.insert(part_number, part);
+        Ok(())
+    });
+    if let Err(e) = result {
+        object_store.remove_file(&s3_request.bucket, &file_id);
+        return Err(e);
+    }
+    debug!("Uploaded part: {} of upload: {}", part_number, upload_id);
+
+    Ok((StatusCode::OK, [(header::ETAG, etag)]).into_response())
+}
+
+/// Joins the listed parts into the object. The object's ETag is the MD5 of the parts' MD5s with
+/// the number of parts, as in S3.
+pub async fn complete_multipart_upload(
+    s3_request: &S3Request,
+    object_store: &ObjectStore,
+) -> Result<Response, S3Error> {
+    let upload_id = get_upload_id(s3_request);
+    let upload = get_upload(s3_request, object_store, upload_id)?;
+
+    let completion = parse_xml(&s3_request.body)?;
+    let mut parts: Vec<&Part> = vec![];
+    let mut last_part_number = 0;
+    for completed_part in completion.get_children("Part") {
+        let part_number = completed_part
+            .get_child_text("PartNumber")
+            .and_then(|part_number| part_number.parse::<u32>().ok())
+            .ok_or_else(S3Error::malformed_xml)?;
+        if part_number <= last_part_number {
+            return Err(S3Error::invalid_part_order());
+        }
+        last_part_number = part_number;
+
+        let part = upload
+            .parts
+            .get(&part_number)
+            .ok_or_else(S3Error::invalid_part)?;
+        let etag = completed_part.get_child_text("ETag").unwrap_or_default();
+        if etag.trim_matches('"') != part.etag.trim_matches('"') {
+            return Err(S3Error::invalid_part());
+        }
+        parts.push(part);
+    }
+    if parts.is_empty() {
+        return Err(S3Error::malformed_xml());
+    }
+    if parts[..parts.len() - 1]
+        .iter()
+        .any(|part| part.size < MIN_PART_SIZE)
+    {
+        return Err(S3Error::entity_too_small());
+    }
+
+    let (file_id, file) = object_store.create_file(&s3_request.bucket).await?;
+    if let Err(e) = join_parts(s3_request, object_store, &parts, file).await {
+        object_store.remove_file(&s3_request.bucket, &file_id);
+        return Err(e);
+    }
+    let digests = parts
+        .iter()
+        .flat_map(|part| part.md5.clone())
+        .collect::<Vec<_>>();
+    let object = Object {
+        file_id,
+        size: parts.iter().map(|part| part.size).sum(),
+        etag: format!("\"{}-{}\"", to_hex(&md5_digest(&digests)), parts.len()),
+        last_modified: Utc::now(),
+        headers: upload.headers.clone(),
+    };
+
+    // The upload's parts are removed with it
+    let removed = object_store.update_bucket(&s3_request.bucket, |bucket| {
+        Ok(bucket.uploads.remove(upload_id))
+    })?;
+    if removed.is_none() {
+        object_store.remove_file(&s3_request.bucket, &file_id);
+        return Err(S3Error::no_such_upload(upload_id));
+    }
+    insert_object(s3_request, object_store, &s3_request.key, object.clone())?;
+    info!(
+        "Completed multipart upload of: {} in bucket: {}",
+        s3_request.key, s3_request.bucket
+    );
+    object_store.notify(
+        s3_request,
+        &s3_request.key,
+        "ObjectCreated:CompleteMultipartUpload",
+        Some(&object),
+    );
+
+    let host = s3_request.get_header("host").unwrap_or("localhost:9000");
+    Ok(xml_response(
+        "CompleteMultipartUploadResult",
+        format!(
+            "<Location>http://{}/{}/{}</Location><Bucket>{}</Bucket><Key>{}</Key><ETag>{}</ETag>",
+            escape_xml(host),
+            escape_xml(&s3_request.bucket),
+            escape_xml(&s3_request.key),
+            escape_xml(&s3_request.bucket),
+            escape_xml(&s3_request.key),
+            escape_xml(&object.etag)
+        ),
+    ))
+}
+
+pub fn abort_multipart_upload(
+    s3_request: &S3Request,
+    object_store: &ObjectStore,
+) -> Result<Response, S3Error> {
+    let upload_id = get_upload_id(s3_request);
+    get_upload(s3_request, object_store, upload_id)?;
+
+    object_store.update_bucket(&s3_request.bucket, |bucket| {
+        bucket
+            .uploads
+            .remove(upload_id)
+            .ok_or_else(|| S3Error::no_such_upload(upload_id))
+    })?;
+    debug!("Aborted multipart upload: {}", upload_id);
+
+    Ok(empty_response(StatusCode::NO_CONTENT))
+}
+
+pub fn list_parts(s3_request: &S3Request, object_store: &ObjectStore) -> Result<Response, S3Error> {
+    let upload_id = get_upload_id(s3_request);
+    let upload = get_upload(s3_request, object_store, upload_id)?;
+
+    let part_number_marker = s3_request
+        .get_query("part-number-marker")
+        .and_then(|marker| marker.parse::<u32>().ok())
+        .unwrap_or_default();
+    let max_parts = s3_request
+        .get_query("max-parts")
+        .and_then(|max_parts| max_parts.parse::<usize>().ok())
+        .unwrap_or(MAX_PARTS)
+        .min(MAX_PARTS);
+
+    let remaining_parts = upload
+        .parts
+        .range(part_number_marker + 1..)
+        .collect::<Vec<_>>();
+    let is_truncated = remaining_parts.len() > max_parts;
+    let parts = &remaining_parts[..remaining_parts.len().min(max_parts)];
+
+    let mut xml = format!(
+        "<Bucket>{}</Bucket><Key>{}</Key><UploadId>{}</UploadId><PartNumberMarker>{}</PartNumberMarker><MaxParts>{}</MaxParts><IsTruncated>{}</IsTruncated><StorageClass>STANDARD</StorageClass>",
+        escape_xml(&s3_request.bucket),
+        escape_xml(&s3_request.key),
+        escape_xml(upload_id),
+        part_number_marker,
+        max_parts,
+        is_truncated
+    );
+    if let Some((part_number, _)) = parts.last().filter(|_| is_truncated) {
+        xml.push_str(&format!(
+            "<NextPartNumberMarker>{}</NextPartNumberMarker>",
+            part_number
+        ));
+    }
+    for (part_number, part) in parts {
+        xml.push_str(&format!(
+            "<Part><PartNumber>{}</PartNumber><LastModified>{}</LastModified><ETag>{}</ETag><Size>{}</Size></Part>",
+            part_number,
+            to_xml_date(&part.last_modified),
+            escape_xml(&part.etag),
+            part.size
+        ));
+    }
+
+    Ok(xml_response("ListPartsResult", xml))
+}
+
+pub fn list_multipart_uploads(
+    s3_request: &S3Request,
+    object_store: &ObjectStore,
+) -> Result<Response, S3Error> {
+    let prefix = s3_request.get_query("prefix").unwrap_or_default();
+    let mut uploads = object_store.read_bucket(&s3_request.bucket, |bucket| {
+        Ok(bucket
+            .uploads
+            .iter()
+            .filter(|(_, upload)| upload.key.starts_with(prefix))
+            .map(|(upload_id, upload)| (upload_id.clone(), upload.key.clone(), upload.initiated))
+            .collect::<Vec<_>>())
+    })?;
+    uploads.sort_by(|(_, key, initiated), (_, other_key, other_initiated)| {
+        key.cmp(other_key).then(initiated.cmp(other_initiated))
+    });
+
+    let mut xml = format!(
+        "<Bucket>{}</Bucket><KeyMarker></KeyMarker><UploadIdMarker></UploadIdMarker><Prefix>{}</Prefix><MaxUploads>{}</MaxUploads><IsTruncated>false</IsTruncated>",
+        escape_xml(&s3_request.bucket),
+        escape_xml(prefix),
+        MAX_PARTS
+    );
+    for (upload_id, key, initiated) in uploads {
+        xml.push_str(&format!(
+            "<Upload><Key>{}</Key><UploadId>{}</UploadId><StorageClass>STANDARD</StorageClass><Initiated>{}</Initiated></Upload>",
+            escape_xml(&key),
+            escape_xml(&upload_id),
+            to_xml_date(&initiated)
+        ));
+    }
+
+    Ok(xml_response("ListMultipartUploadsResult", xml))
+}
+
+/// Appends each part's file to the object's file in turn, so the object is never held in memory
+async fn join_parts(
+    s3_request: &S3Request,
+    object_store: &ObjectStore,
+    parts: &[&Part],
+    mut file: File,
+) -> Result<(), S3Error> {
+    for part in parts {
+        let mut part_file = object_store
+            .open_file(&s3_request.bucket, &part.file_id)
+            .await?;
+        tokio::io::copy(&mut part_file, &mut file)
+            .await
+            .map_err(|e| S3Error::internal_error(e.to_string()))?;
+    }
+    file.flush()
+        .await
+        .map_err(|e| S3Error::internal_error(e.to_string()))
+}
+
+fn get_upload_id(s3_request: &S3Request) -> &str {
+    s3_request.get_query("uploadId").unwrap_or_default()
+}
+
+/// Finds the upload, which has to be for the request's key
+fn get_upload(
+    s3_request: &S3Request,
+    object_store: &ObjectStore,
+    upload_id: &str,
+) -> Result<MultipartUpload, S3Error> {
+    object_store.read_bucket(&s3_request.bucket, |bucket| {
+        bucket
+            .uploads
+            .get(upload_id)
+            .filter(|upload| upload.key == s3_request.key)
+            .cloned()
+            .ok_or_else(|| S3Error::no_such_upload(upload_id))
+    })
+}
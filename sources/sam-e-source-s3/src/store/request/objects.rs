@@ -0,0 +1,497 @@
+use crate::store::{
+    data::{Object, ObjectStore, S3Request},
+    response::{empty_response, xml_response, S3Error},
+    utils::{is_aws_chunked, to_etag, to_http_date, to_xml_date, AwsChunkedDecoder},
+    xml::{escape_xml, parse_xml},
+};
+
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use md5::{Digest, Md5};
+use percent_encoding::percent_decode_str;
+use std::{collections::BTreeMap, io::SeekFrom};
+use tokio::{
+    fs::File,
+    io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt},
+};
+use tokio_util::io::ReaderStream;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+/// Headers kept with an object and returned when it's read
+const OBJECT_HEADERS: [&str; 6] = [
+    "cache-control",
+    "content-disposition",
+    "content-encoding",
+    "content-language",
+    "content-type",
+    "expires",
+];
+
+/// Query parameters that override the headers an object is returned with, such as in presigned
+/// URLs for downloads
+const RESPONSE_OVERRIDES: [(&str, &str); 6] = [
+    ("response-cache-control", "cache-control"),
+    ("response-content-disposition", "content-disposition"),
+    ("response-content-encoding", "content-encoding"),
+    ("response-content-language", "content-language"),
+    ("response-content-type", "content-type"),
+    ("response-expires", "expires"),
+];
+
+const DEFAULT_CONTENT_TYPE: &str = "binary/octet-stream";
+
+/// An upload once it's been written to a file
+pub struct UploadedFile {
+    pub file_id: Uuid,
+    pub size: u64,
+    pub md5: Vec<u8>,
+}
+
+pub async fn put_object(
+    s3_request: &S3Request,
+    upload: Body,
+    object_store: &ObjectStore,
+) -> Result<Response, S3Error> {
+    object_store.read_bucket(&s3_request.bucket, |_| Ok(()))?;
+    let uploaded_file = write_upload(s3_request, upload, object_store).await?;
+
+    let object = Object {
+        file_id: uploaded_file.file_id,
+        size: uploaded_file.size,
+        etag: to_etag(&uploaded_file.md5),
+        last_modified: Utc::now(),
+        headers: get_object_headers(&s3_request.headers),
+    };
+
+    insert_object(s3_request, object_store, &s3_request.key, object.clone())?;
+    info!(
+        "Put object: {} in bucket: {}",
+        s3_request.key, s3_request.bucket
+    );
+    object_store.notify(
+        s3_request,
+        &s3_request.key,
+        "ObjectCreated:Put",
+        Some(&object),
+    );
+
+    Ok((StatusCode::OK, [(header::ETAG, object.etag)]).into_response())
+}
+
+/// Copies the object from `x-amz-copy-source`, keeping its headers unless they're replaced by the
+/// request's
+pub async fn copy_object(
+    s3_request: &S3Request,
+    object_store: &ObjectStore,
+) -> Result<Response, S3Error> {
+    let (source_bucket, source_key) = get_copy_source(s3_request)?;
+    let source_object = object_store.get_object(&source_bucket, &source_key)?;
+
+    let replace_headers = s3_request.get_header("x-amz-metadata-directive") == Some("REPLACE");
+    if source_bucket == s3_request.bucket && source_key == s3_request.key && !replace_headers {
+        return Err(S3Error::invalid_request(
+            "This copy request is illegal because it is trying to copy an object to itself without changing the object's metadata, storage class, website redirect location or encryption attributes.",
+        ));
+    }
+    object_store.read_bucket(&s3_request.bucket, |_| Ok(()))?;
+
+    let file_id = object_store
+        .copy_file(&source_bucket, &source_object.file_id, &s3_request.bucket)
+        .await?;
+    let object = Object {
+        file_id,
+        size: source_object.size,
+        etag: source_object.etag.clone(),
+        last_modified: Utc::now(),
+        headers: match replace_headers {
+            true => get_object_headers(&s3_request.headers),
+            false => source_object.headers.clone(),
+        },
+    };
+
+    insert_object(s3_request, object_store, &s3_request.key, object.clone())?;
+    info!(
+        "Copied object: {}/{} to: {}/{}",
+        source_bucket, source_key, s3_request.bucket, s3_request.key
+    );
+    object_store.notify(
+        s3_request,
+        &s3_request.key,
+        "ObjectCreated:Copy",
+        Some(&object),
+    );
+
+    Ok(xml_response(
+        "CopyObjectResult",
+        format!(
+            "<LastModified>{}</LastModified><ETag>{}</ETag>",
+            to_xml_date(&object.last_modified),
+            escape_xml(&object.etag)
+        ),
+    ))
+}
+
+/// Returns the object, or just its headers for `HEAD` requests. Single byte ranges and the
+/// conditional headers are supported.
+pub async fn get_object(
+    s3_request: &S3Request,
+    object_store: &ObjectStore,
+    with_body: bool,
+) -> Result<Response, S3Error> {
+    let object = object_store.get_object(&s3_request.bucket, &s3_request.key)?;
+    if let Some(status) = check_conditions(&s3_request.headers, &object)? {
+        return Ok(empty_response(status));
+    }
+
+    let range = match s3_request.get_header("range") {
+        Some(range) => parse_range(range, object.size)?,
+        None => None,
+    };
+
+    let mut response = Response::builder()
+        .header(header::ETAG, &object.etag)
+        .header(header::LAST_MODIFIED, to_http_date(&object.last_modified))
+        .header(header::ACCEPT_RANGES, "bytes");
+    for (name, value) in &object.headers {
+        response = response.header(name, value);
+    }
+    for (parameter, name) in RESPONSE_OVERRIDES {
+        if let Some(value) = s3_request.get_query(parameter) {
+            response = response.header(name, value);
+        }
+    }
+
+    let (status, start, end) = match range {
+        Some((start, end)) => {
+            response = response.header(
+                header::CONTENT_RANGE,
+                format!("bytes {}-{}/{}", start, end, object.size),
+            );
+            (StatusCode::PARTIAL_CONTENT, start, end + 1)
+        }
+        None => (StatusCode::OK, 0, object.size),
+    };
+    response = response
+        .status(status)
+        .header(header::CONTENT_LENGTH, end - start);
+
+    // The object is streamed from its file, so it's never held in memory
+    let body = match with_body {
+        true => {
+            let mut file = object_store
+                .open_file(&s3_request.bucket, &object.file_id)
+                .await?;
+            file.seek(SeekFrom::Start(start))
+                .await
+                .map_err(|e| S3Error::internal_error(e.to_string()))?;
+            Body::from_stream(ReaderStream::new(file.take(end - start)))
+        }
+        false => Body::empty(),
+    };
+
+    response
+        .body(body)
+        .map_err(|e| S3Error::internal_error(e.to_string()))
+}
+
+pub fn delete_object(
+    s3_request: &S3Request,
+    object_store: &ObjectStore,
+) -> Result<Response, S3Error> {
+    let deleted = object_store.update_bucket(&s3_request.bucket, |bucket| {
+        Ok(bucket.objects.remove(&s3_request.key))
+    })?;
+
+    // Deleting an object that doesn't exist still succeeds. Removal notifications don't describe
+    // the object, as in S3.
+    if deleted.is_some() {
+        info!(
+            "Deleted object: {} from bucket: {}",
+            s3_request.key, s3_request.bucket
+        );
+        object_store.notify(s3_request, &s3_request.key, "ObjectRemoved:Delete", None);
+    }
+
+    Ok(empty_response(StatusCode::NO_CONTENT))
+}
+
+pub fn delete_objects(
+    s3_request: &S3Request,
+    object_store: &ObjectStore,
+) -> Result<Response, S3Error> {
+    let delete = parse_xml(&s3_request.body)?;
+    let is_quiet = delete.get_child_text("Quiet") == Some("true");
+    let keys = delete
+        .get_children("Object")
+        .filter_map(|object| object.get_child("Key").map(|key| key.text.clone()))
+        .collect::<Vec<_>>();
+    if keys.is_empty() {
+        return Err(S3Error::malformed_xml());
+    }
+
+    let deleted = object_store.update_bucket(&s3_request.bucket, |bucket| {
+        Ok(keys
+            .iter()
+            .map(|key| (key.clone(), bucket.objects.remove(key)))
+            .collect::<Vec<_>>())
+    })?;
+    debug!(
+        "Deleted {} objects from bucket: {}",
+        deleted.len(),
+        s3_request.bucket
+    );
+
+    let mut xml = String::new();
+    for (key, object) in deleted {
+        if object.is_some() {
+            object_store.notify(s3_request, &key, "ObjectRemoved:Delete", None);
+        }
+        if !is_quiet {
+            xml.push_str(&format!(
+                "<Deleted><Key>{}</Key></Deleted>",
+                escape_xml(&key)
+            ));
+        }
+    }
+
+    Ok(xml_response("DeleteResult", xml))
+}
+
+/// Adds the object to the bucket, replacing any object with the key. The object's file is removed
+/// if it can't be added, such as when the bucket has been deleted since the upload started.
+pub fn insert_object(
+    s3_request: &S3Request,
+    object_store: &ObjectStore,
+    key: &str,
+    object: Object,
+) -> Result<(), S3Error> {
+    let file_id = object.file_id;
+    let result = object_store.update_bucket(&s3_request.bucket, |bucket| {
+        bucket.objects.insert(key.to_string(), object);
+        Ok(())
+    });
+    if result.is_err() {
+        object_store.remove_file(&s3_request.bucket, &file_id);
+    }
+
+    result
+}
+
+/// Streams the upload to a new file in the bucket, decoding `aws-chunked` bodies and working out
+/// the MD5 as it's written. The file is removed if the upload fails or doesn't match its
+/// `Content-MD5` header.
+pub async fn write_upload(
+    s3_request: &S3Request,
+    upload: Body,
+    object_store: &ObjectStore,
+) -> Result<UploadedFile, S3Error> {
+    let (file_id, file) = object_store.create_file(&s3_request.bucket).await?;
+    let written = write_body(upload, file, is_aws_chunked(&s3_request.headers))
+        .await
+        .and_then(|(size, md5)| {
+            check_content_md5(&s3_request.headers, &md5)?;
+            Ok((size, md5))
+        });
+
+    match written {
+        Ok((size, md5)) => Ok(UploadedFile { file_id, size, md5 }),
+        Err(e) => {
+            object_store.remove_file(&s3_request.bucket, &file_id);
+            Err(e)
+        }
+    }
+}
+
+async fn write_body(
+    body: Body,
+    mut file: File,
+    is_aws_chunked: bool,
+) -> Result<(u64, Vec<u8>), S3Error> {
+    let mut decoder = is_aws_chunked.then(AwsChunkedDecoder::default);
+    let mut hasher = Md5::new();
+    let mut size = 0;
+
+    let mut stream = body.into_data_stream();
+    while let Some(data) = stream.next().await {
+        let data = data.map_err(|e| S3Error::invalid_request(e.to_string()))?;
+        let data = match &mut decoder {
+            Some(decoder) => decoder
+                .decode(&data)
+                .ok_or_else(S3Error::invalid_chunked_body)?
+                .into(),
+            None => data,
+        };
+
+        hasher.update(&data);
+        size += data.len() as u64;
+        file.write_all(&data)
+            .await
+            .map_err(|e| S3Error::internal_error(e.to_string()))?;
+    }
+    if decoder.is_some_and(|decoder| !decoder.is_finished()) {
+        return Err(S3Error::invalid_chunked_body());
+    }
+    file.flush()
+        .await
+        .map_err(|e| S3Error::internal_error(e.to_string()))?;
+
+    Ok((size, hasher.finalize().to_vec()))
+}
+
+/// The headers stored with an object, which are its content headers and user metadata
+pub fn get_object_headers(headers: &HeaderMap) -> BTreeMap<String, String> {
+    let mut object_headers = headers
+        .iter()
+        .filter(|(name, _)| {
+            OBJECT_HEADERS.contains(&name.as_str()) || name.as_str().starts_with("x-amz-meta-")
+        })
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect::<BTreeMap<_, _>>();
+
+    // The chunked encoding is only for the upload
+    if let Some(content_encoding) = object_headers.remove("content-encoding") {
+        let content_encoding = content_encoding
+            .split(',')
+            .map(|encoding| encoding.trim())
+            .filter(|encoding| *encoding != "aws-chunked")
+            .collect::<Vec<_>>()
+            .join(", ");
+        if !content_encoding.is_empty() {
+            object_headers.insert("content-encoding".to_string(), content_encoding);
+        }
+    }
+    object_headers
+        .entry("content-type".to_string())
+        .or_insert_with(|| DEFAULT_CONTENT_TYPE.to_string());
+
+    object_headers
+}
+
+/// Checks the body against the `Content-MD5` header, if it was sent
+pub fn check_content_md5(headers: &HeaderMap, digest: &[u8]) -> Result<(), S3Error> {
+    let Some(content_md5) = headers.get("content-md5") else {
+        return Ok(());
+    };
+
+    let expected = content_md5
+        .to_str()
+        .ok()
+        .and_then(|content_md5| STANDARD.decode(content_md5).ok())
+        .ok_or_else(|| S3Error::invalid_argument("The Content-MD5 you specified is not valid."))?;
+    if expected != digest {
+        return Err(S3Error::bad_digest());
+    }
+
+    Ok(())
+}
+
+/// The copy source is the URL encoded bucket and key, optionally with a leading `/` and version
+fn get_copy_source(s3_request: &S3Request) -> Result<(String, String), S3Error> {
+    let copy_source = s3_request
+        .get_header("x-amz-copy-source")
+        .unwrap_or_default();
+    let copy_source = copy_source.split('?').next().unwrap_or_default();
+    let copy_source = percent_decode_str(copy_source).decode_utf8_lossy();
+    let copy_source = copy_source.trim_start_matches('/');
+
+    match copy_source.split_once('/') {
+        Some((bucket, key)) if !bucket.is_empty() && !key.is_empty() => {
+            Ok((bucket.to_string(), key.to_string()))
+        }
+        _ => Err(S3Error::invalid_argument(
+            "Copy Source must mention the source bucket and key: sourcebucket/sourcekey",
+        )),
+    }
+}
+
+/// Checks the `If-Match`, `If-None-Match`, `If-Modified-Since` and `If-Unmodified-Since` headers.
+/// Gives back the status to return instead of the object when one doesn't hold.
+fn check_conditions(headers: &HeaderMap, object: &Object) -> Result<Option<StatusCode>, S3Error> {
+    let get_header =
+        |name: header::HeaderName| headers.get(name).and_then(|value| value.to_str().ok());
+    let matches_etag = |etags: &str| {
+        etags
+            .split(',')
+            .map(|etag| etag.trim())
+            .any(|etag| etag == "*" || etag.trim_matches('"') == object.etag.trim_matches('"'))
+    };
+    let parse_date = |date: &str| {
+        DateTime::parse_from_rfc2822(date)
+            .ok()
+            .map(|date| date.with_timezone(&Utc))
+    };
+    // Dates in headers are only to the second
+    let last_modified = object.last_modified.timestamp();
+
+    if let Some(if_match) = get_header(header::IF_MATCH) {
+        if !matches_etag(if_match) {
+            return Err(S3Error::precondition_failed());
+        }
+    } else if let Some(since) = get_header(header::IF_UNMODIFIED_SINCE).and_then(parse_date) {
+        if last_modified > since.timestamp() {
+            return Err(S3Error::precondition_failed());
+        }
+    }
+
+    if let Some(if_none_match) = get_header(header::IF_NONE_MATCH) {
+        if matches_etag(if_none_match) {
+            return Ok(Some(StatusCode::NOT_MODIFIED));
+        }
+    } else if let Some(since) = get_header(header::IF_MODIFIED_SINCE).and_then(parse_date) {
+        if last_modified <= since.timestamp() {
+            return Ok(Some(StatusCode::NOT_MODIFIED));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Parses a single byte range, such as `bytes=0-99`, `bytes=100-` or `bytes=-100`, into its first
+/// and last byte. As in S3, ranges that can't be parsed are ignored.
+fn parse_range(range: &str, size: u64) -> Result<Option<(u64, u64)>, S3Error> {
+    let Some((start, end)) = range
+        .strip_prefix("bytes=")
+        .filter(|range| !range.contains(','))
+        .and_then(|range| range.split_once('-'))
+    else {
+        return Ok(None);
+    };
+
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix_length) => {
+            let Ok(suffix_length) = suffix_length.parse::<u64>() else {
+                return Ok(None);
+            };
+            if suffix_length == 0 {
+                return Err(S3Error::invalid_range());
+            }
+            (size.saturating_sub(suffix_length), size.saturating_sub(1))
+        }
+        (start, end) => {
+            let Ok(start) = start.parse::<u64>() else {
+                return Ok(None);
+            };
+            let end = match end {
+                "" => size.saturating_sub(1),
+                end => match end.parse::<u64>() {
+                    Ok(end) if end >= start => end.min(size.saturating_sub(1)),
+                    _ => return Ok(None),
+                },
+            };
+            (start, end)
+        }
+    };
+
+    if start >= size {
+        return Err(S3Error::invalid_range());
+    }
+
+    Ok(Some((start, end)))
+}
@@ -0,0 +1,231 @@
+use crate::store::xml::escape_xml;
+
+use axum::{
+    body::Body,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use tracing::trace;
+
+pub const XML_NAMESPACE: &str = "http://s3.amazonaws.com/doc/2006-03-01/";
+
+/// An error as S3 returns it, with the code SDKs use to pick the error type
+#[derive(Debug, Clone)]
+pub struct S3Error {
+    status: StatusCode,
+    code: &'static str,
+    message: String,
+    resource: Option<String>,
+}
+
+impl S3Error {
+    fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code,
+            message: message.into(),
+            resource: None,
+        }
+    }
+
+    fn with_resource(mut self, resource: &str) -> Self {
+        self.resource = Some(resource.to_string());
+        self
+    }
+
+    pub fn get_code(&self) -> &str {
+        self.code
+    }
+
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn no_such_bucket(bucket: &str) -> Self {
+        Self::new(
+            StatusCode::NOT_FOUND,
+            "NoSuchBucket",
+            "The specified bucket does not exist",
+        )
+        .with_resource(bucket)
+    }
+
+    pub fn no_such_key(key: &str) -> Self {
+        Self::new(
+            StatusCode::NOT_FOUND,
+            "NoSuchKey",
+            "The specified key does not exist.",
+        )
+        .with_resource(key)
+    }
+
+    pub fn no_such_upload(upload_id: &str) -> Self {
+        Self::new(
+            StatusCode::NOT_FOUND,
+            "NoSuchUpload",
+            "The specified multipart upload does not exist. The upload ID might be invalid, or the multipart upload might have been aborted or completed.",
+        )
+        .with_resource(upload_id)
+    }
+
+    pub fn no_such_cors_configuration(bucket: &str) -> Self {
+        Self::new(
+            StatusCode::NOT_FOUND,
+            "NoSuchCORSConfiguration",
+            "The CORS configuration does not exist",
+        )
+        .with_resource(bucket)
+    }
+
+    pub fn bucket_already_owned_by_you(bucket: &str) -> Self {
+        Self::new(
+            StatusCode::CONFLICT,
+            "BucketAlreadyOwnedByYou",
+            "Your previous request to create the named bucket succeeded and you already own it.",
+        )
+        .with_resource(bucket)
+    }
+
+    pub fn bucket_not_empty(bucket: &str) -> Self {
+        Self::new(
+            StatusCode::CONFLICT,
+            "BucketNotEmpty",
+            "The bucket you tried to delete is not empty",
+        )
+        .with_resource(bucket)
+    }
+
+    pub fn invalid_bucket_name(bucket: &str) -> Self {
+        Self::new(
+            StatusCode::BAD_REQUEST,
+            "InvalidBucketName",
+            "The specified bucket is not valid.",
+        )
+        .with_resource(bucket)
+    }
+
+    pub fn invalid_range() -> Self {
+        Self::new(
+            StatusCode::RANGE_NOT_SATISFIABLE,
+            "InvalidRange",
+            "The requested range is not satisfiable",
+        )
+    }
+
+    pub fn invalid_part() -> Self {
+        Self::new(
+            StatusCode::BAD_REQUEST,
+            "InvalidPart",
+            "One or more of the specified parts could not be found. The part may not have been uploaded, or the specified entity tag may not match the part's entity tag.",
+        )
+    }
+
+    pub fn invalid_part_order() -> Self {
+        Self::new(
+            StatusCode::BAD_REQUEST,
+            "InvalidPartOrder",
+            "The list of parts was not in ascending order. Parts must be ordered by part number.",
+        )
+    }
+
+    pub fn entity_too_small() -> Self {
+        Self::new(
+            StatusCode::BAD_REQUEST,
+            "EntityTooSmall",
+            "Your proposed upload is smaller than the minimum allowed object size.",
+        )
+    }
+
+    pub fn bad_digest() -> Self {
+        Self::new(
+            StatusCode::BAD_REQUEST,
+            "BadDigest",
+            "The Content-MD5 you specified did not match what we received.",
+        )
+    }
+
+    pub fn malformed_xml() -> Self {
+        Self::new(
+            StatusCode::BAD_REQUEST,
+            "MalformedXML",
+            "The XML you provided was not well-formed or did not validate against our published schema",
+        )
+    }
+
+    pub fn invalid_argument(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "InvalidArgument", message)
+    }
+
+    pub fn invalid_request(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "InvalidRequest", message)
+    }
+
+    pub fn invalid_chunked_body() -> Self {
+        Self::invalid_request("The aws-chunked body could not be decoded")
+    }
+
+    pub fn access_denied(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::FORBIDDEN, "AccessDenied", message)
+    }
+
+    pub fn precondition_failed() -> Self {
+        Self::new(
+            StatusCode::PRECONDITION_FAILED,
+            "PreconditionFailed",
+            "At least one of the pre-conditions you specified did not hold",
+        )
+    }
+
+    pub fn not_implemented(action: &str) -> Self {
+        Self::new(
+            StatusCode::NOT_IMPLEMENTED,
+            "NotImplemented",
+            format!("{} is not supported by the local S3 store", action),
+        )
+    }
+
+    pub fn internal_error(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "InternalError", message)
+    }
+
+    /// Renders the error as the XML body S3 returns, or just the status for `HEAD` requests, which
+    /// have no body
+    pub fn to_response(&self, request_id: &str) -> Response {
+        let xml = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?><Error><Code>{}</Code><Message>{}</Message>{}<RequestId>{}</RequestId></Error>"#,
+            self.code,
+            escape_xml(&self.message),
+            self.resource
+                .as_ref()
+                .map(|resource| format!("<Resource>{}</Resource>", escape_xml(resource)))
+                .unwrap_or_default(),
+            request_id
+        );
+        trace!("Error response: {}", xml);
+
+        (
+            self.status,
+            [(header::CONTENT_TYPE, "application/xml")],
+            xml,
+        )
+            .into_response()
+    }
+}
+
+/// A successful response with an XML body in the S3 namespace
+pub fn xml_response(root: &str, content: String) -> Response {
+    let xml = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?><{} xmlns="{}">{}</{}>"#,
+        root, XML_NAMESPACE, content, root
+    );
+    trace!("Response: {}", xml);
+
+    ([(header::CONTENT_TYPE, "application/xml")], xml).into_response()
+}
+
+pub fn empty_response(status: StatusCode) -> Response {
+    Response::builder()
+        .status(status)
+        .body(Body::empty())
+        .unwrap_or_default()
+}
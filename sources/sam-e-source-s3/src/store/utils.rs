@@ -0,0 +1,135 @@
+use axum::http::HeaderMap;
+use chrono::{DateTime, Utc};
+use md5::{Digest, Md5};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+/// Characters left as they are when keys are URL encoded, as S3 does in listings and notifications
+const KEY_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~')
+    .remove(b'/');
+
+pub fn md5_digest(bytes: &[u8]) -> Vec<u8> {
+    Md5::digest(bytes).to_vec()
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// ETags are the MD5 of the object in quotes
+pub fn to_etag(digest: &[u8]) -> String {
+    format!("\"{}\"", to_hex(digest))
+}
+
+pub fn encode_key(key: &str) -> String {
+    utf8_percent_encode(key, KEY_ENCODE_SET).to_string()
+}
+
+/// Dates in XML bodies, such as `2009-10-12T17:50:30.000Z`
+pub fn to_xml_date(date: &DateTime<Utc>) -> String {
+    date.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()
+}
+
+/// Dates in headers, such as `Mon, 12 Oct 2009 17:50:30 GMT`
+pub fn to_http_date(date: &DateTime<Utc>) -> String {
+    date.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+/// Bodies from SDKs can be `aws-chunked` encoded, either for streamed uploads or when the header
+/// says so
+pub fn is_aws_chunked(headers: &HeaderMap) -> bool {
+    let has_header = |name: &str, value: &str| {
+        headers
+            .get(name)
+            .and_then(|header| header.to_str().ok())
+            .is_some_and(|header| header.contains(value))
+    };
+
+    has_header("x-amz-content-sha256", "STREAMING-")
+        || has_header("content-encoding", "aws-chunked")
+}
+
+#[derive(Debug, Default)]
+enum ChunkState {
+    #[default]
+    Header,
+    Data(usize),
+    DataEnd,
+    Finished,
+}
+
+/// SDKs can stream uploads as `aws-chunked` bodies, where each chunk is prefixed with its size in
+/// hex (and a signature) and trailing headers can follow the last chunk. The body is decoded as it's
+/// received, so only a chunk header that's been split across reads is held on to.
+#[derive(Debug, Default)]
+pub struct AwsChunkedDecoder {
+    buffer: Vec<u8>,
+    state: ChunkState,
+}
+
+impl AwsChunkedDecoder {
+    /// Returns the data in the input without the framing, or nothing if the body isn't framed
+    /// correctly
+    pub fn decode(&mut self, input: &[u8]) -> Option<Vec<u8>> {
+        self.buffer.extend_from_slice(input);
+        let mut decoded = Vec::with_capacity(self.buffer.len());
+        let mut position = 0;
+        loop {
+            let rest = &self.buffer[position..];
+            match self.state {
+                ChunkState::Header => {
+                    let Some(line_end) = rest.windows(2).position(|window| window == b"\r\n")
+                    else {
+                        break;
+                    };
+                    let header = std::str::from_utf8(&rest[..line_end]).ok()?;
+                    let size_hex = header.split(';').next()?.trim();
+                    let size = usize::from_str_radix(size_hex, 16).ok()?;
+                    position += line_end + 2;
+                    self.state = match size {
+                        0 => ChunkState::Finished,
+                        size => ChunkState::Data(size),
+                    };
+                }
+                ChunkState::Data(remaining) => {
+                    let length = rest.len().min(remaining);
+                    if length == 0 {
+                        break;
+                    }
+                    decoded.extend_from_slice(&rest[..length]);
+                    position += length;
+                    self.state = match remaining - length {
+                        0 => ChunkState::DataEnd,
+                        remaining => ChunkState::Data(remaining),
+                    };
+                }
+                ChunkState::DataEnd => {
+                    if rest.len() < 2 {
+                        break;
+                    }
+                    if !rest.starts_with(b"\r\n") {
+                        return None;
+                    }
+                    position += 2;
+                    self.state = ChunkState::Header;
+                }
+                // Trailing headers aren't needed
+                ChunkState::Finished => {
+                    position = self.buffer.len();
+                    break;
+                }
+            }
+        }
+        self.buffer.drain(..position);
+
+        Some(decoded)
+    }
+
+    /// Whether the last chunk has been received
+    pub fn is_finished(&self) -> bool {
+        matches!(self.state, ChunkState::Finished)
+    }
+}
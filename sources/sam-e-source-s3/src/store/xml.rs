@@ -0,0 +1,115 @@
+use crate::store::response::S3Error;
+
+use xmlparser::{ElementEnd, Token, Tokenizer};
+
+/// An element of an XML request body, such as the parts of a multipart upload or the rules of a
+/// CORS configuration. Namespaces and attributes aren't needed by any request so are dropped.
+#[derive(Debug, Default)]
+pub struct XmlElement {
+    pub name: String,
+    pub text: String,
+    pub children: Vec<XmlElement>,
+}
+
+impl XmlElement {
+    pub fn get_child(&self, name: &str) -> Option<&XmlElement> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    pub fn get_children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a XmlElement> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    pub fn get_child_text(&self, name: &str) -> Option<&str> {
+        self.get_child(name).map(|child| child.text.trim())
+    }
+}
+
+/// Parses the body into its root element
+pub fn parse_xml(body: &[u8]) -> Result<XmlElement, S3Error> {
+    let body = std::str::from_utf8(body).map_err(|_| S3Error::malformed_xml())?;
+
+    let mut stack: Vec<XmlElement> = vec![];
+    let mut root = None;
+    for token in Tokenizer::from(body) {
+        match token.map_err(|_| S3Error::malformed_xml())? {
+            Token::ElementStart { local, .. } => stack.push(XmlElement {
+                name: local.as_str().to_string(),
+                ..Default::default()
+            }),
+            Token::ElementEnd {
+                end: ElementEnd::Close(..) | ElementEnd::Empty,
+                ..
+            } => {
+                let element = stack.pop().ok_or_else(S3Error::malformed_xml)?;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => root = Some(element),
+                }
+            }
+            Token::Text { text } => {
+                if let Some(element) = stack.last_mut() {
+                    element.text.push_str(&unescape_xml(text.as_str()));
+                }
+            }
+            Token::Cdata { text, .. } => {
+                if let Some(element) = stack.last_mut() {
+                    element.text.push_str(text.as_str());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    root.ok_or_else(S3Error::malformed_xml)
+}
+
+pub fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn unescape_xml(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..end];
+        let character = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(|decimal| decimal.parse()))
+                .and_then(|code| code.ok())
+                .and_then(char::from_u32),
+        };
+
+        match character {
+            Some(character) => {
+                unescaped.push(character);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+
+    unescaped
+}
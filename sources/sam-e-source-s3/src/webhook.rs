@@ -1,94 +1,276 @@
-use crate::data::ApiState;
+use crate::{data::ApiState, event_bridge};
 
-use aws_lambda_events::s3::S3Event;
+use aws_lambda_events::s3::{S3Event, S3EventRecord};
 use aws_sdk_sqs::Client;
 use axum::{
     extract::{Json, State},
     response::IntoResponse,
 };
-use sam_e_types::config::infrastructure::Infrastructure;
+use percent_encoding::percent_decode_str;
+use reqwest::Client as RequestClient;
+use sam_e_types::invocation::{EventRequest, InvocationBuilder};
+use tokio::{
+    sync::mpsc::UnboundedReceiver,
+    time::{sleep, Duration},
+};
 use tracing::{debug, error, info, trace, warn};
+use uuid::Uuid;
+
+/// How many times the test events look for a queue that hasn't been created yet before giving up
+const QUEUE_RETRIES: u32 = 20;
+const QUEUE_RETRY_INTERVAL: Duration = Duration::from_millis(500);
 
 pub async fn handler(State(api_state): State<ApiState>, body: Json<S3Event>) -> impl IntoResponse {
     info!("Received a request to the S3 webhook");
-    info!("Request body: {:#?}", body);
-
-    let s3_event = body.0;
-
-    // Match S3 event to bucket in infrastructure
-    let bucket_name = s3_event.records[0].s3.bucket.name.clone();
-    let infrastructure = api_state.get_infrastructure();
-
-    if let Some(bucket) = bucket_name {
-        for i in infrastructure.into_iter() {
-            match i {
-                Infrastructure::S3(s3_data) => {
-                    let props = &s3_data.properties;
-                    if props.name == bucket {
-                        let triggers = &props.triggers;
-                        if let Some(triggers) = triggers {
-                            trace!("Triggers: {:#?}", triggers);
-
-                            if let Some(queues) = triggers.get_queues() {
-                                for queue in queues {
-                                    debug!("Detected queue trigger for: {}", queue);
-                                    handle_queue_trigger(
-                                        queue.as_str(),
-                                        &s3_event,
-                                        api_state.get_queue_client(),
-                                    )
-                                    .await;
-                                }
-                            }
-
-                            if let Some(lambdas) = triggers.get_lambdas() {
-                                for lambda in lambdas {
-                                    debug!("Detected lambda trigger for: {}", lambda);
-                                    warn!("Currently unsupported!");
-                                }
-                            }
-                        }
-                    }
-                }
-                _ => continue,
-            }
-            info!("Found infrastructure for bucket: {}", bucket);
-        }
+    trace!("Request body: {:#?}", body);
+
+    // AWS sends a notification for each record, so each is routed to its own bucket's destinations
+    for record in body.0.records {
+        handle_record(&api_state, to_aws_record(record, api_state.get_region())).await;
     }
 
     "s3"
 }
 
+/// Routes a notification from the local S3 store, which already follows the AWS format
+pub async fn handle_notifications(
+    api_state: ApiState,
+    mut receiver: UnboundedReceiver<S3EventRecord>,
+) {
+    while let Some(record) = receiver.recv().await {
+        handle_record(&api_state, record).await;
+    }
+}
+
+/// Sends the record to its bucket's EventBridge bus and the destinations whose filters it matches
+async fn handle_record(api_state: &ApiState, record: S3EventRecord) {
+    let Some(bucket_name) = record.s3.bucket.name.clone() else {
+        warn!("No bucket name found for S3 record. Skipping");
+        return;
+    };
+
+    let Some(bucket) = api_state.get_bucket(&bucket_name) else {
+        debug!("No infrastructure found for bucket: {}", bucket_name);
+        return;
+    };
+    info!("Found infrastructure for bucket: {}", bucket_name);
+
+    let event_name = record.event_name.clone().unwrap_or_default();
+    let key = decode_key(record.s3.object.key.as_deref().unwrap_or_default());
+
+    if bucket.event_bridge_enabled {
+        event_bridge::send_event(&record, &key, api_state.get_event_bridge_client()).await;
+    }
+
+    let Some(triggers) = &bucket.triggers else {
+        return;
+    };
+    trace!("Triggers: {:#?}", triggers);
+
+    let is_notified = |destination: &str| {
+        let is_notified = bucket.is_notified(destination, &event_name, &key);
+        if !is_notified {
+            debug!(
+                "{} for: {} doesn't match the filters of: {}",
+                event_name, key, destination
+            );
+        }
+        is_notified
+    };
+
+    let s3_event = S3Event {
+        records: vec![record],
+    };
+
+    for queue in triggers
+        .get_queues()
+        .iter()
+        .flatten()
+        .filter(|queue| is_notified(queue))
+    {
+        debug!("Detected queue trigger for: {}", queue);
+        handle_queue_trigger(
+            &api_state.get_queue_name(queue),
+            &s3_event,
+            api_state.get_queue_client(),
+        )
+        .await;
+    }
+
+    for lambda in triggers
+        .get_lambdas()
+        .iter()
+        .flatten()
+        .filter(|lambda| is_notified(lambda))
+    {
+        debug!("Detected lambda trigger for: {}", lambda);
+        handle_lambda_trigger(lambda, &s3_event, api_state.get_request_client());
+    }
+}
+
+/// MinIO's records follow the AWS format but name themselves as MinIO. They're given the values
+/// AWS would send so consumers can't tell them apart.
+fn to_aws_record(mut record: S3EventRecord, region: &str) -> S3EventRecord {
+    record.event_version = Some("2.1".to_string());
+    record.event_source = Some("aws:s3".to_string());
+    record.aws_region = Some(region.to_string());
+    // MinIO prefixes event names with `s3:` (i.e. `s3:ObjectCreated:Put`) where AWS doesn't
+    record.event_name = record
+        .event_name
+        .map(|name| name.trim_start_matches("s3:").to_string());
+    record
+        .response_elements
+        .retain(|key, _| key.starts_with("x-amz-"));
+    record.s3.schema_version = Some("1.0".to_string());
+    record.s3.bucket.arn = record
+        .s3
+        .bucket
+        .name
+        .as_ref()
+        .map(|name| format!("arn:aws:s3:::{}", name));
+
+    record
+}
+
+/// Keys in notifications are URL encoded (with spaces as `+`), where key filters match the key
+/// itself
+fn decode_key(key: &str) -> String {
+    percent_decode_str(&key.replace('+', " "))
+        .decode_utf8_lossy()
+        .to_string()
+}
+
+/// Invokes the function with the event through the invoker. S3 invokes functions asynchronously so
+/// the webhook doesn't wait for the function to finish.
+fn handle_lambda_trigger(lambda: &str, s3_event: &S3Event, request_client: &RequestClient) {
+    debug!("Handling lambda trigger: {}", lambda);
+
+    let new_invocation = InvocationBuilder::new()
+        .with_request(EventRequest::S3(s3_event.clone()))
+        .with_lambda_name(lambda.to_string())
+        .build();
+
+    let Ok(invocation) = new_invocation else {
+        error!("Failed to create invocation for lambda: {}", lambda);
+        return;
+    };
+
+    let request_client = request_client.clone();
+    let lambda = lambda.to_string();
+    tokio::task::spawn(async move {
+        let response = request_client
+            .post("http://0.0.0.0:3030/invoke")
+            .json(&serde_json::json!(invocation))
+            .send()
+            .await;
+
+        match response {
+            Ok(response) if response.status().is_success() => {
+                debug!("Successfully invoked lambda: {}", lambda);
+            }
+            Ok(response) => {
+                warn!(
+                    "Lambda: {} failed with status: {}",
+                    lambda,
+                    response.status()
+                );
+            }
+            Err(e) => {
+                error!("Failed to invoke lambda: {}: {}", lambda, e);
+            }
+        }
+    });
+}
+
+/// Sends the event to the queue as the JSON message AWS would send
 async fn handle_queue_trigger(queue: &str, s3_event: &S3Event, client: &Client) {
     debug!("Handling queue trigger: {}", queue);
 
-    // Send message to queue
-    let queue_url = client.get_queue_url().queue_name(queue).send().await;
-
-    match queue_url {
-        Ok(queue_url) => {
-            let queue_url = queue_url.queue_url.unwrap();
-            info!("Queue URL: {}", queue_url);
-
-            let message = format!("{:#?}", s3_event);
-            let send_message = client
-                .send_message()
-                .queue_url(queue_url)
-                .message_body(message)
-                .send()
-                .await;
-
-            match send_message {
-                Ok(_) => {
-                    debug!("Successfully sent message to SQS queue");
-                }
-                Err(e) => {
-                    error!("Failed to send message to SQS queue: {}", e);
+    let message = match serde_json::to_string(s3_event) {
+        Ok(message) => message,
+        Err(e) => {
+            error!("Failed to serialise S3 event: {}", e);
+            return;
+        }
+    };
+
+    // Live notifications don't wait for the queue, so a missing queue doesn't hold up the others
+    send_message(queue, message, client, 1).await;
+}
+
+/// AWS sends a test event to each queue when notifications are configured for a bucket. As buckets
+/// are configured when the environment starts, the test events are sent once the queues exist.
+pub async fn send_test_events(api_state: ApiState) {
+    for bucket in api_state.get_buckets() {
+        let Some(triggers) = &bucket.triggers else {
+            continue;
+        };
+
+        for queue in triggers.get_queues().iter().flatten() {
+            let queue_name = api_state.get_queue_name(queue);
+            debug!(
+                "Sending test event for bucket: {} to queue: {}",
+                bucket.name, queue_name
+            );
+
+            let message = serde_json::json!({
+                "Service": "Amazon S3",
+                "Event": "s3:TestEvent",
+                "Time": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                "Bucket": bucket.name,
+                "RequestId": Uuid::new_v4().simple().to_string().to_uppercase(),
+                "HostId": Uuid::new_v4().to_string(),
+            });
+
+            send_message(
+                &queue_name,
+                message.to_string(),
+                api_state.get_queue_client(),
+                QUEUE_RETRIES,
+            )
+            .await;
+        }
+    }
+}
+
+/// Sends the message to the queue, looking for the queue up to the given number of attempts. Queues
+/// are created by the SQS source, which may still be starting when the environment starts.
+async fn send_message(queue: &str, message: String, client: &Client, attempts: u32) {
+    let mut queue_url = None;
+    for attempt in 1..=attempts {
+        match client.get_queue_url().queue_name(queue).send().await {
+            Ok(output) => {
+                queue_url = output.queue_url;
+                break;
+            }
+            Err(e) => {
+                trace!("Queue: {} not found (attempt {}): {}", queue, attempt, e);
+                if attempt < attempts {
+                    sleep(QUEUE_RETRY_INTERVAL).await;
                 }
             }
         }
+    }
+
+    let Some(queue_url) = queue_url else {
+        error!("Failed to get queue URL for queue: {}", queue);
+        return;
+    };
+    debug!("Queue URL: {}", queue_url);
+
+    let send_message = client
+        .send_message()
+        .queue_url(queue_url)
+        .message_body(message)
+        .send()
+        .await;
+
+    match send_message {
+        Ok(_) => {
+            debug!("Successfully sent message to SQS queue");
+        }
         Err(e) => {
-            error!("Failed to get queue URL: {}", e);
+            error!("Failed to send message to SQS queue: {}", e);
         }
     }
 }
@@ -1,4 +1,7 @@
-use sam_e_types::config::{lambda::Lambda, Config};
+use sam_e_types::config::{
+    lambda::{event::EventSqsProperties, Lambda},
+    Config,
+};
 
 use aws_sdk_sqs::{config::Region, Client as QueueClient};
 
@@ -10,6 +13,8 @@ pub struct QueueState {
     pub lambdas: Vec<Lambda>,
     pub request_client: RequestClient,
     pub queue_client: QueueClient,
+    pub region: String,
+    pub account_id: String,
 }
 
 impl QueueState {
@@ -18,23 +23,31 @@ impl QueueState {
         let request_client = RequestClient::new();
         debug!("Reqwest client created");
 
-        let queue_client = create_sqs_client().await;
+        let runtime = config.get_runtime();
+        let region = runtime.get_region().to_string();
+        let queue_client = create_sqs_client(&region).await;
         debug!("Queue client created");
 
         Self {
             lambdas: config.get_lambdas().to_owned(),
             request_client,
             queue_client,
+            region,
+            account_id: runtime.get_account_id().to_string(),
         }
     }
 
-    pub fn _get_queue_lambdas(&self) -> Vec<&Lambda> {
+    /// Returns each function subscribed to the queue along with the settings of its subscription.
+    /// A function can subscribe to the same queue more than once.
+    pub fn get_queue_subscriptions(&self, queue_name: &str) -> Vec<(&str, &EventSqsProperties)> {
         self.lambdas
             .iter()
-            .filter(|l| {
+            .flat_map(|l| {
                 l.get_events()
-                    .into_iter()
-                    .any(|e| e.get_sqs_properties().is_some())
+                    .iter()
+                    .filter_map(|e| e.get_sqs_properties())
+                    .filter(|sqs_props| sqs_props.get_queue() == queue_name)
+                    .map(|sqs_props| (l.get_name(), sqs_props))
             })
             .collect()
     }
@@ -46,16 +59,28 @@ impl QueueState {
     pub fn get_queue_client(&self) -> &QueueClient {
         &self.queue_client
     }
+
+    pub fn get_region(&self) -> &str {
+        &self.region
+    }
+
+    /// Returns the ARN the queue would have in AWS, using the account and region set in the runtime
+    pub fn get_queue_arn(&self, queue_name: &str) -> String {
+        format!(
+            "arn:aws:sqs:{}:{}:{}",
+            self.region, self.account_id, queue_name
+        )
+    }
 }
 
-async fn create_sqs_client() -> QueueClient {
+async fn create_sqs_client(region: &str) -> QueueClient {
     debug!("Creating AWS SQS client");
-    let region = Region::new("eu-west-1");
+    let region = Region::new(region.to_string());
 
     // let config = aws_config::defaults(BehaviorVersion::v2023_11_09())
     let config = aws_config::from_env()
         .region(region)
-        .endpoint_url("http://sqs-local:9324")
+        .endpoint_url("http://localhost:9324")
         .load()
         .await;
 
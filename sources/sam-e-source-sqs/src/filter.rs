@@ -0,0 +1,132 @@
+use sam_e_types::config::lambda::event::EventSqsProperties;
+
+use aws_lambda_events::sqs::SqsMessage;
+use serde_json::{Map, Value};
+use tracing::{trace, warn};
+
+/// Returns the event's filter patterns as JSON to match messages against
+pub fn get_filter_patterns(sqs_props: &EventSqsProperties) -> Vec<Value> {
+    sqs_props
+        .get_filter_patterns()
+        .iter()
+        .filter_map(|pattern| serde_json::to_value(pattern).ok())
+        .collect()
+}
+
+/// Checks the message against the event's filter patterns as Lambda does, matching when any of the
+/// patterns match. Fields of a JSON body are matched on, otherwise the body is matched as a string.
+pub fn matches_filters(filter_patterns: &[Value], message: &SqsMessage) -> bool {
+    if filter_patterns.is_empty() {
+        return true;
+    }
+
+    let Ok(mut message_value) = serde_json::to_value(message) else {
+        warn!(
+            "Unable to read message: {:?} for filtering",
+            message.message_id
+        );
+        return false;
+    };
+    if let Some(body) = &message.body {
+        if let Ok(body @ Value::Object(_)) = serde_json::from_str::<Value>(body) {
+            message_value["body"] = body;
+        }
+    }
+
+    let is_match = filter_patterns.iter().any(|pattern| {
+        pattern
+            .as_object()
+            .is_some_and(|pattern| matches_pattern(pattern, &message_value))
+    });
+    trace!(
+        "Message: {:?} matches filters: {}",
+        message.message_id,
+        is_match
+    );
+
+    is_match
+}
+
+/// Every field in the pattern has to match, with objects matching the nested fields and arrays
+/// listing the values the field can have
+fn matches_pattern(pattern: &Map<String, Value>, value: &Value) -> bool {
+    pattern.iter().all(|(key, field_pattern)| {
+        let field = value.get(key);
+        match field_pattern {
+            Value::Object(nested_pattern) => {
+                field.is_some_and(|field| matches_pattern(nested_pattern, field))
+            }
+            Value::Array(matchers) => matchers.iter().any(|matcher| match field {
+                None => is_missing_matcher(matcher),
+                Some(Value::Array(values)) => values.iter().any(|v| matches_value(matcher, v)),
+                Some(value) => matches_value(matcher, value),
+            }),
+            _ => {
+                warn!("Invalid filter pattern for field: {}", key);
+                false
+            }
+        }
+    })
+}
+
+/// Only `{"exists": false}` matches a field that isn't there
+fn is_missing_matcher(matcher: &Value) -> bool {
+    matcher.get("exists") == Some(&Value::Bool(false))
+}
+
+fn matches_value(matcher: &Value, value: &Value) -> bool {
+    match matcher {
+        Value::Object(operator) => matches_operator(operator, value),
+        Value::Number(number) => value
+            .as_f64()
+            .is_some_and(|value| Some(value) == number.as_f64()),
+        matcher => matcher == value,
+    }
+}
+
+fn matches_operator(operator: &Map<String, Value>, value: &Value) -> bool {
+    let Some((name, operand)) = operator.iter().next() else {
+        return false;
+    };
+
+    match (name.as_str(), operand) {
+        ("exists", Value::Bool(exists)) => *exists,
+        ("prefix", Value::String(prefix)) => value.as_str().is_some_and(|v| v.starts_with(prefix)),
+        ("suffix", Value::String(suffix)) => value.as_str().is_some_and(|v| v.ends_with(suffix)),
+        ("equals-ignore-case", Value::String(expected)) => value
+            .as_str()
+            .is_some_and(|v| v.to_lowercase() == expected.to_lowercase()),
+        ("anything-but", Value::Array(excluded)) => {
+            !excluded.iter().any(|matcher| matches_value(matcher, value))
+        }
+        ("anything-but", excluded) => !matches_value(excluded, value),
+        ("numeric", Value::Array(conditions)) => value
+            .as_f64()
+            .is_some_and(|value| matches_numeric(conditions, value)),
+        _ => {
+            warn!("Unsupported filter operator: {}", name);
+            false
+        }
+    }
+}
+
+/// Numeric conditions are pairs of a comparison and a number, i.e. `[">", 0, "<=", 5]`
+fn matches_numeric(conditions: &[Value], value: f64) -> bool {
+    conditions.chunks(2).all(|condition| {
+        let [Value::String(comparison), bound] = condition else {
+            return false;
+        };
+        let Some(bound) = bound.as_f64() else {
+            return false;
+        };
+
+        match comparison.as_str() {
+            "=" => value == bound,
+            "<" => value < bound,
+            "<=" => value <= bound,
+            ">" => value > bound,
+            ">=" => value >= bound,
+            _ => false,
+        }
+    })
+}
@@ -1,30 +1,63 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
 
-use aws_lambda_events::sqs::{SqsEvent, SqsMessage};
-use aws_sdk_sqs::Client;
+use aws_lambda_events::{
+    encodings::Base64Data,
+    sqs::{SqsEvent, SqsMessage, SqsMessageAttribute},
+};
+use aws_sdk_sqs::{
+    types::{
+        DeleteMessageBatchRequestEntry, Message, MessageAttributeValue, MessageSystemAttributeName,
+        QueueAttributeName,
+    },
+    Client,
+};
 use sam_e_types::{
     config::{
         infrastructure::{sqs::QueueInfrastructure, Infrastructure},
+        lambda::event::EventSqsProperties,
         Config,
     },
     invocation::{EventRequest, InvocationBuilder},
 };
-use tokio::time::{sleep, Duration};
+use tokio::{
+    sync::Semaphore,
+    time::{sleep, Duration, Instant},
+};
 use tracing::{debug, error, info, trace, warn};
 
-use crate::data::QueueState;
+use crate::{data::QueueState, filter};
+
+/// The most messages SQS returns from a single receive
+const MAX_RECEIVE_MESSAGES: usize = 10;
+
+/// How often an empty queue is checked for messages
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// How many times to check the local queue has started before creating queues on it
+const QUEUE_SERVICE_RETRIES: u32 = 20;
 
 pub async fn listen_to_queues(config: Config, queue_state: QueueState) {
     let config_infrastructure = config.get_infrastructure();
     // let queues = get_queues_from_config(&config);
 
+    wait_for_queue_service(queue_state.get_queue_client()).await;
+
+    let mut queues = vec![];
     for infrastructure in config_infrastructure {
         match infrastructure {
             Infrastructure::Sqs(sqs_data) => {
                 debug!("Found SQS infrastructure");
                 let mut props = sqs_data.properties.clone();
 
-                if !check_queue_exists(&props.name, queue_state.get_queue_client()).await {
+                if let Some(queue_url) =
+                    check_queue_exists(&props.get_queue_name(), queue_state.get_queue_client())
+                        .await
+                {
+                    debug!("Queue exists: {}", props.name);
+                    props.queue_url = Some(queue_url);
+                } else {
                     debug!("Queue doesn't exist, creating: {}", props.name);
                     if let Ok(queue_url) =
                         create_queue(&props, queue_state.get_queue_client()).await
@@ -34,12 +67,9 @@ pub async fn listen_to_queues(config: Config, queue_state: QueueState) {
                     } else {
                         warn!("Failed to create queue: {}", props.name);
                     }
-                } else {
-                    debug!("Queue exists: {}", props.name);
                 }
 
-                debug!("SQS Client to poll queue: {}", props.name);
-                let _ = poll_queue_for_invoke(props, queue_state.clone()).await;
+                queues.push(props);
             }
             _ => {
                 trace!("Skipping infrastructure type");
@@ -48,130 +78,565 @@ pub async fn listen_to_queues(config: Config, queue_state: QueueState) {
         }
     }
 
+    // Dead-letter queues have to exist before they can be set as a target, so redrive policies
+    // are set once every queue has been created
+    for props in queues.iter() {
+        let Some(redrive_policy) = &props.redrive_policy else {
+            continue;
+        };
+        let Some(dead_letter_queue) = queues
+            .iter()
+            .find(|dlq| dlq.name == redrive_policy.dead_letter_queue)
+        else {
+            warn!(
+                "Dead-letter queue {} not found for queue: {}",
+                redrive_policy.dead_letter_queue, props.name
+            );
+            continue;
+        };
+
+        if let Err(e) = set_redrive_policy(
+            props,
+            &queue_state.get_queue_arn(&dead_letter_queue.get_queue_name()),
+            queue_state.get_queue_client(),
+        )
+        .await
+        {
+            error!(
+                "Failed to set redrive policy for queue: {}: {}",
+                props.name, e
+            );
+        }
+    }
+
+    for props in queues {
+        // Each function subscribed to the queue polls it separately, as each event source mapping
+        // does in AWS
+        for (lambda_name, sqs_props) in queue_state.get_queue_subscriptions(&props.name) {
+            debug!(
+                "SQS Client to poll queue: {} for lambda: {}",
+                props.name, lambda_name
+            );
+            poll_queue_for_invoke(
+                props.clone(),
+                lambda_name.to_string(),
+                sqs_props.clone(),
+                queue_state.clone(),
+            );
+        }
+    }
+
     loop {
         trace!("Sleeping for 1 second before staying awake...");
         sleep(Duration::from_secs(1)).await;
     }
 }
 
-pub async fn poll_queue_for_invoke(queue: QueueInfrastructure, queue_state: QueueState) {
-    debug!("Polling queue: {}", queue.name);
+/// Polls the queue for a function, invoking it with batches of messages as set by the event's
+/// batch size and batching window. Batches are sent concurrently up to the event's maximum
+/// concurrency, with messages left on the queue until there is room for another batch.
+pub fn poll_queue_for_invoke(
+    queue: QueueInfrastructure,
+    lambda_name: String,
+    sqs_props: EventSqsProperties,
+    queue_state: QueueState,
+) {
+    debug!("Polling queue: {} for lambda: {}", queue.name, lambda_name);
 
     tokio::task::spawn(async move {
+        let Some(url) = &queue.queue_url else {
+            error!("Queue URL not set for queue: {}", queue.name);
+            return;
+        };
+
         let client = queue_state.get_queue_client().clone();
+        let concurrency = Arc::new(Semaphore::new(sqs_props.get_maximum_concurrency().into()));
+        let in_flight_groups: Arc<Mutex<HashSet<String>>> = Arc::new(Mutex::new(HashSet::new()));
+        let event_source_arn = queue_state.get_queue_arn(&queue.get_queue_name());
+        let filter_patterns = filter::get_filter_patterns(&sqs_props);
         loop {
-            // Only check every half second to avoid lock contention
-            sleep(Duration::from_millis(500)).await;
+            let Ok(permit) = concurrency.clone().acquire_owned().await else {
+                error!(
+                    "Unable to invoke lambda: {} from queue: {}",
+                    lambda_name, queue.name
+                );
+                return;
+            };
+
+            let mut messages = receive_batch(&client, url, &sqs_props).await;
+            if !filter_patterns.is_empty() {
+                // Lambda deletes messages that don't match its filters without invoking
+                let (matched_messages, filtered_messages): (Vec<_>, Vec<_>) =
+                    messages.into_iter().partition(|m| {
+                        let message =
+                            to_sqs_message(m, queue_state.get_region(), &event_source_arn);
+                        filter::matches_filters(&filter_patterns, &message)
+                    });
+                if !filtered_messages.is_empty() {
+                    debug!(
+                        "{} messages don't match the filters for lambda: {}. Deleting",
+                        filtered_messages.len(),
+                        lambda_name
+                    );
+                    delete_messages(&client, url, &filtered_messages.iter().collect::<Vec<_>>())
+                        .await;
+                }
+                messages = matched_messages;
+            }
+            if queue.fifo_queue {
+                messages = hold_in_flight_groups(&client, url, messages, &in_flight_groups).await;
+            }
+            if messages.is_empty() {
+                trace!("No messages found in queue: {}", queue.name);
+                drop(permit);
+                // Only check every half second to avoid lock contention
+                sleep(POLL_INTERVAL).await;
+                continue;
+            }
+
+            let formatted_messages = messages
+                .iter()
+                .map(|m| to_sqs_message(m, queue_state.get_region(), &event_source_arn))
+                .collect::<Vec<SqsMessage>>();
 
-            let Some(url) = &queue.queue_url else {
-                error!("Queue URL not set for queue: {}", queue.name);
+            debug!(
+                "Found {} messages in queue: {} for lambda: {}",
+                formatted_messages.len(),
+                queue.name,
+                lambda_name
+            );
+            trace!("Messages: {:#?}", formatted_messages);
+
+            let new_invocation = InvocationBuilder::new()
+                .with_request(EventRequest::Sqs(SqsEvent {
+                    records: formatted_messages,
+                }))
+                .with_lambda_name(lambda_name.clone())
+                .build();
+
+            let Ok(invocation) = new_invocation else {
+                error!("Failed to create invocation for lambda: {}", lambda_name);
                 continue;
             };
 
-            let receive_message_res = &client
-                .receive_message()
-                .queue_url(url)
-                .max_number_of_messages(10) // TODO: this should be configured
-                .send()
-                .await;
-
-            match receive_message_res {
-                Ok(message_output) => {
-                    let Some(messages) = &message_output.messages else {
-                        trace!("No messages found in queue: {}", queue.name);
-                        continue;
-                    };
-                    let formatted_messages = messages
-                        .iter()
-                        .map(|m| SqsMessage {
-                            message_id: m.message_id.clone(),
-                            receipt_handle: m.receipt_handle.clone(),
-                            body: m.body.clone(),
-                            attributes: HashMap::new(),
-                            md5_of_body: m.md5_of_body.clone(),
-                            event_source: Some(queue.name.clone()),
-                            aws_region: Some("eu-west-2".to_string()),
-                            event_source_arn: None,
-                            md5_of_message_attributes: None,
-                            message_attributes: HashMap::new(),
-                        })
-                        .collect::<Vec<SqsMessage>>();
-
-                    if formatted_messages.len() > 0 {
-                        debug!(
-                            "Found {} messages in queue: {}",
-                            formatted_messages.len(),
-                            queue.name
-                        );
-                        trace!("Messages: {:#?}", formatted_messages);
-
-                        if let Some(triggers) = &queue.triggers {
-                            let lambda_triggers = triggers.get_lambdas();
-
-                            if let Some(lambda_triggers) = lambda_triggers {
-                                debug!("Detected lambda triggers for queue: {:?}", lambda_triggers);
-                                for lambda in lambda_triggers {
-                                    debug!("Adding SQS invocation for container: {}", lambda);
-                                    let new_invocation = InvocationBuilder::new()
-                                        .with_request(EventRequest::Sqs(SqsEvent {
-                                            records: formatted_messages.clone(),
-                                        }))
-                                        .with_lambda_name(lambda.clone())
-                                        .build();
-
-                                    if let Ok(invocation) = new_invocation {
-                                        debug!(
-                                            "Invocation created successfully. Now adding to store"
-                                        );
-                                        let client = queue_state.get_request_client();
-                                        let response = client
-                                            .post("http://0.0.0.0:3030/invoke")
-                                            .json(&serde_json::json!(invocation))
-                                            .send()
-                                            .await;
-
-                                        match response {
-                                            Ok(_) => debug!("Successfully invoked lambda"),
-                                            Err(e) => error!("Failed to invoke lambda: {}", e),
-                                        }
-                                    }
-                                }
-                            }
+            debug!("Invocation created successfully. Now adding to store");
+            let request_client = queue_state.get_request_client().clone();
+            let client = client.clone();
+            let url = url.clone();
+            let report_batch_item_failures = sqs_props.get_report_batch_item_failures();
+            let is_fifo_queue = queue.fifo_queue;
+            let in_flight_groups = in_flight_groups.clone();
+            tokio::task::spawn(async move {
+                let response = request_client
+                    .post("http://0.0.0.0:3030/invoke")
+                    .json(&serde_json::json!(invocation))
+                    .send()
+                    .await;
+
+                // Failed messages are left on the queue to be received again once their
+                // visibility timeout has passed
+                let mut failed_message_ids = match response {
+                    Ok(response) if response.status().is_success() => {
+                        debug!("Successfully invoked lambda");
+                        let response_body = response.text().await.unwrap_or_default();
+                        if report_batch_item_failures {
+                            get_failed_message_ids(&response_body, &messages)
+                        } else {
+                            vec![]
                         }
                     }
+                    Ok(response) => {
+                        warn!(
+                            "Lambda failed with status: {}. Messages will be retried",
+                            response.status()
+                        );
+                        get_message_ids(&messages)
+                    }
+                    Err(e) => {
+                        error!("Failed to invoke lambda: {}", e);
+                        get_message_ids(&messages)
+                    }
+                };
+
+                // Messages in a FIFO queue are processed in order, so anything after a failed
+                // message in the same group has to be retried with it
+                if is_fifo_queue {
+                    failed_message_ids =
+                        get_failed_group_message_ids(&messages, failed_message_ids);
                 }
-                Err(e) => {
-                    debug!("Failed to receive messages: {}", e);
-                    continue;
+
+                let processed_messages = messages
+                    .iter()
+                    .filter(|m| {
+                        m.message_id
+                            .as_ref()
+                            .is_none_or(|id| !failed_message_ids.contains(id))
+                    })
+                    .collect::<Vec<_>>();
+                delete_messages(&client, &url, &processed_messages).await;
+
+                if is_fifo_queue {
+                    let mut in_flight_groups = in_flight_groups.lock().unwrap();
+                    for message_group_id in messages.iter().filter_map(get_message_group_id) {
+                        in_flight_groups.remove(message_group_id);
+                    }
                 }
-            }
+
+                // The next batch can be sent once the function has finished with this one
+                drop(permit);
+            });
         }
     });
 }
 
-/// A function to check if the queue exists. In the event that it isn't it's passed onto another
-/// process in charge of creating the queue. Note: this is only necessary while the queue .conf
-/// file can't be passed as docker volume (within VM setup)
-async fn check_queue_exists(queue_name: &str, client: &Client) -> bool {
+/// Receives up to the batch size from the queue, which can take more than one receive as SQS
+/// returns at most 10 messages at a time. Without a batching window the batch is sent as soon as
+/// the queue has no more messages, otherwise it waits for the window to pass from the first
+/// message (or for the batch to fill).
+async fn receive_batch(
+    client: &Client,
+    queue_url: &str,
+    sqs_props: &EventSqsProperties,
+) -> Vec<Message> {
+    let batch_size = usize::from(sqs_props.get_batch_size());
+    let window = Duration::from_secs(sqs_props.get_maximum_batching_window().into());
+
+    let mut batch: Vec<Message> = vec![];
+    let mut first_received: Option<Instant> = None;
+    while batch.len() < batch_size {
+        let max_messages = (batch_size - batch.len()).min(MAX_RECEIVE_MESSAGES);
+        let receive_message_res = client
+            .receive_message()
+            .queue_url(queue_url)
+            .max_number_of_messages(max_messages as i32)
+            .message_system_attribute_names(MessageSystemAttributeName::All)
+            .message_attribute_names("All")
+            .send()
+            .await;
+
+        let messages = match receive_message_res {
+            Ok(message_output) => message_output.messages.unwrap_or_default(),
+            Err(e) => {
+                debug!("Failed to receive messages: {}", e);
+                break;
+            }
+        };
+
+        let received_none = messages.is_empty();
+        if !received_none {
+            first_received.get_or_insert_with(Instant::now);
+            batch.extend(messages);
+        }
+
+        let Some(first_received) = first_received else {
+            break;
+        };
+
+        let elapsed = first_received.elapsed();
+        if received_none {
+            if elapsed >= window {
+                break;
+            }
+            sleep(POLL_INTERVAL.min(window - elapsed)).await;
+        } else if !window.is_zero() && elapsed >= window {
+            break;
+        }
+    }
+
+    batch
+}
+
+fn to_sqs_message(message: &Message, region: &str, event_source_arn: &str) -> SqsMessage {
+    SqsMessage {
+        message_id: message.message_id.clone(),
+        receipt_handle: message.receipt_handle.clone(),
+        body: message.body.clone(),
+        attributes: message
+            .attributes
+            .iter()
+            .flatten()
+            .map(|(key, value)| (key.as_str().to_string(), value.to_string()))
+            .collect(),
+        md5_of_body: message.md5_of_body.clone(),
+        event_source: Some("aws:sqs".to_string()),
+        aws_region: Some(region.to_string()),
+        event_source_arn: Some(event_source_arn.to_string()),
+        md5_of_message_attributes: message.md5_of_message_attributes.clone(),
+        message_attributes: message
+            .message_attributes
+            .iter()
+            .flatten()
+            .map(|(key, value)| (key.to_string(), to_event_message_attribute(value)))
+            .collect(),
+    }
+}
+
+/// Message attributes are given to the function with their binary values base64 encoded, as
+/// they are in AWS
+fn to_event_message_attribute(attribute: &MessageAttributeValue) -> SqsMessageAttribute {
+    SqsMessageAttribute {
+        string_value: attribute.string_value.clone(),
+        binary_value: attribute
+            .binary_value
+            .as_ref()
+            .map(|value| Base64Data(value.as_ref().to_vec())),
+        string_list_values: attribute.string_list_values.clone().unwrap_or_default(),
+        binary_list_values: attribute
+            .binary_list_values
+            .iter()
+            .flatten()
+            .map(|value| Base64Data(value.as_ref().to_vec()))
+            .collect(),
+        data_type: Some(attribute.data_type.clone()),
+    }
+}
+
+/// FIFO queues only have one batch in flight for each message group at a time. Messages from a
+/// group that's still being processed are made visible again, to be received once it's finished,
+/// and the groups in the new batch are marked as in flight.
+async fn hold_in_flight_groups(
+    client: &Client,
+    queue_url: &str,
+    messages: Vec<Message>,
+    in_flight_groups: &Mutex<HashSet<String>>,
+) -> Vec<Message> {
+    let (held_messages, ready_messages): (Vec<_>, Vec<_>) = {
+        let mut in_flight_groups = in_flight_groups.lock().unwrap();
+        let (held_messages, ready_messages): (Vec<_>, Vec<_>) =
+            messages.into_iter().partition(|m| {
+                get_message_group_id(m).is_some_and(|group| in_flight_groups.contains(group))
+            });
+        for message_group_id in ready_messages.iter().filter_map(get_message_group_id) {
+            in_flight_groups.insert(message_group_id.to_string());
+        }
+        (held_messages, ready_messages)
+    };
+
+    for message in held_messages {
+        debug!(
+            "Message group {:?} already in flight. Returning message to the queue",
+            get_message_group_id(&message)
+        );
+        let change_visibility_res = client
+            .change_message_visibility()
+            .queue_url(queue_url)
+            .set_receipt_handle(message.receipt_handle)
+            .visibility_timeout(0)
+            .send()
+            .await;
+        if let Err(e) = change_visibility_res {
+            error!("Failed to return message to the queue: {}", e);
+        }
+    }
+
+    ready_messages
+}
+
+fn get_message_group_id(message: &Message) -> Option<&String> {
+    message
+        .attributes
+        .as_ref()
+        .and_then(|attributes| attributes.get(&MessageSystemAttributeName::MessageGroupId))
+}
+
+/// Adds every message after a failed message in the same message group to the failed messages
+fn get_failed_group_message_ids(
+    messages: &[Message],
+    failed_message_ids: Vec<String>,
+) -> Vec<String> {
+    let mut failed_groups: HashSet<&String> = HashSet::new();
+    let mut group_failed_message_ids = vec![];
+    for message in messages {
+        let Some(message_id) = &message.message_id else {
+            continue;
+        };
+        let message_group_id = get_message_group_id(message);
+
+        let group_failed = message_group_id.is_some_and(|group| failed_groups.contains(group));
+        if group_failed || failed_message_ids.contains(message_id) {
+            if let Some(message_group_id) = message_group_id {
+                failed_groups.insert(message_group_id);
+            }
+            group_failed_message_ids.push(message_id.to_string());
+        }
+    }
+
+    group_failed_message_ids
+}
+
+/// Works out which messages in the batch the function failed to process from its batch item
+/// failures. As in AWS, an empty or null response means the whole batch succeeded, while a
+/// response that can't be understood (or names a message that isn't in the batch) fails it all.
+fn get_failed_message_ids(response_body: &str, messages: &[Message]) -> Vec<String> {
+    if response_body.trim().is_empty() {
+        return vec![];
+    }
+
+    let Ok(response) = serde_json::from_str::<serde_json::Value>(response_body) else {
+        warn!("Lambda response is not valid JSON. Retrying the whole batch");
+        return get_message_ids(messages);
+    };
+
+    let failures = match response.get("batchItemFailures") {
+        None | Some(serde_json::Value::Null) => return vec![],
+        Some(serde_json::Value::Array(failures)) => failures,
+        Some(_) => {
+            warn!("batchItemFailures in lambda response is not a list. Retrying the whole batch");
+            return get_message_ids(messages);
+        }
+    };
+
+    let mut failed_message_ids = vec![];
+    for failure in failures {
+        let Some(message_id) = failure
+            .get("itemIdentifier")
+            .and_then(|id| id.as_str())
+            .filter(|id| {
+                messages
+                    .iter()
+                    .any(|m| m.message_id.as_deref() == Some(*id))
+            })
+        else {
+            warn!(
+                "Batch item failure doesn't match a message in the batch: {}. Retrying the whole batch",
+                failure
+            );
+            return get_message_ids(messages);
+        };
+        failed_message_ids.push(message_id.to_string());
+    }
+
+    debug!("Lambda reported failed messages: {:?}", failed_message_ids);
+    failed_message_ids
+}
+
+fn get_message_ids(messages: &[Message]) -> Vec<String> {
+    messages
+        .iter()
+        .filter_map(|m| m.message_id.clone())
+        .collect()
+}
+
+/// Deletes the messages from the queue, in batches of up to 10 as SQS allows
+async fn delete_messages(client: &Client, queue_url: &str, messages: &[&Message]) {
+    for chunk in messages.chunks(MAX_RECEIVE_MESSAGES) {
+        let entries = chunk
+            .iter()
+            .enumerate()
+            .filter_map(|(index, m)| {
+                DeleteMessageBatchRequestEntry::builder()
+                    .id(index.to_string())
+                    .set_receipt_handle(m.receipt_handle.clone())
+                    .build()
+                    .ok()
+            })
+            .collect::<Vec<_>>();
+
+        let delete_res = client
+            .delete_message_batch()
+            .queue_url(queue_url)
+            .set_entries(Some(entries))
+            .send()
+            .await;
+
+        match delete_res {
+            Ok(output) if output.failed.is_empty() => {
+                debug!("Deleted {} messages from queue", chunk.len())
+            }
+            Ok(output) => error!("Failed to delete messages: {:?}", output.failed),
+            Err(e) => error!("Failed to delete messages: {}", e),
+        }
+    }
+}
+
+/// The local queue is started alongside the sources so may not be ready to take requests yet. Waits
+/// until it responds, giving up after a number of retries to let the requests themselves fail.
+async fn wait_for_queue_service(client: &Client) {
+    for attempt in 1..=QUEUE_SERVICE_RETRIES {
+        match client.list_queues().send().await {
+            Ok(_) => {
+                debug!("Local queue is ready");
+                return;
+            }
+            Err(e) => {
+                trace!("Local queue not ready (attempt {}): {}", attempt, e);
+                sleep(POLL_INTERVAL).await;
+            }
+        }
+    }
+
+    warn!("Local queue didn't respond. Continuing to create queues regardless");
+}
+
+/// A function to check if the queue exists, returning its URL if so. In the event that it isn't
+/// it's passed onto another process in charge of creating the queue.
+async fn check_queue_exists(queue_name: &str, client: &Client) -> Option<String> {
     debug!("Checking if queue exists: {}", queue_name);
 
     let check_queue = client.get_queue_url().queue_name(queue_name).send().await;
     debug!("Check queue result: {:?}", check_queue);
 
     match check_queue {
-        Ok(_) => true,
+        Ok(queue_url_output) => queue_url_output.queue_url,
         Err(_e) => {
             warn!("Queue not found. Will create before polling");
-            false
+            None
         }
     }
 }
 
+/// Sets the queue's redrive policy so messages move to its dead-letter queue once they have been
+/// received more than the max receive count. Also updates queues that already existed.
+async fn set_redrive_policy(
+    queue: &QueueInfrastructure,
+    dead_letter_target_arn: &str,
+    client: &Client,
+) -> anyhow::Result<()> {
+    let (Some(redrive_policy), Some(queue_url)) = (&queue.redrive_policy, &queue.queue_url) else {
+        return Ok(());
+    };
+    debug!(
+        "Setting dead-letter queue for {} to: {}",
+        queue.name, dead_letter_target_arn
+    );
+
+    let policy = serde_json::json!({
+        "deadLetterTargetArn": dead_letter_target_arn,
+        "maxReceiveCount": redrive_policy.max_receive_count,
+    });
+
+    client
+        .set_queue_attributes()
+        .queue_url(queue_url)
+        .attributes(QueueAttributeName::RedrivePolicy, policy.to_string())
+        .send()
+        .await?;
+
+    Ok(())
+}
+
 async fn create_queue(queue: &QueueInfrastructure, client: &Client) -> anyhow::Result<String> {
-    let queue_name = &queue.name;
+    let queue_name = queue.get_queue_name();
     debug!("Creating queue: {}", queue_name);
 
-    let created_queue = client.create_queue().queue_name(queue_name).send().await;
+    let mut create_queue = client.create_queue().queue_name(&queue_name);
+    if queue.fifo_queue {
+        debug!("Creating as a FIFO queue");
+        create_queue = create_queue
+            .attributes(QueueAttributeName::FifoQueue, "true")
+            .attributes(
+                QueueAttributeName::ContentBasedDeduplication,
+                queue.content_based_deduplication.to_string(),
+            );
+    }
+    for (name, value) in queue.get_attributes() {
+        trace!("Setting queue attribute {} to: {}", name, value);
+        create_queue = create_queue.attributes(QueueAttributeName::from(name), value.to_string());
+    }
+    for (key, value) in &queue.tags {
+        create_queue = create_queue.tags(key, value);
+    }
+    let created_queue = create_queue.send().await;
 
     info!("Queue created: {:?}", created_queue);
 
@@ -180,8 +645,7 @@ async fn create_queue(queue: &QueueInfrastructure, client: &Client) -> anyhow::R
         Err(e) => error!("Failed to create queue: {}", e),
     }
 
-    // Need to wait at least 1 second after queue has been created before using
-    sleep(Duration::from_secs(1)).await;
-
-    Ok(created_queue.unwrap().queue_url.unwrap())
+    created_queue?
+        .queue_url
+        .ok_or_else(|| anyhow::anyhow!("No queue URL returned for queue: {}", queue_name))
 }
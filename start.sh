@@ -1,5 +1,8 @@
 #!/bin/bash
 
+# Start the local queue first so the sources can create their queues on it
+/app/release/sam-e-queue &
+
 # Start the lambda runtime custom invoker
 /app/release/sam-e-invoker &
 